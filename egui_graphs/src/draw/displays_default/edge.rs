@@ -23,6 +23,8 @@ pub struct DefaultEdgeShape {
     pub loop_size: f32,
     pub label_text: String,
     pub color: Option<Color32>,
+    /// Whether to draw the tip, `None` follows the directedness of the graph.
+    pub directed: Option<bool>,
 }
 
 impl<E: Clone> From<EdgeProps<E>> for DefaultEdgeShape {
@@ -38,6 +40,7 @@ impl<E: Clone> From<EdgeProps<E>> for DefaultEdgeShape {
             curve_size: 20.,
             loop_size: 3.,
             color: None,
+            directed: None,
         }
     }
 }
@@ -74,7 +77,8 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, E, Ty, I
     ) -> Vec<egui::Shape> {
         let mut res = vec![];
 
-        let label_visible = ctx.style.labels_always || self.selected;
+        let label_visible =
+            !ctx.style.edge_labels_hidden && (ctx.style.labels_always || self.selected);
 
         let style = if self.selected {
             ctx.ctx.style().visuals.widgets.active
@@ -122,8 +126,14 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, E, Ty, I
         }
 
         let dir = (end.location() - start.location()).normalized();
-        let start_connector_point = start.display().closest_boundary_point(dir);
-        let end_connector_point = end.display().closest_boundary_point(-dir);
+        let (start_connector_point, end_connector_point) = if ctx.style.edges_to_center {
+            (start.location(), end.location())
+        } else {
+            (
+                start.display().closest_boundary_point(dir),
+                end.display().closest_boundary_point(-dir),
+            )
+        };
 
         if self.order == 0 {
             // draw straight edge
@@ -136,7 +146,7 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, E, Ty, I
                 size: self.tip_size,
                 angle: self.tip_angle,
             };
-            if ctx.is_directed {
+            if self.directed.unwrap_or(ctx.is_directed) {
                 builder = builder.with_tip(&tip_props);
             };
             let straight_shapes = builder.build();
@@ -180,7 +190,7 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, E, Ty, I
             size: self.tip_size,
             angle: self.tip_angle,
         };
-        if ctx.is_directed {
+        if self.directed.unwrap_or(ctx.is_directed) {
             builder = builder.with_tip(&tip_props);
         };
         let curved_shapes = builder.build();
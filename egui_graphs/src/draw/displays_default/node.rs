@@ -5,7 +5,7 @@ use egui::{
 use petgraph::{stable_graph::IndexType, EdgeType};
 use serde::{Deserialize, Serialize};
 
-use crate::{draw::drawer::DrawContext, DisplayNode, NodeProps};
+use crate::{draw::drawer::DrawContext, DisplayNode, LabelPosition, NodeProps};
 
 /// This is the default node shape which is used to display nodes in the graph.
 ///
@@ -89,11 +89,23 @@ impl<N: Clone, E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<N, E, Ty, Ix>
             )
         });
 
-        // display label centered over the circle
-        let label_pos = Pos2::new(
-            circle_center.x - galley.size().x / 2.,
-            circle_center.y - circle_radius * 2.,
-        );
+        let size = galley.size();
+        let label_pos = match ctx.style.node_label_position {
+            // display label centered over the circle
+            LabelPosition::Above => Pos2::new(
+                circle_center.x - size.x / 2.,
+                circle_center.y - circle_radius * 2.,
+            ),
+            LabelPosition::Inside => circle_center - size / 2.,
+            LabelPosition::Below => Pos2::new(
+                circle_center.x - size.x / 2.,
+                circle_center.y + circle_radius * 1.5,
+            ),
+            LabelPosition::Right => Pos2::new(
+                circle_center.x + circle_radius * 1.5,
+                circle_center.y - size.y / 2.,
+            ),
+        };
 
         let label_shape = TextShape::new(label_pos, galley, color);
         res.push(label_shape.into());
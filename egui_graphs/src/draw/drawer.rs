@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use egui::{Context, Painter, Shape};
+use egui::{Context, Painter, Pos2, Shape, Stroke};
 use petgraph::{graph::IndexType, EdgeType};
 
 use crate::{
@@ -11,6 +11,11 @@ use crate::{
 
 use super::{DisplayEdge, DisplayNode};
 
+/// Every n-th grid line is drawn as a major line.
+const GRID_MAJOR_EVERY: i64 = 5;
+/// Grid is not drawn if minor lines would be closer than this on screen.
+const GRID_MIN_SCREEN_SPACING: f32 = 4.;
+
 /// Contains all the data about current widget state which is needed for custom drawing functions.
 pub struct DrawContext<'a> {
     pub ctx: &'a Context,
@@ -59,11 +64,69 @@ where
     }
 
     pub fn draw(mut self) {
+        self.draw_background();
+        self.draw_grid();
         self.draw_edges();
         self.draw_nodes();
         self.draw_postponed();
     }
 
+    fn draw_background(&self) {
+        if let Some(color) = self.ctx.style.background {
+            self.ctx
+                .painter
+                .rect_filled(self.ctx.painter.clip_rect(), 0., color);
+        }
+    }
+
+    fn draw_grid(&self) {
+        let Some(spacing) = self.ctx.style.grid_spacing else {
+            return;
+        };
+        if self.ctx.meta.canvas_to_screen_size(spacing) < GRID_MIN_SCREEN_SPACING {
+            return;
+        }
+
+        let rect = self.ctx.painter.clip_rect();
+        let style = self.ctx.ctx.style();
+        let color = style.visuals.widgets.noninteractive.bg_stroke.color;
+        let minor = Stroke::new(1., color.gamma_multiply(0.4));
+        let major = Stroke::new(1., color);
+        let stroke = |i: i64| {
+            if i % GRID_MAJOR_EVERY == 0 {
+                major
+            } else {
+                minor
+            }
+        };
+
+        let min = self.ctx.meta.screen_to_canvas_pos(rect.min);
+        let max = self.ctx.meta.screen_to_canvas_pos(rect.max);
+
+        for i in (min.x / spacing).floor() as i64..=(max.x / spacing).ceil() as i64 {
+            let x = self
+                .ctx
+                .meta
+                .canvas_to_screen_pos(Pos2::new(i as f32 * spacing, 0.))
+                .x;
+            self.ctx.painter.line_segment(
+                [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                stroke(i),
+            );
+        }
+        for i in (min.y / spacing).floor() as i64..=(max.y / spacing).ceil() as i64 {
+            let y = self
+                .ctx
+                .meta
+                .canvas_to_screen_pos(Pos2::new(0., i as f32 * spacing))
+                .y;
+            self.ctx.painter.line_segment(
+                [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+                stroke(i),
+            );
+        }
+    }
+
     fn draw_postponed(&mut self) {
         self.delayed.iter().for_each(|s| {
             self.ctx.painter.add(s.clone());
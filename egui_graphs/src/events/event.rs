@@ -69,6 +69,12 @@ pub struct PayloadEdgeDeselect {
     pub id: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PayloadEdgeCreateRequest {
+    pub source: usize,
+    pub target: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Event {
     Pan(PayloadPan),
@@ -84,4 +90,7 @@ pub enum Event {
     EdgeDoubleClick(PayloadEdgeDoubleClick),
     EdgeSelect(PayloadEdgeSelect),
     EdgeDeselect(PayloadEdgeDeselect),
+    /// An edge was dragged out from the source node and dropped onto the target node.
+    /// The graph is left unchanged, it's up to the client to add the edge.
+    EdgeCreateRequest(PayloadEdgeCreateRequest),
 }
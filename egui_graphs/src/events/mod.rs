@@ -1,7 +1,8 @@
 mod event;
 
 pub use event::{
-    Event, PayloadEdgeClick, PayloadEdgeDeselect, PayloadEdgeDoubleClick, PayloadEdgeSelect,
-    PayloadNodeClick, PayloadNodeDeselect, PayloadNodeDoubleClick, PayloadNodeDragEnd,
-    PayloadNodeDragStart, PayloadNodeMove, PayloadNodeSelect, PayloadPan, PayloadZoom,
+    Event, PayloadEdgeClick, PayloadEdgeCreateRequest, PayloadEdgeDeselect, PayloadEdgeDoubleClick,
+    PayloadEdgeSelect, PayloadNodeClick, PayloadNodeDeselect, PayloadNodeDoubleClick,
+    PayloadNodeDragEnd, PayloadNodeDragStart, PayloadNodeMove, PayloadNodeSelect, PayloadPan,
+    PayloadZoom,
 };
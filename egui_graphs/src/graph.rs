@@ -1,4 +1,4 @@
-use egui::Pos2;
+use egui::{Pos2, Rect};
 use petgraph::{stable_graph::DefaultIx, Directed};
 
 use petgraph::{
@@ -40,6 +40,12 @@ pub struct Graph<
     selected_nodes: Vec<NodeIndex<Ix>>,
     selected_edges: Vec<EdgeIndex<Ix>>,
     dragged_node: Option<NodeIndex<Ix>>,
+    /// Node an edge is being dragged out from
+    #[serde(skip)]
+    edge_source: Option<NodeIndex<Ix>>,
+    /// Corner the selection rectangle is being dragged from, in canvas coordinates
+    #[serde(skip)]
+    selection_start: Option<Pos2>,
 }
 
 impl<N, E, Ty, Ix, Dn, De> From<&StableGraph<N, E, Ty, Ix>> for Graph<N, E, Ty, Ix, Dn, De>
@@ -71,6 +77,8 @@ where
             selected_nodes: Vec::default(),
             selected_edges: Vec::default(),
             dragged_node: Option::default(),
+            edge_source: Option::default(),
+            selection_start: Option::default(),
         }
     }
 
@@ -86,6 +94,22 @@ where
         None
     }
 
+    /// Finds nodes whose shapes intersect the rectangle given in screen coordinates.
+    pub fn nodes_in_screen_rect(&self, meta: &Metadata, screen_rect: Rect) -> Vec<NodeIndex<Ix>> {
+        let rect = Rect::from_two_pos(
+            meta.screen_to_canvas_pos(screen_rect.min),
+            meta.screen_to_canvas_pos(screen_rect.max),
+        );
+        self.nodes_iter()
+            .filter(|(_, node)| {
+                // The point of the rectangle closest to the node center lies inside of convex
+                // shapes intersecting it, shapes may refuse it e.g. for nodes which are hidden
+                node.display().is_inside(rect.clamp(node.location()))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
     /// Finds edge by position.
     #[allow(clippy::missing_panics_doc)] // TODO: add panics doc
     pub fn edge_by_screen_pos(&self, meta: &Metadata, screen_pos: Pos2) -> Option<EdgeIndex<Ix>> {
@@ -344,6 +368,22 @@ where
         self.dragged_node = node;
     }
 
+    pub fn edge_source(&self) -> Option<NodeIndex<Ix>> {
+        self.edge_source
+    }
+
+    pub fn set_edge_source(&mut self, node: Option<NodeIndex<Ix>>) {
+        self.edge_source = node;
+    }
+
+    pub fn selection_start(&self) -> Option<Pos2> {
+        self.selection_start
+    }
+
+    pub fn set_selection_start(&mut self, pos: Option<Pos2>) {
+        self.selection_start = pos;
+    }
+
     pub fn edge_count(&self) -> usize {
         self.g.edge_count()
     }
@@ -8,7 +8,7 @@ use crate::{
     DisplayEdge, DisplayNode, Graph,
 };
 
-use egui::{PointerButton, Pos2, Rect, Response, Sense, Ui, Vec2, Widget};
+use egui::{PointerButton, Pos2, Rect, Response, Sense, Shape, Ui, Vec2, Widget};
 
 use petgraph::{
     graph::{EdgeIndex, IndexType},
@@ -32,9 +32,10 @@ pub type DefaultGraphView<'a> = GraphView<
 
 #[cfg(feature = "events")]
 use crate::events::{
-    Event, PayloadEdgeClick, PayloadEdgeDeselect, PayloadEdgeDoubleClick, PayloadEdgeSelect,
-    PayloadNodeClick, PayloadNodeDeselect, PayloadNodeDoubleClick, PayloadNodeDragEnd,
-    PayloadNodeDragStart, PayloadNodeMove, PayloadNodeSelect, PayloadPan, PayloadZoom,
+    Event, PayloadEdgeClick, PayloadEdgeCreateRequest, PayloadEdgeDeselect, PayloadEdgeDoubleClick,
+    PayloadEdgeSelect, PayloadNodeClick, PayloadNodeDeselect, PayloadNodeDoubleClick,
+    PayloadNodeDragEnd, PayloadNodeDragStart, PayloadNodeMove, PayloadNodeSelect, PayloadPan,
+    PayloadZoom,
 };
 #[cfg(feature = "events")]
 use crossbeam::channel::Sender;
@@ -107,7 +108,9 @@ where
 
         let (resp, p) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
         self.handle_fit_to_screen(&resp, &mut meta);
+        self.handle_rect_selection(ui, &resp, &meta);
         self.handle_navigation(ui, &resp, &mut meta);
+        self.handle_edge_creation(ui, &resp, &meta);
         self.handle_node_drag(&resp, &mut meta);
         self.handle_click(&resp, &mut meta);
 
@@ -122,6 +125,8 @@ where
             },
         )
         .draw();
+        self.draw_edge_preview(ui, &resp, &p, &meta);
+        self.draw_selection_rect(ui, &p, &meta);
 
         meta.first_frame = false;
         meta.save(ui, self.id);
@@ -394,8 +399,112 @@ where
         self.select_edge(idx);
     }
 
+    /// Starts dragging out an edge on shift-drags from a node and requests the edge once it's
+    /// dropped onto another node.
+    fn handle_edge_creation(&mut self, ui: &Ui, resp: &Response, meta: &Metadata) {
+        if !self.settings_interaction.edge_creation_enabled {
+            self.g.set_edge_source(None);
+            return;
+        }
+
+        if resp.drag_started_by(PointerButton::Primary) && ui.input(|i| i.modifiers.shift) {
+            let source = resp
+                .hover_pos()
+                .and_then(|pos| self.g.node_by_screen_pos(meta, pos));
+            self.g.set_edge_source(source);
+        }
+
+        let Some(source) = self.g.edge_source() else {
+            return;
+        };
+        if !resp.drag_stopped() {
+            return;
+        }
+        self.g.set_edge_source(None);
+        let target = ui
+            .input(|i| i.pointer.interact_pos())
+            .and_then(|pos| self.g.node_by_screen_pos(meta, pos));
+        if let Some(target) = target.filter(|t| *t != source) {
+            self.request_edge(source, target);
+        }
+    }
+
+    /// Draws a line from the node an edge is dragged out from to the pointer.
+    fn draw_edge_preview(&self, ui: &Ui, resp: &Response, p: &egui::Painter, meta: &Metadata) {
+        let Some(node) = self.g.edge_source().and_then(|idx| self.g.node(idx)) else {
+            return;
+        };
+        let Some(pointer) = resp.hover_pos().or(ui.input(|i| i.pointer.interact_pos())) else {
+            return;
+        };
+        let start = meta.canvas_to_screen_pos(node.location());
+        p.add(Shape::dashed_line(
+            &[start, pointer],
+            ui.visuals().selection.stroke,
+            6.,
+            4.,
+        ));
+    }
+
+    /// Starts dragging out a selection rectangle on drags from empty space and selects the nodes
+    /// it touches once released.
+    fn handle_rect_selection(&mut self, ui: &Ui, resp: &Response, meta: &Metadata) {
+        if !self.settings_interaction.rect_selection_enabled
+            || !self.settings_interaction.node_selection_multi_enabled
+        {
+            self.g.set_selection_start(None);
+            return;
+        }
+
+        if resp.drag_started_by(PointerButton::Primary) {
+            // The drag only starts once the pointer moved a bit, so look at where it was pressed
+            let origin = ui
+                .input(|i| i.pointer.press_origin())
+                .filter(|pos| self.g.node_by_screen_pos(meta, *pos).is_none());
+            self.g
+                .set_selection_start(origin.map(|pos| meta.screen_to_canvas_pos(pos)));
+        }
+
+        let Some(rect) = self.selection_rect(ui, meta) else {
+            return;
+        };
+        if !resp.drag_stopped() {
+            return;
+        }
+        self.g.set_selection_start(None);
+        if !ui.input(|i| i.modifiers.shift) {
+            self.deselect_all();
+        }
+        for idx in self.g.nodes_in_screen_rect(meta, rect) {
+            if !self.g.node(idx).unwrap().selected() {
+                self.select_node(idx);
+            }
+        }
+    }
+
+    /// Selection rectangle being dragged out in screen coordinates.
+    fn selection_rect(&self, ui: &Ui, meta: &Metadata) -> Option<Rect> {
+        let start = self.g.selection_start()?;
+        let pointer = ui.input(|i| i.pointer.interact_pos())?;
+        Some(Rect::from_two_pos(
+            meta.canvas_to_screen_pos(start),
+            pointer,
+        ))
+    }
+
+    fn draw_selection_rect(&self, ui: &Ui, p: &egui::Painter, meta: &Metadata) {
+        let Some(rect) = self.selection_rect(ui, meta) else {
+            return;
+        };
+        let stroke = ui.visuals().selection.stroke;
+        p.rect(rect, 0., stroke.color.gamma_multiply(0.1), stroke);
+    }
+
     fn handle_node_drag(&mut self, resp: &Response, meta: &mut Metadata) {
-        if !self.settings_interaction.dragging_enabled {
+        if !self.settings_interaction.dragging_enabled
+            || self.g.edge_source().is_some()
+            || self.g.selection_start().is_some()
+        {
             return;
         }
 
@@ -520,6 +629,8 @@ where
 
         if (resp.dragged_by(PointerButton::Middle) || resp.dragged_by(PointerButton::Primary))
             && self.g.dragged_node().is_none()
+            && self.g.edge_source().is_none()
+            && self.g.selection_start().is_none()
             && (resp.drag_delta().x.abs() > 0. || resp.drag_delta().y.abs() > 0.)
         {
             let new_pan = meta.pan + resp.drag_delta();
@@ -634,6 +745,15 @@ where
         }));
     }
 
+    #[allow(unused_variables, clippy::unused_self)]
+    fn request_edge(&self, source: NodeIndex<Ix>, target: NodeIndex<Ix>) {
+        #[cfg(feature = "events")]
+        self.publish_event(Event::EdgeCreateRequest(PayloadEdgeCreateRequest {
+            source: source.index(),
+            target: target.index(),
+        }));
+    }
+
     fn set_drag_start(&mut self, idx: NodeIndex<Ix>) {
         let n = self.g.node_mut(idx).unwrap();
         n.set_dragged(true);
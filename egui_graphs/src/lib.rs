@@ -20,7 +20,9 @@ pub use layouts::{
     random::{Random as LayoutRandom, State as LayoutStateRandom},
 };
 pub use metadata::Metadata;
-pub use settings::{SettingsInteraction, SettingsNavigation, SettingsStyle};
+pub use settings::{
+    AgeFade, LabelPosition, SettingsInteraction, SettingsNavigation, SettingsStyle,
+};
 
 #[cfg(feature = "events")]
 pub mod events;
@@ -1,3 +1,7 @@
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
 /// Represents graph interaction settings.
 #[derive(Debug, Clone, Default)]
 pub struct SettingsInteraction {
@@ -8,6 +12,8 @@ pub struct SettingsInteraction {
     pub(crate) edge_clicking_enabled: bool,
     pub(crate) edge_selection_enabled: bool,
     pub(crate) edge_selection_multi_enabled: bool,
+    pub(crate) edge_creation_enabled: bool,
+    pub(crate) rect_selection_enabled: bool,
 }
 
 impl SettingsInteraction {
@@ -79,6 +85,28 @@ impl SettingsInteraction {
         self.edge_selection_multi_enabled = enabled;
         self
     }
+
+    /// Drag from a node to another one while holding shift to request an edge between them.
+    ///
+    /// The request is reported as [`crate::events::Event::EdgeCreateRequest`], the graph itself
+    /// is not modified.
+    ///
+    /// Default: `false`
+    pub fn with_edge_creation_enabled(mut self, enabled: bool) -> Self {
+        self.edge_creation_enabled = enabled;
+        self
+    }
+
+    /// Drag a rectangle from empty space to select all nodes it touches, replacing the current
+    /// selection unless shift is held. Requires node multiselection.
+    ///
+    /// Dragging the empty canvas with the primary button no longer pans then.
+    ///
+    /// Default: `false`
+    pub fn with_rect_selection_enabled(mut self, enabled: bool) -> Self {
+        self.rect_selection_enabled = enabled;
+        self
+    }
 }
 
 /// Represents graph navigation settings.
@@ -140,10 +168,53 @@ impl SettingsNavigation {
     }
 }
 
+/// Placement of node labels relative to the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LabelPosition {
+    #[default]
+    Above,
+    Inside,
+    Below,
+    Right,
+}
+
+/// Fading of elements by their age, for custom shapes keeping track of when their element was last
+/// changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgeFade {
+    /// Elements younger than this are fully opaque.
+    pub threshold: Duration,
+    /// Time over which the opacity decreases linearly after the threshold.
+    pub span: Duration,
+    /// Opacity of elements older than threshold plus span.
+    pub min_opacity: f32,
+}
+
+impl AgeFade {
+    /// Opacity of an element of the given age.
+    pub fn opacity(&self, age: Duration) -> f32 {
+        let Some(over) = age.checked_sub(self.threshold) else {
+            return 1.0;
+        };
+        if self.span.is_zero() {
+            return self.min_opacity;
+        }
+        let t = (over.as_secs_f32() / self.span.as_secs_f32()).min(1.0);
+        1.0 - t * (1.0 - self.min_opacity)
+    }
+}
+
 /// `SettingsStyle` stores settings for the style of the graph.
 #[derive(Debug, Clone, Default)]
 pub struct SettingsStyle {
     pub(crate) labels_always: bool,
+    pub(crate) grid_spacing: Option<f32>,
+    pub(crate) edge_labels_hidden: bool,
+    pub(crate) node_label_position: LabelPosition,
+    pub(crate) edges_to_center: bool,
+    pub(crate) background: Option<Color32>,
+    pub(crate) edge_label_background: bool,
+    pub(crate) age_fade: Option<AgeFade>,
 }
 
 impl SettingsStyle {
@@ -163,4 +234,81 @@ impl SettingsStyle {
         self.labels_always = always;
         self
     }
+
+    /// Whether labels are shown always, see [`SettingsStyle::with_labels_always`].
+    pub fn labels_always(&self) -> bool {
+        self.labels_always
+    }
+
+    /// Draws a background grid with the given spacing between minor lines in canvas coordinates.
+    /// Every fifth line is drawn as a major line. `None` disables the grid.
+    ///
+    /// Default is `None`.
+    pub fn with_grid(mut self, spacing: Option<f32>) -> Self {
+        self.grid_spacing = spacing;
+        self
+    }
+
+    /// Whether to hide edge labels even when they would be shown otherwise.
+    ///
+    /// Default is false.
+    pub fn with_edge_labels_hidden(mut self, hidden: bool) -> Self {
+        self.edge_labels_hidden = hidden;
+        self
+    }
+
+    /// Where to place node labels.
+    ///
+    /// Default is [`LabelPosition::Above`].
+    pub fn with_node_label_position(mut self, position: LabelPosition) -> Self {
+        self.node_label_position = position;
+        self
+    }
+
+    /// Whether edges end at node centers instead of node boundaries.
+    ///
+    /// Default is false.
+    pub fn with_edges_to_center(mut self, center: bool) -> Self {
+        self.edges_to_center = center;
+        self
+    }
+
+    /// Fills the canvas with the color. `None` leaves the background of the parent ui.
+    ///
+    /// Default is `None`.
+    pub fn with_background(mut self, color: Option<Color32>) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Whether custom edge shapes should draw a backdrop behind edge labels.
+    ///
+    /// Default is false.
+    pub fn with_edge_label_background(mut self, backdrop: bool) -> Self {
+        self.edge_label_background = backdrop;
+        self
+    }
+
+    /// Whether edge labels should get a backdrop, see [`SettingsStyle::with_edge_label_background`].
+    pub fn edge_label_background(&self) -> bool {
+        self.edge_label_background
+    }
+
+    /// Fades elements by their age, see [`AgeFade`]. `None` disables fading.
+    ///
+    /// Default is `None`.
+    pub fn with_age_fade(mut self, fade: Option<AgeFade>) -> Self {
+        self.age_fade = fade;
+        self
+    }
+
+    /// Fading of elements by their age if enabled.
+    pub fn age_fade(&self) -> Option<AgeFade> {
+        self.age_fade
+    }
+
+    /// Background color of the canvas if set, useful for custom shapes keeping their contrast.
+    pub fn background(&self) -> Option<Color32> {
+        self.background
+    }
 }
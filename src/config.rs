@@ -0,0 +1,158 @@
+// This module contains the config file of the app, a TOML file in the platform config directory
+// which is read at startup and written by the settings window. A missing file means the defaults,
+// and command line arguments take precedence over the file.
+
+use anyhow::{anyhow, Result};
+use egui::ThemePreference;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+#[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    // Follow the theme of the system
+    System,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::System];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::System => "System",
+        }
+    }
+
+    pub fn preference(self) -> ThemePreference {
+        match self {
+            Theme::Dark => ThemePreference::Dark,
+            Theme::Light => ThemePreference::Light,
+            Theme::System => ThemePreference::System,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Address of the neovim server to open files in, files are opened by default apps without it
+    pub nvim_srv: Option<String>,
+    // Extensions of files opened in neovim
+    pub nvim_ext: Vec<String>,
+    pub theme: Theme,
+    // Interval between saving pinboards with unsaved changes in place, zero disables autosave
+    pub autosave_secs: u64,
+    // Directory file dialogs start in, the working directory if unset
+    pub dialog_dir: Option<PathBuf>,
+    // Directories searched for missing files of every pinboard
+    pub repair_roots: Vec<PathBuf>,
+    // Shortcuts replacing the defaults by command name, e.g. `fit = "Ctrl+F"`. Names are checked
+    // when building the keymap, so that a typo doesn't make the whole file fail to load.
+    pub keymap: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            nvim_srv: None,
+            nvim_ext: vec!["md".into(), "markdown".into(), "typ".into()],
+            theme: Theme::default(),
+            autosave_secs: 0,
+            dialog_dir: None,
+            repair_roots: Vec::new(),
+            keymap: HashMap::new(),
+        }
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("pinlab").join("config.toml"))
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let path = path().ok_or(anyhow!("cannot determine the config directory"))?;
+        if !path.try_exists()? {
+            return Ok(Self::default());
+        }
+        Self::parse(&std::fs::read_to_string(&path)?)
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = path().ok_or(anyhow!("cannot determine the config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// Directory file dialogs start in, set from the config
+static DIALOG_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+pub fn set_dialog_dir(dir: Option<PathBuf>) {
+    *DIALOG_DIR.write().unwrap() = dir;
+}
+
+pub fn dialog_dir() -> std::io::Result<PathBuf> {
+    match DIALOG_DIR.read().unwrap().as_ref() {
+        Some(dir) => Ok(dir.clone()),
+        None => Path::new(".").canonicalize(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keymap() {
+        let config = Config::parse(
+            r#"
+            [keymap]
+            save = "Ctrl+W"
+            fit_selection = "Shift+F"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.keymap["save"], "Ctrl+W");
+        assert_eq!(config.keymap["fit_selection"], "Shift+F");
+        assert!(Config::parse("").unwrap().keymap.is_empty());
+        // Unknown commands don't fail the other settings
+        let config = Config::parse("theme = \"light\"\n[keymap]\nfly = \"F\"").unwrap();
+        assert_eq!(config.theme, Theme::Light);
+    }
+
+    #[test]
+    fn config_roundtrip() {
+        let config = Config {
+            nvim_srv: Some("/tmp/nvim.sock".into()),
+            theme: Theme::System,
+            autosave_secs: 30,
+            dialog_dir: Some(PathBuf::from("/home/me/boards")),
+            repair_roots: vec![PathBuf::from("/mnt/archive")],
+            keymap: HashMap::from([("fit".to_string(), "Ctrl+F".to_string())]),
+            ..Default::default()
+        };
+        let s = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(Config::parse(&s).unwrap(), config);
+        // Missing settings keep their defaults
+        let partial = Config::parse("theme = \"light\"").unwrap();
+        assert_eq!(partial.theme, Theme::Light);
+        assert_eq!(partial.nvim_ext, Config::default().nvim_ext);
+    }
+}
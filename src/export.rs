@@ -0,0 +1,399 @@
+// This module contains exporters of pinboards into other formats.
+
+use crate::graph::{Arrow, BlobType, PinboardGraph, Relation};
+use anyhow::{anyhow, Result};
+use egui::Color32;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+// A single blob the pinboard depends on
+#[derive(Serialize)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub hash: String,
+    pub ty: &'static str,
+    pub exists: bool,
+}
+
+// Collect every blob of nodes and edges
+pub fn manifest(graph: &PinboardGraph) -> Vec<ManifestEntry> {
+    let nodes = graph
+        .nodes_iter()
+        .filter_map(|(_, n)| n.payload().blob.as_ref());
+    let edges = graph
+        .edges_iter()
+        .filter_map(|(_, e)| e.payload().comment.as_ref());
+    nodes
+        .chain(edges)
+        .map(|b| ManifestEntry {
+            path: b.path().clone(),
+            hash: b.hash().to_hex().to_string(),
+            ty: b.ty().label(),
+            exists: !b.is_local() || b.path().exists(),
+        })
+        .collect()
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn manifest_to_csv(entries: &[ManifestEntry]) -> String {
+    let mut res = String::from("path,hash,type,exists\n");
+    for e in entries {
+        res.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&e.path.to_string_lossy()),
+            e.hash,
+            e.ty,
+            e.exists
+        ));
+    }
+    res
+}
+
+// Write the manifest, the format is decided by the extension of the path
+pub async fn export_manifest(entries: Vec<ManifestEntry>, path: PathBuf) -> Result<()> {
+    let content = match path.extension().and_then(|s| s.to_str()) {
+        Some("csv") => manifest_to_csv(&entries),
+        Some("json") => serde_json::to_string_pretty(&entries)?,
+        _ => return Err(anyhow!("unsupported manifest format {:?}", path)),
+    };
+    tokio::fs::write(&path, content).await?;
+    Ok(())
+}
+
+// Value of a matrix cell for the relation, zero means unconnected
+fn relation_code(relation: &Relation) -> u8 {
+    match relation {
+        Relation::Related => 1,
+        Relation::Insight => 2,
+        Relation::Progress => 3,
+        Relation::Conflict => 4,
+        Relation::Custom(_) => 5,
+    }
+}
+
+// Sparse rows of the adjacency matrix with nodes ordered by label, the dense matrix is only
+// produced while writing
+pub struct Adjacency {
+    labels: Vec<String>,
+    rows: Vec<Vec<(usize, u8)>>,
+}
+
+pub fn adjacency(graph: &PinboardGraph) -> Adjacency {
+    let mut nodes: Vec<_> = graph.nodes_iter().map(|(id, n)| (id, n.label())).collect();
+    nodes.sort_by(|a, b| a.1.cmp(&b.1));
+    let index: HashMap<_, _> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| (*id, i))
+        .collect();
+    let mut rows = vec![Vec::new(); nodes.len()];
+    for (id, e) in graph.edges_iter() {
+        let (a, b) = graph.edge_endpoints(id).unwrap();
+        let code = relation_code(&e.payload().relation);
+        // Edges are undirected, so the matrix is symmetric
+        rows[index[&a]].push((index[&b], code));
+        rows[index[&b]].push((index[&a], code));
+    }
+    for row in &mut rows {
+        row.sort();
+    }
+    Adjacency {
+        labels: nodes.into_iter().map(|(_, label)| label).collect(),
+        rows,
+    }
+}
+
+// Write the matrix as CSV row by row, with labels as the header and the first column. Of several
+// edges between the same nodes, the one with the highest code wins.
+pub fn write_adjacency_csv(adjacency: &Adjacency, mut w: impl Write) -> std::io::Result<()> {
+    let labels: Vec<String> = adjacency.labels.iter().map(|l| csv_field(l)).collect();
+    writeln!(w, ",{}", labels.join(","))?;
+    let mut cells = vec![0; labels.len()];
+    for (label, row) in labels.iter().zip(&adjacency.rows) {
+        cells.fill(0);
+        for (col, code) in row {
+            cells[*col] = cells[*col].max(*code);
+        }
+        let line: Vec<String> = cells.iter().map(|c| c.to_string()).collect();
+        writeln!(w, "{},{}", label, line.join(","))?;
+    }
+    w.flush()
+}
+
+pub async fn export_adjacency(adjacency: Adjacency, path: PathBuf) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::File::create(path)?;
+        write_adjacency_csv(&adjacency, BufWriter::new(file))
+    })
+    .await??;
+    Ok(())
+}
+
+// Namespace of predicates without a schema.org counterpart
+const JSONLD_NAMESPACE: &str = "urn:pinlab:";
+
+fn jsonld_predicate(relation: &Relation) -> &'static str {
+    match relation {
+        Relation::Related => "isRelatedTo",
+        Relation::Insight => "pinlab:insightInto",
+        Relation::Progress => "pinlab:progressTowards",
+        Relation::Conflict => "pinlab:conflictsWith",
+        // There is no vocabulary for user-defined relations
+        Relation::Custom(_) => "isRelatedTo",
+    }
+}
+
+// Describe the graph as JSON-LD, nodes become schema.org entities and edges become predicates of
+// their start node. Edges without arrowhead are stated from the node they were created from.
+pub fn jsonld(graph: &PinboardGraph) -> Value {
+    let mut entities: Vec<(usize, Map<String, Value>)> = graph
+        .nodes_iter()
+        .map(|(id, n)| {
+            let data = n.payload();
+            let mut entity = Map::new();
+            entity.insert("@id".into(), json!(format!("_:n{}", id.index())));
+            let ty = match data.blob.as_ref().map(|b| b.ty()) {
+                Some(BlobType::File) => "DigitalDocument",
+                Some(BlobType::PinboardGraph) => "Collection",
+                Some(BlobType::Url) => "WebPage",
+                Some(BlobType::Note) => "NoteDigitalDocument",
+                None => "Thing",
+            };
+            entity.insert("@type".into(), json!(ty));
+            entity.insert("name".into(), json!(n.label()));
+            if !data.description.is_empty() {
+                entity.insert("description".into(), json!(data.description));
+            }
+            if !data.aliases.is_empty() {
+                entity.insert("alternateName".into(), json!(data.aliases));
+            }
+            if let Some(text) = data.blob.as_ref().and_then(|b| b.note_text()) {
+                entity.insert("text".into(), json!(text));
+            } else if let Some(blob) = &data.blob {
+                entity.insert("url".into(), json!(blob.path().to_string_lossy()));
+                entity.insert("pinlab:blake3".into(), json!(blob.hash().to_hex().as_str()));
+            }
+            (id.index(), entity)
+        })
+        .collect();
+    let position: HashMap<usize, usize> = entities
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| (*id, i))
+        .collect();
+    for (id, e) in graph.edges_iter() {
+        let (mut a, mut b) = graph.edge_endpoints(id).unwrap();
+        if e.payload().arrow == Arrow::Backward {
+            std::mem::swap(&mut a, &mut b);
+        }
+        let objects = entities[position[&a.index()]]
+            .1
+            .entry(jsonld_predicate(&e.payload().relation))
+            .or_insert_with(|| json!([]));
+        if let Value::Array(objects) = objects {
+            objects.push(json!({ "@id": format!("_:n{}", b.index()) }));
+        }
+    }
+    json!({
+        "@context": {
+            "@vocab": "https://schema.org/",
+            "pinlab": JSONLD_NAMESPACE,
+            "url": { "@type": "@id" },
+        },
+        "@graph": entities.into_iter().map(|(_, e)| Value::Object(e)).collect::<Vec<_>>(),
+    })
+}
+
+pub async fn export_jsonld(document: Value, path: PathBuf) -> Result<()> {
+    tokio::fs::write(&path, serde_json::to_string_pretty(&document)?).await?;
+    Ok(())
+}
+
+fn dot_string(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+fn dot_color(color: Color32) -> String {
+    format!("\"#{:02x}{:02x}{:02x}\"", color.r(), color.g(), color.b())
+}
+
+// GraphViz document of the pinboard. Nodes keep their positions for neato, blob paths become
+// tooltips and edges are colored like on the canvas.
+pub fn dot(graph: &PinboardGraph, title: &str) -> String {
+    let mut out = format!("digraph {} {{\n    node [shape=box];\n", dot_string(title));
+    let mut nodes: Vec<_> = graph.nodes_iter().collect();
+    nodes.sort_by_key(|(id, _)| *id);
+    for (id, n) in nodes {
+        let mut attrs = vec![format!("label={}", dot_string(&n.label()))];
+        // Canvas y points down while GraphViz y points up
+        let pos = n.location();
+        attrs.push(format!("pos=\"{},{}!\"", pos.x, 0.0 - pos.y));
+        if let Some(blob) = &n.payload().blob {
+            let tooltip = blob
+                .link()
+                .map_or_else(|| blob.path().display().to_string(), str::to_string);
+            if !tooltip.is_empty() {
+                attrs.push(format!("tooltip={}", dot_string(&tooltip)));
+            }
+            if let Some(color) = blob.color() {
+                attrs.push(format!("color={}", dot_color(color)));
+            }
+        }
+        out.push_str(&format!("    n{} [{}];\n", id.index(), attrs.join(", ")));
+    }
+    let mut edges: Vec<_> = graph.edges_iter().collect();
+    edges.sort_by_key(|(id, _)| *id);
+    for (id, e) in edges {
+        let (a, b) = graph.edge_endpoints(id).unwrap();
+        let conn = e.payload();
+        let mut attrs = vec![format!("label={}", dot_string(&conn.relation.label()))];
+        if let Some(color) = conn.relation.color() {
+            attrs.push(format!("color={}", dot_color(color)));
+        }
+        attrs.push(
+            match conn.arrow {
+                Arrow::None => "dir=none",
+                Arrow::Forward => "dir=forward",
+                Arrow::Backward => "dir=back",
+            }
+            .to_string(),
+        );
+        out.push_str(&format!(
+            "    n{} -> n{} [{}];\n",
+            a.index(),
+            b.index(),
+            attrs.join(", ")
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Write the DOT document, or render it with GraphViz neato keeping the node positions if the path
+// ends with `.svg`
+pub async fn export_dot(document: String, path: PathBuf) -> Result<()> {
+    if path.extension().and_then(|s| s.to_str()) != Some("svg") {
+        tokio::fs::write(&path, document).await?;
+        return Ok(());
+    }
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut child = Command::new("neato")
+            .arg("-n")
+            .arg("-Tsvg")
+            .arg("-o")
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("cannot run GraphViz neato, is it installed? {}", e))?;
+        child
+            .stdin
+            .take()
+            .ok_or(anyhow!("cannot write to GraphViz neato"))?
+            .write_all(document.as_bytes())?;
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(anyhow!("GraphViz neato failed with {}", status));
+        }
+        Ok(())
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{BlobType, Conn, NodeData};
+    use petgraph::stable_graph::StableGraph;
+
+    #[test]
+    fn manifest_csv_escaping() {
+        let entries = vec![ManifestEntry {
+            path: PathBuf::from("a,\"b\".txt"),
+            hash: "abc".to_string(),
+            ty: BlobType::File.label(),
+            exists: false,
+        }];
+        assert_eq!(
+            manifest_to_csv(&entries),
+            "path,hash,type,exists\n\"a,\"\"b\"\".txt\",abc,File,false\n"
+        );
+    }
+
+    #[test]
+    fn adjacency_csv() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let b = graph.add_node_with_label(NodeData::default(), "b".to_string());
+        let a = graph.add_node_with_label(NodeData::default(), "a".to_string());
+        let c = graph.add_node_with_label(NodeData::default(), "c,d".to_string());
+        graph.add_edge(a, b, Conn::new(Relation::Insight));
+        graph.add_edge(b, c, Conn::new(Relation::Related));
+        graph.add_edge(c, c, Conn::new(Relation::Conflict));
+
+        let mut out = Vec::new();
+        write_adjacency_csv(&adjacency(&graph), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ",a,b,\"c,d\"\na,0,2,0\nb,2,0,1\n\"c,d\",0,1,4\n"
+        );
+    }
+
+    #[test]
+    fn dot_document() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let a = graph.add_node_with_label(NodeData::default(), "say \"hi\"".to_string());
+        let b = graph.add_node_with_label(NodeData::default(), "b".to_string());
+        let mut conn = Conn::new(Relation::Insight);
+        conn.arrow = Arrow::Backward;
+        graph.add_edge(a, b, conn);
+        graph.add_edge(a, b, Conn::new(Relation::Related));
+
+        let document = dot(&graph, "Board");
+        assert!(document.starts_with("digraph \"Board\" {"));
+        assert!(document.contains("n0 [label=\"say \\\"hi\\\"\", pos=\"0,0!\"];"));
+        assert!(document.contains("n0 -> n1 [label=\"Insight\", color=\"#90ee90\", dir=back];"));
+        assert!(document.contains("n0 -> n1 [label=\"Related\", dir=none];"));
+    }
+
+    #[test]
+    fn jsonld_relations() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let a = graph.add_node_with_label(NodeData::default(), "a".to_string());
+        let b = graph.add_node_with_label(NodeData::default(), "b".to_string());
+        graph.add_edge(a, b, Conn::new(Relation::Conflict));
+        let mut conn = Conn::new(Relation::Insight);
+        conn.arrow = Arrow::Backward;
+        graph.add_edge(a, b, conn);
+
+        let document = jsonld(&graph);
+        let entities = document["@graph"].as_array().unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0]["@type"], "Thing");
+        assert_eq!(entities[0]["name"], "a");
+        assert_eq!(
+            entities[0]["pinlab:conflictsWith"],
+            json!([{ "@id": "_:n1" }])
+        );
+        assert_eq!(
+            entities[1]["pinlab:insightInto"],
+            json!([{ "@id": "_:n0" }])
+        );
+    }
+}
@@ -2,18 +2,154 @@
 
 use anyhow::anyhow;
 use blake3::Hash as BlakeHash;
-use egui::Color32;
+use egui::{epaint::TextShape, Color32, FontFamily, FontId, Pos2, Rect, Shape, Stroke, Vec2};
 use egui_graphs::{
     DefaultEdgeShape, DefaultNodeShape, DisplayEdge, DisplayNode, DrawContext, EdgeProps, Graph,
     GraphView, Node, NodeProps,
 };
+use ignore::{WalkBuilder, WalkState};
 use log::{info, warn};
 use petgraph::{
     csr::{DefaultIx, IndexType},
+    stable_graph::{NodeIndex, StableGraph},
     EdgeType, Undirected,
 };
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::SystemTime,
+};
+
+type HashCache = HashMap<PathBuf, (SystemTime, u64, BlakeHash)>;
+
+// Hashes of files by absolute path, valid as long as the modification time and size of the file
+// stay the same
+static HASH_CACHE: LazyLock<Mutex<HashCache>> = LazyLock::new(Default::default);
+
+/// Hash the file, skipping reading it if its modification time and size haven't changed since it
+/// was last hashed. The content is returned if the file has been read.
+pub fn hash_file(path: &Path) -> std::io::Result<(BlakeHash, Option<Vec<u8>>)> {
+    hash_file_in(&HASH_CACHE, path)
+}
+
+fn hash_file_in(
+    cache: &Mutex<HashCache>,
+    path: &Path,
+) -> std::io::Result<(BlakeHash, Option<Vec<u8>>)> {
+    let meta = std::fs::metadata(path)?;
+    let (mtime, size) = (meta.modified()?, meta.len());
+    let key = std::path::absolute(path)?;
+    if let Some((m, s, hash)) = cache.lock().unwrap().get(&key) {
+        if (*m, *s) == (mtime, size) {
+            return Ok((*hash, None));
+        }
+    }
+    let content = std::fs::read(path)?;
+    let hash = blake3::hash(&content);
+    cache.lock().unwrap().insert(key, (mtime, size, hash));
+    Ok((hash, Some(content)))
+}
+
+// File keeping the hash cache across sessions
+fn hash_cache_file() -> anyhow::Result<PathBuf> {
+    eframe::storage_dir("Pinlab")
+        .map(|d| d.join("hash_cache.json"))
+        .ok_or(anyhow!("cannot determine the data directory"))
+}
+
+fn read_hash_cache(current: &Mutex<HashCache>, path: &Path) -> anyhow::Result<()> {
+    if !path.try_exists()? {
+        return Ok(());
+    }
+    let cache: Vec<(PathBuf, (SystemTime, u64, BlakeHash))> =
+        serde_json::from_slice(&std::fs::read(path)?)?;
+    let mut current = current.lock().unwrap();
+    // Hashes computed during this session are more recent
+    for (path, entry) in cache {
+        current.entry(path).or_insert(entry);
+    }
+    Ok(())
+}
+
+fn write_hash_cache(cache: &Mutex<HashCache>, path: &Path) -> anyhow::Result<()> {
+    // Paths which aren't valid UTF-8 cannot be stored as JSON, their files are simply read again
+    let cache: Vec<_> = cache
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(p, _)| p.to_str().is_some())
+        .map(|(p, e)| (p.clone(), *e))
+        .collect();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_vec(&cache)?)?;
+    Ok(())
+}
+
+/// Load hashes saved by earlier sessions, this should be called on startup
+pub fn load_hash_cache() -> anyhow::Result<()> {
+    read_hash_cache(&HASH_CACHE, &hash_cache_file()?)
+}
+
+/// Save the hashes for later sessions
+pub fn save_hash_cache() -> anyhow::Result<()> {
+    write_hash_cache(&HASH_CACHE, &hash_cache_file()?)
+}
+
+/// Forget all hashes, files are read again the next time they are hashed
+pub fn clear_hash_cache() -> anyhow::Result<()> {
+    HASH_CACHE.lock().unwrap().clear();
+    let path = hash_cache_file()?;
+    if path.try_exists()? {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Name of files listing patterns of files never matched to missing blobs, like `.gitignore`
+pub const IGNORE_FILE: &str = ".pinbrdignore";
+
+/// Files larger than this are not hashed while searching for missing blobs by default
+pub const DEFAULT_MAX_SEARCH_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Where missing blobs are searched for
+#[derive(Clone)]
+pub struct SearchScope {
+    pub roots: Vec<PathBuf>,
+    /// Files larger than this many bytes are skipped
+    pub max_size: u64,
+}
+
+impl Default for SearchScope {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            max_size: DEFAULT_MAX_SEARCH_SIZE,
+        }
+    }
+}
+
+/// Error of auto-repairing a blob when several files match its hash, candidates are ordered by
+/// likelihood
+#[derive(Debug)]
+pub struct MultipleMatches(pub Vec<PathBuf>);
+
+impl std::fmt::Display for MultipleMatches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files match the hash of the missing blob, aborting auto-repairing",
+            self.0.len()
+        )
+    }
+}
+
+impl std::error::Error for MultipleMatches {}
 
 /// A blob is any document (e.g. PDF, image, hyperlink, etc. or even a pinboard!)
 // NOTE: Cloning an trait object is impossible, that's why we didn't implement in that style
@@ -21,96 +157,319 @@ use std::path::{Path, PathBuf};
 pub enum BlobType {
     PinboardGraph,
     File,
+    // Web link, the path of the blob is the URL and its hash is never checked
+    Url,
+    // Text kept inside the pinboard file itself
+    Note,
 }
 
+// Number of lines and characters at most kept in a blob snippet
+const SNIPPET_LINES: usize = 6;
+const SNIPPET_CHARS: usize = 240;
+// Bytes read from a file to find its snippet, enough for the longest one
+const SNIPPET_BYTES: u64 = SNIPPET_CHARS as u64 * 4;
+
 #[derive(PartialEq, Serialize, Deserialize, Clone)]
 pub struct Blob {
     ty: BlobType,
     path: PathBuf,
     hash: BlakeHash,
+    // First lines of a textual file, shown on node cards. Notes derive theirs from their text and
+    // files are read again after loading, see `Blob::load_snippet`.
+    #[serde(skip)]
+    snippet: Option<String>,
+    // Title of a link, links are labeled by their URL without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    // Content of a note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
 }
 
 impl Blob {
     pub async fn new(ty: BlobType, path: PathBuf) -> anyhow::Result<Self> {
         let content = tokio::fs::read(&path).await?;
         let hash = blake3::hash(&content);
-        Ok(Self { ty, path, hash })
+        let snippet = Self::snippet_of(&ty, &content);
+        Ok(Self {
+            ty,
+            path,
+            hash,
+            snippet,
+            title: None,
+            note: None,
+        })
+    }
+
+    /// Blob of a web link, nothing is fetched so the hash only identifies the URL
+    pub fn url(link: &str, title: Option<String>) -> Self {
+        Self {
+            ty: BlobType::Url,
+            path: PathBuf::from(link),
+            hash: blake3::hash(link.as_bytes()),
+            snippet: None,
+            title: title.filter(|t| !t.trim().is_empty()),
+            note: None,
+        }
+    }
+
+    /// Blob of a note stored inline, it has no path
+    pub fn note(text: String) -> Self {
+        let mut blob = Self {
+            ty: BlobType::Note,
+            path: PathBuf::new(),
+            hash: blake3::hash(b""),
+            snippet: None,
+            title: None,
+            note: None,
+        };
+        blob.set_note(text);
+        blob
+    }
+
+    /// Content of a note blob
+    pub fn note_text(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    pub fn set_note(&mut self, text: String) {
+        self.hash = blake3::hash(text.as_bytes());
+        self.note = Some(text);
+    }
+
+    /// Whether the blob is backed by a file on disk
+    pub fn is_local(&self) -> bool {
+        matches!(self.ty, BlobType::File | BlobType::PinboardGraph)
+    }
+
+    /// URL of a link blob
+    pub fn link(&self) -> Option<&str> {
+        (self.ty == BlobType::Url)
+            .then(|| self.path.to_str())
+            .flatten()
+    }
+
+    /// Name to label nodes and edges carrying the blob with
+    pub fn name(&self) -> String {
+        if let Some(note) = &self.note {
+            // Notes are named by their first line
+            return note
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty())
+                .unwrap_or("Note")
+                .to_string();
+        }
+        match self.link() {
+            Some(link) => self.title.clone().unwrap_or_else(|| link.to_string()),
+            None => self.path.file_name().map_or_else(
+                || self.path.display().to_string(),
+                |n| n.to_string_lossy().to_string(),
+            ),
+        }
+    }
+
+    fn snippet_of(ty: &BlobType, content: &[u8]) -> Option<String> {
+        if !matches!(ty, BlobType::File | BlobType::Note) {
+            return None;
+        }
+        let text = match std::str::from_utf8(content) {
+            Ok(text) => text,
+            // Content read partially may end within a character
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&content[..e.valid_up_to()]).unwrap()
+            }
+            Err(_) => return None,
+        };
+        Some(
+            text.lines()
+                .take(SNIPPET_LINES)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .chars()
+                .take(SNIPPET_CHARS)
+                .collect(),
+        )
+    }
+
+    /// Read the snippet of a file again, since snippets aren't saved
+    pub fn load_snippet(&mut self) {
+        if self.ty != BlobType::File {
+            return;
+        }
+        let mut head = Vec::new();
+        if let Ok(file) = std::fs::File::open(&self.path) {
+            if file.take(SNIPPET_BYTES).read_to_end(&mut head).is_ok() {
+                self.snippet = Self::snippet_of(&self.ty, &head);
+            }
+        }
+    }
+
+    pub fn snippet(&self) -> Option<Cow<'_, str>> {
+        match &self.note {
+            Some(note) => Self::snippet_of(&self.ty, note.as_bytes()).map(Cow::Owned),
+            None => self.snippet.as_deref().map(Cow::Borrowed),
+        }
     }
 
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
 
+    // Point the blob at a new location of the same content, e.g. after restoring it elsewhere
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = path;
+    }
+
+    /// Path of the blob relative to the base directory, `None` if the blob is outside of it.
+    /// Relative paths are taken as relative to the working directory.
+    pub fn relative_to(&self, base: &Path) -> Option<PathBuf> {
+        let path = if self.path.is_absolute() {
+            self.path.clone()
+        } else {
+            std::env::current_dir().ok()?.join(&self.path)
+        };
+        path.strip_prefix(base).ok().map(Path::to_path_buf)
+    }
+
     pub fn ty(&self) -> &BlobType {
         &self.ty
     }
 
-    #[allow(dead_code)]
     pub fn hash(&self) -> &BlakeHash {
         &self.hash
     }
 
-    fn walk(dir: &Path, hash: &BlakeHash) -> anyhow::Result<Option<PathBuf>> {
-        let mut count = 0;
-        let mut res = None;
-        if dir.is_dir() {
-            info!("searching inside {:?}", dir);
-            // Count number of files matching our hash
-            for entry in std::fs::read_dir(dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                // If the path is not hidden
-                // If errored, we assume it's hidden to play safe
-                if !hf::is_hidden(&path).unwrap_or(true) {
-                    if path.is_dir() {
-                        if let Some(matched) = Self::walk(&path, hash)? {
-                            info!("path {:?} matches the hash {}", path, hash);
-                            res = Some(matched);
-                            count += 1;
-                        }
-                    } else {
-                        if blake3::hash(&std::fs::read(&path)?) == *hash {
-                            info!("path {:?} matches the hash {}", path, hash);
-                            res = Some(path);
-                            count += 1;
-                        }
+    // Files under the directory matching the hash, searched in parallel. Hidden files, files
+    // ignored by `.gitignore` or `IGNORE_FILE` and files larger than the limit are skipped, so are
+    // files which cannot be read.
+    fn walk(dir: &Path, hash: &BlakeHash, max_size: u64) -> Vec<PathBuf> {
+        if !dir.is_dir() {
+            return Vec::new();
+        }
+        info!("searching inside {:?}", dir);
+        let (tx, rx) = crossbeam::channel::unbounded();
+        WalkBuilder::new(dir)
+            .add_custom_ignore_filename(IGNORE_FILE)
+            // Ignore files apply outside of git repositories as well
+            .require_git(false)
+            .build_parallel()
+            .run(|| {
+                let tx = tx.clone();
+                Box::new(move |entry| {
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    let matches = entry.file_type().is_some_and(|t| t.is_file())
+                        && entry.metadata().is_ok_and(|m| m.len() <= max_size)
+                        && hash_file(entry.path()).is_ok_and(|(h, _)| h == *hash);
+                    if matches {
+                        info!("path {:?} matches the hash {}", entry.path(), hash);
+                        // The receiver outlives the walk
+                        tx.send(entry.into_path()).unwrap();
                     }
+                    WalkState::Continue
+                })
+            });
+        drop(tx);
+        // Threads finish in any order
+        let mut res: Vec<_> = rx.into_iter().collect();
+        res.sort();
+        res
+    }
+
+    /// Accept the current content of the file as the content of the blob
+    pub fn refresh_hash(&mut self) -> std::io::Result<()> {
+        if !self.is_local() {
+            return Ok(());
+        }
+        let (hash, content) = hash_file(&self.path)?;
+        self.hash = hash;
+        if let Some(content) = content {
+            self.snippet = Self::snippet_of(&self.ty, &content);
+        }
+        Ok(())
+    }
+
+    /// Files in the scope matching the hash of the blob, files under several roots are found once
+    pub fn find_matches(&self, scope: &SearchScope) -> Vec<PathBuf> {
+        if !self.is_local() {
+            return Vec::new();
+        }
+        let mut res = Vec::new();
+        let mut seen = HashSet::new();
+        for root in &scope.roots {
+            for path in Self::walk(root, &self.hash, scope.max_size) {
+                if seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())) {
+                    res.push(path);
                 }
             }
         }
-        if count > 1 {
-            return Err(anyhow!(
-                "multiple files matching targeted hash {} exists, aborting auto-repairing",
-                hash
-            ));
-        }
-        Ok(res)
+        res
+    }
+
+    // Order candidates so that the likeliest new location of the blob comes first: files keeping
+    // the name, then files sharing more leading directories with the old path
+    fn prioritize(&self, candidates: &mut [PathBuf]) {
+        candidates.sort_by_key(|p| {
+            let renamed = p.file_name() != self.path.file_name();
+            let common = p
+                .components()
+                .zip(self.path.components())
+                .take_while(|(a, b)| a == b)
+                .count();
+            (renamed, std::cmp::Reverse(common))
+        });
     }
 
     /// Update the blob info
-    /// If the path exists, then update the hash
-    /// If the path is no longer accessible, then try find the _unique_ _unhidden_ file matching the current hash in
-    /// the provided root
-    /// If cannot find one file matching the hash, then error
+    /// If the path exists, then update the hash, unless `refresh_hash` is false in which case
+    /// the hash is kept and a content drift is only warned about
+    /// If the path is no longer accessible, then try find the _unique_ file matching the current hash in
+    /// the provided scope, see [`Blob::find_matches`]
+    /// If several files match the hash, then error with [`MultipleMatches`] for the user to pick
     ///
-    /// NOTE: root must be a folder
+    /// NOTE: roots must be folders
     /// This should be spawned as blocking
-    pub fn update(&mut self, root: &Path) -> anyhow::Result<()> {
+    pub fn update(&mut self, scope: &SearchScope, refresh_hash: bool) -> anyhow::Result<()> {
+        // Links have nothing on disk to be checked
+        if !self.is_local() {
+            return Ok(());
+        }
         match self.path.try_exists() {
             Ok(true) => {
                 // File exists, update the hash
-                self.hash = blake3::hash(&std::fs::read(&self.path)?);
+                let (hash, content) = hash_file(&self.path)?;
+                if refresh_hash {
+                    self.hash = hash;
+                } else if hash != self.hash {
+                    warn!(
+                        "content of {:?} has drifted from the recorded hash {}",
+                        self.path, self.hash
+                    );
+                }
+                // Unchanged files keep their snippet
+                if let Some(content) = content {
+                    self.snippet = Self::snippet_of(&self.ty, &content);
+                }
             }
             Ok(false) => {
                 warn!(
                     "{:?} doesn't exist, trying to automatically match file under {:?}",
-                    self.path, root
+                    self.path, scope.roots
                 );
                 // File doesn't exist or is not accessible, search from the path
-                if let Some(path) = Self::walk(root, &self.hash)? {
-                    self.path = path;
-                } else {
-                    warn!("{:?} doesn't exist and cannot be matched to any unique, unhidden file under {:?}", self.path, root);
+                let mut matches = self.find_matches(scope);
+                match matches.len() {
+                    0 => warn!(
+                        "{:?} doesn't exist and cannot be matched to any unhidden file under {:?}",
+                        self.path, scope.roots
+                    ),
+                    1 => self.path = matches.pop().unwrap(),
+                    _ => {
+                        self.prioritize(&mut matches);
+                        return Err(MultipleMatches(matches).into());
+                    }
                 }
             }
             Err(e) => {
@@ -132,12 +491,23 @@ impl BlobType {
         match self {
             BlobType::PinboardGraph => Some(Color32::LIGHT_BLUE),
             BlobType::File => None,
+            BlobType::Url => Some(Color32::KHAKI),
+            BlobType::Note => Some(Color32::LIGHT_YELLOW),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlobType::PinboardGraph => "Pinboard",
+            BlobType::File => "File",
+            BlobType::Url => "Link",
+            BlobType::Note => "Note",
         }
     }
 }
 
 /// Relation between nodes
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub enum Relation {
     /// Contradicting or confusing
     Conflict,
@@ -147,9 +517,40 @@ pub enum Relation {
     Insight,
     /// Easy to identify or probably trivial relation
     Related,
+    /// Kind of relation defined by the user for the pinboard
+    Custom(RelationKind),
+}
+
+/// User-defined kind of relation. Connections carry a copy of it, so renaming or recoloring a
+/// kind has to update them as well.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct RelationKind {
+    /// Tells kinds apart even if they share name and color, nil in older pinboard files
+    #[serde(default)]
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub color: Color32,
+}
+
+impl RelationKind {
+    pub fn new(name: String, color: Color32) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4(),
+            name,
+            color,
+        }
+    }
 }
 
 impl Relation {
+    /// Every relation, in the order offered to users
+    pub const ALL: [Relation; 4] = [
+        Relation::Related,
+        Relation::Insight,
+        Relation::Progress,
+        Relation::Conflict,
+    ];
+
     pub fn color(&self) -> Option<Color32> {
         match self {
             Self::Conflict => Some(Color32::LIGHT_RED),
@@ -157,6 +558,7 @@ impl Relation {
             Self::Insight => Some(Color32::LIGHT_GREEN),
             // Color should be determined by foregrapund default
             Self::Related => None,
+            Self::Custom(kind) => Some(kind.color),
         }
     }
 
@@ -166,29 +568,733 @@ impl Relation {
             Relation::Progress => "Progress",
             Relation::Insight => "Insight",
             Relation::Related => "Related",
+            Relation::Custom(kind) => &kind.name,
         }
         .to_string()
     }
+
+    /// Whether the relation naturally points from one node to the other, new connections with it
+    /// get a forward arrowhead
+    pub fn is_directed(&self) -> bool {
+        *self == Relation::Progress
+    }
+
+    /// Parse a relation from its label, ignoring case
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().as_str() {
+            "conflict" => Some(Relation::Conflict),
+            "progress" => Some(Relation::Progress),
+            "insight" => Some(Relation::Insight),
+            "related" => Some(Relation::Related),
+            _ => None,
+        }
+    }
+}
+
+/// Arrowhead of a connection, relative to the order of the endpoints it was created with.
+/// The underlying graph stays undirected.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Arrow {
+    #[default]
+    None,
+    Forward,
+    Backward,
+}
+
+impl Arrow {
+    pub fn is_none(&self) -> bool {
+        *self == Arrow::None
+    }
+
+    /// Point the arrowhead the other way, an edge without arrowhead gets a forward one
+    pub fn reversed(self) -> Self {
+        match self {
+            Arrow::None | Arrow::Backward => Arrow::Forward,
+            Arrow::Forward => Arrow::Backward,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Conn {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub comment: Option<Blob>,
     pub relation: Relation,
+    #[serde(default, skip_serializing_if = "Arrow::is_none")]
+    pub arrow: Arrow,
+    // Last time the connection was edited, older connections don't have it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<SystemTime>,
 }
 
-pub type PinboardGraph = Graph<Option<Blob>, Conn, Undirected, DefaultIx, MyNodeShape, MyEdgeShape>;
+impl Conn {
+    pub fn new(relation: Relation) -> Self {
+        Self {
+            comment: None,
+            arrow: if relation.is_directed() {
+                Arrow::Forward
+            } else {
+                Arrow::None
+            },
+            relation,
+            modified: Some(SystemTime::now()),
+        }
+    }
+
+    pub fn touch(&mut self) {
+        self.modified = Some(SystemTime::now());
+    }
+}
+
+/// How a node is drawn given the tag filter of its board
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub enum Visibility {
+    #[default]
+    Shown,
+    Dimmed,
+    Hidden,
+}
+
+// Opacity of nodes and edges dimmed by the tag filter
+const DIMMED_OPACITY: f32 = 0.15;
+
+/// Data attached to a node
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(from = "NodeDataRepr")]
+pub struct NodeData {
+    // Empty fields are left out of pinboard files, see `NodeDataRepr` for their defaults
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob: Option<Blob>,
+    /// Render the node as a card showing the snippet of its blob
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub card: bool,
+    /// Why the node is on the board, shown when hovering the node
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Other names the node can be found by, one per line while being edited
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Last time the node was edited, nodes created before this was tracked don't have it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<SystemTime>,
+    /// Cross-cutting themes the node belongs to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Label chosen by the user, shown instead of the name of the blob
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Set by the tag filter of the board every frame, never saved
+    #[serde(skip)]
+    pub visibility: Visibility,
+}
+
+impl NodeData {
+    /// Empty node data stamped with the current time
+    pub fn new() -> Self {
+        Self {
+            modified: Some(SystemTime::now()),
+            ..Default::default()
+        }
+    }
+
+    pub fn touch(&mut self) {
+        self.modified = Some(SystemTime::now());
+    }
+
+    /// Size of the node in canvas coordinates if it's drawn as a card or thumbnail rather than a
+    /// circle
+    pub fn box_size(&self) -> Option<Vec2> {
+        let blob = self.blob.as_ref()?;
+        if self.card {
+            Some(CARD_SIZE)
+        } else if *blob.ty() == BlobType::File && is_image(blob.path()) {
+            Some(THUMBNAIL_SIZE)
+        } else {
+            None
+        }
+    }
+
+    /// The custom label if set, the given name of the content otherwise
+    pub fn label_or(&self, name: String) -> String {
+        self.label.clone().unwrap_or(name)
+    }
+
+    /// Trimmed tags without blank lines left over from editing
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty())
+    }
+
+    /// Whether any alias contains the lowercase query, blank aliases never match
+    pub fn alias_matches(&self, query: &str) -> bool {
+        self.aliases
+            .iter()
+            .filter(|a| !a.trim().is_empty())
+            .any(|a| a.to_lowercase().contains(query))
+    }
+}
+
+// Older pinboards stored the blob directly as the node payload
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NodeDataRepr {
+    Legacy(Option<Blob>),
+    Current {
+        #[serde(default)]
+        blob: Option<Blob>,
+        #[serde(default)]
+        card: bool,
+        #[serde(default)]
+        description: String,
+        #[serde(default)]
+        aliases: Vec<String>,
+        #[serde(default)]
+        modified: Option<SystemTime>,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        label: Option<String>,
+    },
+}
+
+impl From<NodeDataRepr> for NodeData {
+    fn from(repr: NodeDataRepr) -> Self {
+        match repr {
+            NodeDataRepr::Legacy(blob) => Self {
+                blob,
+                ..Default::default()
+            },
+            NodeDataRepr::Current {
+                blob,
+                card,
+                description,
+                aliases,
+                modified,
+                tags,
+                label,
+            } => Self {
+                blob,
+                card,
+                description,
+                aliases,
+                modified,
+                tags,
+                label,
+                visibility: Visibility::Shown,
+            },
+        }
+    }
+}
+
+pub type PinboardGraph = Graph<NodeData, Conn, Undirected, DefaultIx, MyNodeShape, MyEdgeShape>;
 
 pub type PinboardGraphView<'a> =
-    GraphView<'a, Option<Blob>, Conn, Undirected, DefaultIx, MyNodeShape, MyEdgeShape>;
+    GraphView<'a, NodeData, Conn, Undirected, DefaultIx, MyNodeShape, MyEdgeShape>;
+
+/// Path from the base directory to the path, going up with `..` where needed. Relative paths are
+/// taken as relative to the working directory. `None` if they have nothing but the root in common.
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path = std::path::absolute(path).ok()?;
+    let base = std::path::absolute(base).ok()?;
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if !path[..common]
+        .iter()
+        .any(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    let mut res: PathBuf = base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    res.extend(&path[common..]);
+    Some(res)
+}
+
+/// Join the path onto the base directory, resolving `..` and `.` lexically
+pub fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    let mut res = PathBuf::new();
+    for c in base.join(path).components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(res.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                res.pop();
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Rebuild the graph so that node and edge indices are dense, keeping payloads, labels, positions
+/// and connections.
+pub fn compact(graph: &PinboardGraph) -> PinboardGraph {
+    let mut res = PinboardGraph::from(&StableGraph::default());
+    let mut map = HashMap::with_capacity(graph.node_count());
+    for (id, n) in graph.nodes_iter() {
+        let new_id =
+            res.add_node_with_label_and_location(n.payload().clone(), n.label(), n.location());
+        map.insert(id, new_id);
+    }
+    for (id, e) in graph.edges_iter() {
+        let (a, b) = graph.edge_endpoints(id).unwrap();
+        res.add_edge_with_label(map[&a], map[&b], e.payload().clone(), e.label());
+    }
+    res
+}
+
+/// Distance (in canvas coordinates) between neighbouring nodes arranged in a grid at spacing 1
+pub const GRID_CELL: f32 = 80.0;
+
+/// Arrange the nodes in a roughly square grid starting at the top left corner of their bounding
+/// box, with the cell size scaled by `spacing`. Returns the previous locations of moved nodes.
+pub fn arrange_grid(
+    graph: &mut PinboardGraph,
+    nodes: &[NodeIndex],
+    spacing: f32,
+) -> Vec<(NodeIndex, Pos2)> {
+    let origins: Vec<(NodeIndex, Pos2)> = nodes
+        .iter()
+        .filter_map(|id| graph.node(*id).map(|n| (*id, n.location())))
+        .collect();
+    let Some(min) = origins.iter().map(|(_, p)| *p).reduce(|a, b| a.min(b)) else {
+        return origins;
+    };
+    let columns = (origins.len() as f32).sqrt().ceil() as usize;
+    let cell = GRID_CELL * spacing;
+    for (i, (id, _)) in origins.iter().enumerate() {
+        let (row, col) = (i / columns, i % columns);
+        if let Some(n) = graph.node_mut(*id) {
+            n.set_location(min + Vec2::new(col as f32 * cell, row as f32 * cell));
+        }
+    }
+    origins
+}
+
+/// Ways of lining up nodes relative to each other
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Alignment {
+    Left,
+    Top,
+    DistributeHorizontally,
+    DistributeVertically,
+}
+
+impl Alignment {
+    pub const ALL: [Alignment; 4] = [
+        Alignment::Left,
+        Alignment::Top,
+        Alignment::DistributeHorizontally,
+        Alignment::DistributeVertically,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Alignment::Left => "Align left",
+            Alignment::Top => "Align top",
+            Alignment::DistributeHorizontally => "Distribute horizontally",
+            Alignment::DistributeVertically => "Distribute vertically",
+        }
+    }
+}
+
+/// Line up the nodes. Aligning moves them onto the leftmost or topmost one, distributing spaces
+/// them evenly between the outermost ones while keeping their order. Returns the previous
+/// locations of the nodes.
+pub fn align(
+    graph: &mut PinboardGraph,
+    nodes: &[NodeIndex],
+    alignment: Alignment,
+) -> Vec<(NodeIndex, Pos2)> {
+    let mut origins: Vec<(NodeIndex, Pos2)> = nodes
+        .iter()
+        .filter_map(|id| graph.node(*id).map(|n| (*id, n.location())))
+        .collect();
+    let Some(min) = origins.iter().map(|(_, p)| *p).reduce(|a, b| a.min(b)) else {
+        return origins;
+    };
+    let max = origins.iter().map(|(_, p)| *p).fold(min, |a, b| a.max(b));
+    let horizontal = matches!(
+        alignment,
+        Alignment::Left | Alignment::DistributeHorizontally
+    );
+    let coord = |p: &Pos2| if horizontal { p.x } else { p.y };
+    origins.sort_by(|(_, a), (_, b)| coord(a).total_cmp(&coord(b)));
+    let step = if origins.len() > 1 {
+        (max - min) / (origins.len() - 1) as f32
+    } else {
+        Vec2::ZERO
+    };
+    for (i, (id, pos)) in origins.iter().enumerate() {
+        let to = match alignment {
+            Alignment::Left => Pos2::new(min.x, pos.y),
+            Alignment::Top => Pos2::new(pos.x, min.y),
+            Alignment::DistributeHorizontally => Pos2::new(min.x + step.x * i as f32, pos.y),
+            Alignment::DistributeVertically => Pos2::new(pos.x, min.y + step.y * i as f32),
+        };
+        if let Some(n) = graph.node_mut(*id) {
+            n.set_location(to);
+        }
+    }
+    origins
+}
+
+/// Nearest intersection of the grid with lines `spacing` apart
+pub fn snap_to_grid(pos: Pos2, spacing: f32) -> Pos2 {
+    Pos2::new(
+        (pos.x / spacing).round() * spacing,
+        (pos.y / spacing).round() * spacing,
+    )
+}
+
+/// Preferred distance (in canvas coordinates) between connected nodes of the force-directed layout
+/// at spacing 1
+pub const FORCE_DISTANCE: f32 = 120.0;
+
+/// Move every node by one step of a Fruchterman-Reingold simulation, where all nodes repel each
+/// other and connected nodes attract each other. No node moves further than `temperature`, which
+/// is meant to cool down over the steps. Returns whether any node moved.
+pub fn force_step(graph: &mut PinboardGraph, spacing: f32, temperature: f32) -> bool {
+    let k = FORCE_DISTANCE * spacing;
+    let nodes: Vec<(NodeIndex, Pos2)> = graph
+        .nodes_iter()
+        .map(|(id, n)| (id, n.location()))
+        .collect();
+    let index: HashMap<NodeIndex, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| (*id, i))
+        .collect();
+    let mut disp = vec![Vec2::ZERO; nodes.len()];
+    for (i, (_, a)) in nodes.iter().enumerate() {
+        for (j, (_, b)) in nodes.iter().enumerate().skip(i + 1) {
+            let mut delta = *a - *b;
+            // Nodes on top of each other are pushed apart in an arbitrary but fixed direction
+            if delta.length() < 0.01 {
+                delta = Vec2::angled(i as f32 + j as f32) * 0.01;
+            }
+            let force = delta.normalized() * k * k / delta.length();
+            disp[i] += force;
+            disp[j] -= force;
+        }
+    }
+    for e in graph.g.edge_indices() {
+        let Some((a, b)) = graph.g.edge_endpoints(e) else {
+            continue;
+        };
+        let (Some(&i), Some(&j)) = (index.get(&a), index.get(&b)) else {
+            continue;
+        };
+        let delta = nodes[i].1 - nodes[j].1;
+        if i == j || delta.length() < 0.01 {
+            continue;
+        }
+        let force = delta.normalized() * delta.length_sq() / k;
+        disp[i] -= force;
+        disp[j] += force;
+    }
+    let mut moved = false;
+    for ((id, pos), d) in nodes.into_iter().zip(disp) {
+        let len = d.length();
+        if len < 0.01 {
+            continue;
+        }
+        if let Some(n) = graph.node_mut(id) {
+            n.set_location(pos + d / len * len.min(temperature));
+            moved = true;
+        }
+    }
+    moved
+}
+
+/// Foreground of nodes and edges without a color of their own, egui's default doesn't have enough
+/// contrast in every theme
+fn default_color(ctx: &DrawContext) -> Color32 {
+    let dark = match ctx.style.background() {
+        Some(bg) => luminance(bg) < 0.5,
+        None => ctx.ctx.style().visuals.dark_mode,
+    };
+    if dark {
+        Color32::from_gray(210)
+    } else {
+        Color32::from_gray(50)
+    }
+}
+
+// Colors closer in contrast to the background than this get adjusted
+const MIN_CONTRAST: f32 = 3.0;
+
+// Relative luminance as defined by WCAG
+fn luminance(color: Color32) -> f32 {
+    let channel = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.r()) + 0.7152 * channel(color.g()) + 0.0722 * channel(color.b())
+}
+
+fn contrast_ratio(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Brighten or darken the color until it stands out from the background, whichever direction
+/// leaves more room
+fn with_contrast(color: Color32, background: Color32) -> Color32 {
+    let target = if luminance(background) < 0.5 {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+    let mut res = color;
+    for step in 1..=10 {
+        if contrast_ratio(res, background) >= MIN_CONTRAST {
+            break;
+        }
+        res = color.lerp_to_gamma(target, step as f32 / 10.0);
+    }
+    res
+}
+
+// Color of a shape with the contrast adjusted to the background of the board, if any
+fn adjusted_color(color: Color32, ctx: &DrawContext) -> Color32 {
+    match ctx.style.background() {
+        Some(bg) => with_contrast(color, bg),
+        None => color,
+    }
+}
+
+// Fade the color of elements not edited for a while, elements without timestamp count as oldest
+fn faded(color: Color32, modified: Option<SystemTime>, ctx: &DrawContext) -> Color32 {
+    let Some(fade) = ctx.style.age_fade() else {
+        return color;
+    };
+    let opacity = modified
+        .and_then(|m| m.elapsed().ok())
+        .map_or(fade.min_opacity, |age| fade.opacity(age));
+    color.gamma_multiply(opacity)
+}
+
+// Size of a node card in canvas coordinates
+const CARD_SIZE: Vec2 = Vec2::new(160.0, 100.0);
+const CARD_PADDING: f32 = 6.0;
+// Box which thumbnails of images are fitted into
+const THUMBNAIL_SIZE: Vec2 = Vec2::new(64.0, 64.0);
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+/// Whether the file is an image nodes can show a thumbnail of
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| IMAGE_EXTENSIONS.iter().any(|i| e.eq_ignore_ascii_case(i)))
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MyNodeShape {
     super_shape: DefaultNodeShape,
+    // Snippet to show if the node is rendered as a card
+    card: Option<String>,
+    // URI of the image shown instead of the circle, textures are cached by egui per URI
+    thumbnail: Option<String>,
+    // The node refers to a pinboard which can be drilled into
+    pinboard: bool,
+    modified: Option<SystemTime>,
+    // Set by the tag filter every frame, never saved
+    #[serde(skip)]
+    visibility: Visibility,
+}
+
+// Gap (in canvas coordinates) between a pinboard node and its extra outline
+const PINBOARD_OUTLINE_GAP: f32 = 3.0;
+
+impl MyNodeShape {
+    fn card_rect(&self) -> Rect {
+        Rect::from_center_size(self.super_shape.pos, CARD_SIZE)
+    }
+
+    // Size of the node if it's drawn as a box rather than a circle
+    fn box_size(&self) -> Option<Vec2> {
+        if self.card.is_some() {
+            Some(CARD_SIZE)
+        } else if self.thumbnail.is_some() {
+            Some(THUMBNAIL_SIZE)
+        } else {
+            None
+        }
+    }
+
+    // An extra outline behind the node hinting that it contains a nested pinboard, a stacked card
+    // for cards and a ring for circles
+    fn pinboard_outline(&self, ctx: &DrawContext) -> Shape {
+        let color = self.super_shape.color.unwrap_or(default_color(ctx));
+        let gap = ctx.meta.canvas_to_screen_size(PINBOARD_OUTLINE_GAP);
+        let stroke = Stroke::new(ctx.meta.canvas_to_screen_size(1.0), color);
+        if self.card.is_some() {
+            let rect = self.card_rect();
+            let screen_rect = Rect::from_min_max(
+                ctx.meta.canvas_to_screen_pos(rect.min),
+                ctx.meta.canvas_to_screen_pos(rect.max),
+            );
+            Shape::rect_stroke(
+                screen_rect.translate(Vec2::splat(gap)),
+                ctx.meta.canvas_to_screen_size(CARD_PADDING),
+                stroke,
+            )
+        } else {
+            Shape::circle_stroke(
+                ctx.meta.canvas_to_screen_pos(self.super_shape.pos),
+                ctx.meta.canvas_to_screen_size(self.super_shape.radius) + gap,
+                stroke,
+            )
+        }
+    }
+
+    fn card_shapes(&self, snippet: &str, ctx: &DrawContext) -> Vec<Shape> {
+        let style = if self.super_shape.selected || self.super_shape.dragged {
+            ctx.ctx.style().visuals.widgets.active
+        } else {
+            ctx.ctx.style().visuals.widgets.inactive
+        };
+        // The node color is dimmed already, the colors taken from the style are not
+        let opacity = match self.visibility {
+            Visibility::Dimmed => DIMMED_OPACITY,
+            _ => 1.0,
+        };
+        let text_color = style.fg_stroke.color.gamma_multiply(opacity);
+        let color = self.super_shape.color.unwrap_or(text_color);
+
+        let rect = self.card_rect();
+        let screen_rect = Rect::from_min_max(
+            ctx.meta.canvas_to_screen_pos(rect.min),
+            ctx.meta.canvas_to_screen_pos(rect.max),
+        );
+        let padding = ctx.meta.canvas_to_screen_size(CARD_PADDING);
+        let font_size = ctx.meta.canvas_to_screen_size(8.0);
+        let inner = screen_rect.shrink(padding);
+
+        let mut res = vec![Shape::rect_filled(
+            screen_rect,
+            padding,
+            ctx.ctx
+                .style()
+                .visuals
+                .extreme_bg_color
+                .gamma_multiply(opacity),
+        )];
+        res.push(Shape::rect_stroke(
+            screen_rect,
+            padding,
+            Stroke::new(ctx.meta.canvas_to_screen_size(1.0), color),
+        ));
+
+        let title = ctx.ctx.fonts(|f| {
+            f.layout(
+                self.super_shape.label_text.clone(),
+                FontId::new(font_size, FontFamily::Proportional),
+                color,
+                inner.width(),
+            )
+        });
+        let title_height = title.size().y;
+        res.push(TextShape::new(inner.min, title, color).into());
+
+        let body = ctx.ctx.fonts(|f| {
+            f.layout(
+                snippet.to_string(),
+                FontId::new(font_size * 0.9, FontFamily::Monospace),
+                text_color,
+                inner.width(),
+            )
+        });
+        // Text doesn't get clipped by itself, so only draw the body if it fits
+        if title_height + body.size().y <= inner.height() {
+            res.push(
+                TextShape::new(
+                    inner.min + Vec2::new(0.0, title_height + padding / 2.0),
+                    body,
+                    text_color,
+                )
+                .into(),
+            );
+        }
+        res
+    }
+
+    // The image fitted into the thumbnail box with an outline, `None` until the texture is loaded
+    // or if it cannot be loaded
+    fn thumbnail_shapes(&self, uri: &str, ctx: &DrawContext) -> Option<Vec<Shape>> {
+        let texture = match ctx.ctx.try_load_texture(
+            uri,
+            egui::TextureOptions::LINEAR,
+            egui::SizeHint::default(),
+        ) {
+            Ok(egui::load::TexturePoll::Ready { texture }) => texture,
+            // Broken files are reported by the health check, not on every frame
+            Ok(egui::load::TexturePoll::Pending { .. }) | Err(_) => return None,
+        };
+        let color = self.super_shape.color.unwrap_or(default_color(ctx));
+        let scale = (THUMBNAIL_SIZE.x / texture.size.x).min(THUMBNAIL_SIZE.y / texture.size.y);
+        let rect = Rect::from_center_size(self.super_shape.pos, texture.size * scale);
+        let screen_rect = Rect::from_min_max(
+            ctx.meta.canvas_to_screen_pos(rect.min),
+            ctx.meta.canvas_to_screen_pos(rect.max),
+        );
+        let tint = match self.visibility {
+            Visibility::Dimmed => Color32::WHITE.gamma_multiply(DIMMED_OPACITY),
+            _ => Color32::WHITE,
+        };
+        let width = if self.super_shape.selected || self.super_shape.dragged {
+            2.0
+        } else {
+            1.0
+        };
+        let mut res = vec![
+            Shape::image(
+                texture.id,
+                screen_rect,
+                Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                tint,
+            ),
+            Shape::rect_stroke(
+                screen_rect,
+                0.0,
+                Stroke::new(ctx.meta.canvas_to_screen_size(width), color),
+            ),
+        ];
+        if ctx.style.labels_always() || self.super_shape.selected || self.super_shape.dragged {
+            let galley = ctx.ctx.fonts(|f| {
+                f.layout_no_wrap(
+                    self.super_shape.label_text.clone(),
+                    FontId::new(ctx.meta.canvas_to_screen_size(8.0), FontFamily::Monospace),
+                    color,
+                )
+            });
+            let pos = Pos2::new(
+                screen_rect.center().x - galley.size().x / 2.0,
+                screen_rect.max.y + ctx.meta.canvas_to_screen_size(2.0),
+            );
+            res.push(TextShape::new(pos, galley, color).into());
+        }
+        Some(res)
+    }
 }
 
-impl<E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<Option<Blob>, E, Ty, Ix> for MyNodeShape {
+impl<E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<NodeData, E, Ty, Ix> for MyNodeShape {
     fn closest_boundary_point(&self, dir: egui::Vec2) -> egui::Pos2 {
+        if let Some(size) = self.box_size() {
+            // Scale the direction so that it reaches the closest side of the box
+            let half = size / 2.0;
+            let dir = dir.normalized();
+            let t = (half.x / dir.x.abs()).min(half.y / dir.y.abs());
+            return self.super_shape.pos + dir * t;
+        }
         <DefaultNodeShape as DisplayNode<Blob, E, Ty, Ix>>::closest_boundary_point(
             &self.super_shape,
             dir,
@@ -196,56 +1302,220 @@ impl<E: Clone, Ty: EdgeType, Ix: IndexType> DisplayNode<Option<Blob>, E, Ty, Ix>
     }
 
     fn shapes(&mut self, ctx: &DrawContext) -> Vec<egui::Shape> {
-        <DefaultNodeShape as DisplayNode<Blob, E, Ty, Ix>>::shapes(&mut self.super_shape, ctx)
+        if self.visibility == Visibility::Hidden {
+            return Vec::new();
+        }
+        if self.super_shape.color.is_none()
+            && !self.super_shape.selected
+            && !self.super_shape.dragged
+        {
+            self.super_shape.color = Some(default_color(ctx));
+        }
+        self.super_shape.color = self
+            .super_shape
+            .color
+            .map(|c| faded(adjusted_color(c, ctx), self.modified, ctx));
+        if self.visibility == Visibility::Dimmed {
+            self.super_shape.color = self
+                .super_shape
+                .color
+                .map(|c| c.gamma_multiply(DIMMED_OPACITY));
+        }
+        let mut res = Vec::new();
+        if self.pinboard {
+            res.push(self.pinboard_outline(ctx));
+        }
+        if let Some(snippet) = &self.card {
+            res.extend(self.card_shapes(snippet, ctx));
+        } else if let Some(shapes) = self
+            .thumbnail
+            .as_ref()
+            .and_then(|uri| self.thumbnail_shapes(uri, ctx))
+        {
+            res.extend(shapes);
+        } else {
+            res.extend(<DefaultNodeShape as DisplayNode<Blob, E, Ty, Ix>>::shapes(
+                &mut self.super_shape,
+                ctx,
+            ));
+        }
+        res
     }
 
     fn is_inside(&self, pos: egui::Pos2) -> bool {
+        // Hidden nodes cannot be interacted with
+        if self.visibility == Visibility::Hidden {
+            return false;
+        }
+        if let Some(size) = self.box_size() {
+            return Rect::from_center_size(self.super_shape.pos, size).contains(pos);
+        }
         <DefaultNodeShape as DisplayNode<Blob, E, Ty, Ix>>::is_inside(&self.super_shape, pos)
     }
 }
 
-impl From<NodeProps<Option<Blob>>> for MyNodeShape {
-    fn from(node_props: NodeProps<Option<Blob>>) -> Self {
-        let color = node_props.payload.as_ref().map(|b| b.color()).flatten();
+impl From<NodeProps<NodeData>> for MyNodeShape {
+    fn from(node_props: NodeProps<NodeData>) -> Self {
+        let color = node_props.payload.blob.as_ref().and_then(|b| b.color());
+        let card = node_props
+            .payload
+            .card
+            .then(|| {
+                node_props
+                    .payload
+                    .blob
+                    .as_ref()
+                    .map(|b| b.snippet().unwrap_or_default().to_string())
+            })
+            .flatten();
+        let pinboard = node_props
+            .payload
+            .blob
+            .as_ref()
+            .is_some_and(|b| *b.ty() == BlobType::PinboardGraph);
+        let thumbnail = node_props
+            .payload
+            .blob
+            .as_ref()
+            .filter(|b| card.is_none() && *b.ty() == BlobType::File && is_image(b.path()))
+            .and_then(|b| std::path::absolute(b.path()).ok())
+            .map(|p| format!("file://{}", p.display()));
+        let modified = node_props.payload.modified;
+        let visibility = node_props.payload.visibility;
         let mut super_shape = DefaultNodeShape::from(node_props);
         super_shape.color = color;
-        Self { super_shape }
+        Self {
+            super_shape,
+            card,
+            thumbnail,
+            pinboard,
+            modified,
+            visibility,
+        }
     }
 }
 
+// Opacity of the pill behind edge labels, edges stay faintly visible through it
+const LABEL_BACKGROUND_OPACITY: f32 = 0.8;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MyEdgeShape {
     super_shape: DefaultEdgeShape,
+    arrow: Arrow,
+    modified: Option<SystemTime>,
+    // The connection carries a comment blob, marked with a dot in the middle of the edge
+    commented: bool,
 }
 
 impl From<EdgeProps<Conn>> for MyEdgeShape {
     fn from(edge: EdgeProps<Conn>) -> Self {
         let color = edge.payload.relation.color();
+        let arrow = edge.payload.arrow;
+        let modified = edge.payload.modified;
+        let commented = edge.payload.comment.is_some();
         let mut super_shape = DefaultEdgeShape::from(edge);
         super_shape.color = color;
-        Self { super_shape }
+        if arrow != Arrow::None {
+            super_shape.directed = Some(true);
+        }
+        Self {
+            super_shape,
+            arrow,
+            modified,
+            commented,
+        }
     }
 }
 
-impl<N: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, Conn, Ty, Ix>>
-    DisplayEdge<N, Conn, Ty, Ix, D> for MyEdgeShape
+// Radius (in canvas coordinates) of the dot marking edges with a comment
+const COMMENT_MARKER_RADIUS: f32 = 3.5;
+
+impl MyEdgeShape {
+    // Dot in the middle of the edge line, the line being the first line or curve among the shapes
+    fn comment_marker(&self, shapes: &[Shape], ctx: &DrawContext) -> Option<Shape> {
+        let center = shapes.iter().find_map(|s| match s {
+            Shape::LineSegment { points, .. } => Some(points[0].lerp(points[1], 0.5)),
+            Shape::CubicBezier(curve) => {
+                let points = curve.flatten(None);
+                points.get(points.len() / 2).copied()
+            }
+            _ => None,
+        })?;
+        Some(Shape::circle_filled(
+            center,
+            ctx.meta.canvas_to_screen_size(COMMENT_MARKER_RADIUS),
+            self.super_shape.color.unwrap_or_else(|| default_color(ctx)),
+        ))
+    }
+}
+
+impl<Ty: EdgeType, Ix: IndexType, D: DisplayNode<NodeData, Conn, Ty, Ix>>
+    DisplayEdge<NodeData, Conn, Ty, Ix, D> for MyEdgeShape
 {
     fn shapes(
         &mut self,
-        start: &Node<N, Conn, Ty, Ix, D>,
-        end: &Node<N, Conn, Ty, Ix, D>,
+        start: &Node<NodeData, Conn, Ty, Ix, D>,
+        end: &Node<NodeData, Conn, Ty, Ix, D>,
         ctx: &DrawContext,
     ) -> Vec<egui::Shape> {
-        self.super_shape.shapes(start, end, ctx)
+        // Edges are as visible as the less visible of their nodes
+        let visibility = start.payload().visibility.max(end.payload().visibility);
+        if visibility == Visibility::Hidden {
+            return Vec::new();
+        }
+        if self.super_shape.color.is_none() && !self.super_shape.selected {
+            self.super_shape.color = Some(default_color(ctx));
+        }
+        self.super_shape.color = self
+            .super_shape
+            .color
+            .map(|c| faded(adjusted_color(c, ctx), self.modified, ctx));
+        if visibility == Visibility::Dimmed {
+            self.super_shape.color = self
+                .super_shape
+                .color
+                .map(|c| c.gamma_multiply(DIMMED_OPACITY));
+        }
+        // The tip is always drawn at the end node
+        let mut shapes = match self.arrow {
+            Arrow::Backward => self.super_shape.shapes(end, start, ctx),
+            Arrow::None | Arrow::Forward => self.super_shape.shapes(start, end, ctx),
+        };
+        if self.commented {
+            if let Some(marker) = self.comment_marker(&shapes, ctx) {
+                shapes.push(marker);
+            }
+        }
+        if !ctx.style.edge_label_background() {
+            return shapes;
+        }
+        let fill = ctx
+            .style
+            .background()
+            .unwrap_or(ctx.ctx.style().visuals.window_fill)
+            .gamma_multiply(LABEL_BACKGROUND_OPACITY);
+        let mut res = Vec::with_capacity(shapes.len() + 1);
+        for shape in shapes {
+            // Labels are the only text among edge shapes
+            if let Shape::Text(text) = &shape {
+                let rect = text.visual_bounding_rect().expand(2.0);
+                res.push(Shape::rect_filled(rect, rect.height() / 2.0, fill));
+            }
+            res.push(shape);
+        }
+        res
     }
 
     fn is_inside(
         &self,
-        start: &egui_graphs::Node<N, Conn, Ty, Ix, D>,
-        end: &egui_graphs::Node<N, Conn, Ty, Ix, D>,
+        start: &egui_graphs::Node<NodeData, Conn, Ty, Ix, D>,
+        end: &egui_graphs::Node<NodeData, Conn, Ty, Ix, D>,
         pos: egui::Pos2,
     ) -> bool {
-        self.super_shape.is_inside(start, end, pos)
+        let hidden = [start, end]
+            .iter()
+            .any(|n| n.payload().visibility == Visibility::Hidden);
+        !hidden && self.super_shape.is_inside(start, end, pos)
     }
 }
 
@@ -253,27 +1523,385 @@ impl<N: Clone, Ty: EdgeType, Ix: IndexType, D: DisplayNode<N, Conn, Ty, Ix>>
 mod tests {
     use super::*;
 
+    #[test]
+    fn image_extensions() {
+        assert!(is_image(Path::new("figures/plot.PNG")));
+        assert!(is_image(Path::new("screenshot.jpeg")));
+        assert!(!is_image(Path::new("paper.pdf")));
+        assert!(!is_image(Path::new("png")));
+    }
+
+    #[test]
+    fn hash_cache() {
+        let cache = Mutex::default();
+        let path = Path::new("./tests/misc/foo.txt");
+        let (hash, _) = hash_file_in(&cache, path).unwrap();
+        let file =
+            std::env::temp_dir().join(format!("pinlab_hash_cache_{}.json", uuid::Uuid::new_v4()));
+        write_hash_cache(&cache, &file).unwrap();
+        let cache = Mutex::default();
+        read_hash_cache(&cache, &file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        // The hash is taken from the cache without reading the file
+        assert_eq!(hash_file_in(&cache, path).unwrap(), (hash, None));
+    }
+
+    #[tokio::test]
+    async fn file_snippet() {
+        let blob = Blob::new(BlobType::File, PathBuf::from("./tests/misc/foo.txt"))
+            .await
+            .unwrap();
+        assert_eq!(blob.snippet().as_deref(), Some("bar"));
+        // Snippets of files are read again rather than saved
+        let mut blob: Blob = serde_json::from_str(&serde_json::to_string(&blob).unwrap()).unwrap();
+        assert_eq!(blob.snippet(), None);
+        blob.load_snippet();
+        assert_eq!(blob.snippet().as_deref(), Some("bar"));
+        // Content read partially keeps the characters before a cut one
+        assert_eq!(
+            Blob::snippet_of(&BlobType::File, &"ab€".as_bytes()[..4]).as_deref(),
+            Some("ab")
+        );
+    }
+
     #[test]
     fn updating_blob_multi_match() {
         let hash = blake3::hash(&std::fs::read(&Path::new("./tests/misc/foo.txt")).unwrap());
-        assert!(Blob::walk(Path::new("./tests/misc/multi_match"), &hash).is_err());
+        assert_eq!(
+            Blob::walk(
+                Path::new("./tests/misc/multi_match"),
+                &hash,
+                DEFAULT_MAX_SEARCH_SIZE
+            )
+            .len(),
+            2
+        );
+
+        let mut blob = Blob {
+            ty: BlobType::File,
+            path: PathBuf::from("./tests/misc/multi_match/bar/gone"),
+            hash,
+            snippet: None,
+            title: None,
+            note: None,
+        };
+        let err = blob
+            .update(
+                &SearchScope {
+                    roots: vec![PathBuf::from("./tests/misc/multi_match")],
+                    ..Default::default()
+                },
+                true,
+            )
+            .unwrap_err();
+        let candidates = &err.downcast_ref::<MultipleMatches>().unwrap().0;
+        // The file sharing more directories with the old path comes first
+        assert!(candidates[0].ends_with("bar/foo2"));
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(
+            relative_path(
+                Path::new("/project/notes/a.md"),
+                Path::new("/project/boards")
+            ),
+            Some(PathBuf::from("../notes/a.md"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/project/a.md"), Path::new("/project")),
+            Some(PathBuf::from("a.md"))
+        );
+        // Nothing but the root in common
+        assert_eq!(
+            relative_path(Path::new("/usr/a.md"), Path::new("/project")),
+            None
+        );
+        assert_eq!(
+            resolve_path(Path::new("/project/boards"), Path::new("../notes/./a.md")),
+            PathBuf::from("/project/notes/a.md")
+        );
+        // Absolute paths stay as they are
+        assert_eq!(
+            resolve_path(Path::new("/project"), Path::new("/usr/a.md")),
+            PathBuf::from("/usr/a.md")
+        );
+    }
+
+    #[test]
+    fn blob_relative_to_base() {
+        let hash = blake3::hash(b"");
+        let blob = |path: &str| Blob {
+            ty: BlobType::File,
+            path: PathBuf::from(path),
+            hash,
+            snippet: None,
+            title: None,
+            note: None,
+        };
+        assert_eq!(
+            blob("/project/notes/a.md").relative_to(Path::new("/project")),
+            Some(PathBuf::from("notes/a.md"))
+        );
+        assert_eq!(
+            blob("/elsewhere/b.md").relative_to(Path::new("/project")),
+            None
+        );
+        // Relative paths are resolved against the working directory first
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            blob("tests/misc/foo.txt").relative_to(&cwd.join("tests")),
+            Some(PathBuf::from("misc/foo.txt"))
+        );
+    }
+
+    #[test]
+    fn link_blob() {
+        let mut blob = Blob::url("https://example.com/", Some(" ".to_string()));
+        assert_eq!(blob.link(), Some("https://example.com/"));
+        assert_eq!(blob.name(), "https://example.com/");
+        assert!(!blob.is_local());
+        // Links are never looked up on disk
+        let scope = SearchScope {
+            roots: vec![PathBuf::from("./tests/misc")],
+            ..Default::default()
+        };
+        blob.update(&scope, true).unwrap();
+        assert_eq!(blob.path(), Path::new("https://example.com/"));
+
+        let blob = Blob::url("https://example.com/", Some("Example".to_string()));
+        assert_eq!(blob.name(), "Example");
+    }
+
+    #[test]
+    fn note_blob() {
+        let mut blob = Blob::note(String::new());
+        assert_eq!(blob.name(), "Note");
+        assert!(!blob.is_local());
+        blob.set_note("\n  Idea  \nmore details".to_string());
+        assert_eq!(blob.name(), "Idea");
+        assert_eq!(blob.snippet().as_deref(), Some("\n  Idea  \nmore details"));
+        assert_eq!(*blob.hash(), blake3::hash(b"\n  Idea  \nmore details"));
+        // The content survives a round trip through the pinboard file
+        let blob: Blob = serde_json::from_str(&serde_json::to_string(&blob).unwrap()).unwrap();
+        assert_eq!(blob.note_text(), Some("\n  Idea  \nmore details"));
+        assert_eq!(blob.snippet().as_deref(), Some("\n  Idea  \nmore details"));
     }
 
     #[test]
     fn blob_hidden_match() {
         let hash = blake3::hash(&std::fs::read(&Path::new("./tests/misc/foo.txt")).unwrap());
-        // We cannot do assert_eq because of anyhow::Error doesn't implement PartialEq
-        assert!(Blob::walk(Path::new("./tests/misc/hidden_match"), &hash)
-            .map(|o| o.is_none())
-            .unwrap());
+        assert!(Blob::walk(
+            Path::new("./tests/misc/hidden_match"),
+            &hash,
+            DEFAULT_MAX_SEARCH_SIZE
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn blob_ignored_match() {
+        let hash = blake3::hash(&std::fs::read(&Path::new("./tests/misc/foo.txt")).unwrap());
+        assert!(Blob::walk(
+            Path::new("./tests/misc/ignored_match"),
+            &hash,
+            DEFAULT_MAX_SEARCH_SIZE
+        )
+        .is_empty());
     }
 
     #[test]
     fn blob_match() {
         let hash = blake3::hash(&std::fs::read(&Path::new("./tests/misc/foo.txt")).unwrap());
-        // We cannot do assert_eq because of anyhow::Error doesn't implement PartialEq
-        assert!(Blob::walk(Path::new("./tests/misc/match"), &hash)
-            .map(|o| o.is_some())
-            .unwrap());
+        let matches = Blob::walk(
+            Path::new("./tests/misc/match"),
+            &hash,
+            DEFAULT_MAX_SEARCH_SIZE,
+        );
+        assert_eq!(matches.len(), 1);
+        // Files over the size limit are never hashed
+        assert!(Blob::walk(Path::new("./tests/misc/match"), &hash, 0).is_empty());
+    }
+
+    #[test]
+    fn grid_arrangement() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let nodes: Vec<_> = (0..5)
+            .map(|i| graph.add_node_with_location(NodeData::default(), Pos2::new(i as f32, 10.0)))
+            .collect();
+        let origins = arrange_grid(&mut graph, &nodes, 0.5);
+        assert_eq!(origins.len(), 5);
+        let cell = GRID_CELL * 0.5;
+        // Five nodes make three columns
+        assert_eq!(
+            graph.node(nodes[0]).unwrap().location(),
+            Pos2::new(0.0, 10.0)
+        );
+        assert_eq!(
+            graph.node(nodes[4]).unwrap().location(),
+            Pos2::new(cell, 10.0 + cell)
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let locations = [(30.0, 0.0), (0.0, 50.0), (100.0, 20.0)];
+        let nodes: Vec<_> = locations
+            .iter()
+            .map(|(x, y)| graph.add_node_with_location(NodeData::default(), Pos2::new(*x, *y)))
+            .collect();
+        let loc = |g: &PinboardGraph, i: usize| g.node(nodes[i]).unwrap().location();
+
+        assert_eq!(align(&mut graph, &nodes, Alignment::Left).len(), 3);
+        assert!((0..3).all(|i| loc(&graph, i).x == 0.0));
+        assert_eq!(loc(&graph, 1).y, 50.0);
+
+        // Nodes keep their vertical order while spaced evenly
+        align(&mut graph, &nodes, Alignment::DistributeVertically);
+        assert_eq!(
+            (0..3).map(|i| loc(&graph, i).y).collect::<Vec<_>>(),
+            vec![0.0, 50.0, 25.0]
+        );
+
+        align(&mut graph, &nodes, Alignment::Top);
+        assert!((0..3).all(|i| loc(&graph, i).y == 0.0));
+    }
+
+    #[test]
+    fn grid_snapping() {
+        assert_eq!(
+            snap_to_grid(Pos2::new(29.0, -11.0), 20.0),
+            Pos2::new(20.0, -20.0)
+        );
+        assert_eq!(snap_to_grid(Pos2::new(10.0, 0.0), 20.0).y, 0.0);
+    }
+
+    #[test]
+    fn force_layout() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let a = graph.add_node_with_location(NodeData::default(), Pos2::new(0.0, 0.0));
+        let b = graph.add_node_with_location(NodeData::default(), Pos2::new(1000.0, 0.0));
+        let c = graph.add_node_with_location(NodeData::default(), Pos2::new(1.0, 0.0));
+        graph.add_edge(a, b, Conn::new(Relation::Related));
+        let dist = |g: &PinboardGraph, x, y| {
+            (g.node(x).unwrap().location() - g.node(y).unwrap().location()).length()
+        };
+        for i in 0..200 {
+            force_step(&mut graph, 1.0, 50.0 * (1.0 - i as f32 / 200.0));
+        }
+        // Connected nodes are pulled together, crowded unconnected ones are pushed apart
+        assert!(dist(&graph, a, b) < 1000.0);
+        assert!(dist(&graph, a, c) > 1.0);
+    }
+
+    #[test]
+    fn directed_relations() {
+        assert!(Conn::new(Relation::Progress).arrow == Arrow::Forward);
+        assert!(Conn::new(Relation::Related).arrow.is_none());
+        // Older connections without an arrowhead stay undirected
+        let conn: Conn = serde_json::from_str(r#"{"relation":"Progress"}"#).unwrap();
+        assert!(conn.arrow.is_none());
+    }
+
+    #[test]
+    fn age_fade() {
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        let fade = egui_graphs::AgeFade {
+            threshold: day * 7,
+            span: day * 10,
+            min_opacity: 0.2,
+        };
+        assert_eq!(fade.opacity(day), 1.0);
+        assert!((fade.opacity(day * 12) - 0.6).abs() < 1e-4);
+        assert!((fade.opacity(day * 100) - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn contrast_adjustment() {
+        let bg = Color32::from_rgb(250, 250, 240);
+        assert!(contrast_ratio(Color32::YELLOW, bg) < MIN_CONTRAST);
+        assert!(contrast_ratio(with_contrast(Color32::YELLOW, bg), bg) >= MIN_CONTRAST);
+        // Colors with enough contrast are kept
+        assert_eq!(with_contrast(Color32::BLACK, bg), Color32::BLACK);
+    }
+
+    #[test]
+    fn cached_hash() {
+        let path = Path::new("./tests/misc/foo.txt");
+        let (hash, _) = hash_file(path).unwrap();
+        assert_eq!(hash, blake3::hash(&std::fs::read(path).unwrap()));
+        // The file hasn't changed, so it's not read again
+        let (cached, content) = hash_file(path).unwrap();
+        assert_eq!(cached, hash);
+        assert!(content.is_none());
+    }
+
+    #[test]
+    fn compact_graph() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let a = graph.add_node_with_location(NodeData::default(), egui::Pos2::new(1.0, 2.0));
+        let b = graph.add_node(NodeData::default());
+        let c = graph.add_node_with_location(NodeData::default(), egui::Pos2::new(3.0, 4.0));
+        graph.add_edge_with_label(a, c, Conn::new(Relation::Insight), "foo".to_string());
+        graph.remove_node(b);
+
+        let compacted = compact(&graph);
+        let ids: Vec<usize> = compacted.nodes_iter().map(|(id, _)| id.index()).collect();
+        assert_eq!(ids, vec![0, 1]);
+        let (_, e) = compacted.edges_iter().next().unwrap();
+        assert_eq!(e.label(), "foo");
+        let (start, end) = compacted.edge_endpoints(e.id()).unwrap();
+        assert_eq!(
+            compacted.node(start).unwrap().location(),
+            egui::Pos2::new(1.0, 2.0)
+        );
+        assert_eq!(
+            compacted.node(end).unwrap().location(),
+            egui::Pos2::new(3.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn legacy_node_data() {
+        let data = serde_json::from_str::<NodeData>("null").unwrap();
+        assert!(data.blob.is_none());
+        assert!(!data.card);
+
+        let blob = serde_json::to_string(&Blob {
+            ty: BlobType::File,
+            path: PathBuf::from("foo.txt"),
+            hash: blake3::hash(b"foo"),
+            snippet: None,
+            title: None,
+            note: None,
+        })
+        .unwrap();
+        let data = serde_json::from_str::<NodeData>(&blob).unwrap();
+        assert!(data.blob.is_some());
+        assert!(!data.card);
+
+        let data = serde_json::from_str::<NodeData>(
+            &serde_json::to_string(&NodeData {
+                blob: None,
+                card: true,
+                description: "foo".to_string(),
+                aliases: vec!["bar".to_string()],
+                modified: Some(SystemTime::UNIX_EPOCH),
+                tags: vec!["baz".to_string()],
+                label: Some("qux".to_string()),
+                visibility: Visibility::Hidden,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(data.card);
+        assert_eq!(data.description, "foo");
+        assert!(data.alias_matches("ba"));
+        assert!(!data.alias_matches("foo"));
+        assert_eq!(data.modified, Some(SystemTime::UNIX_EPOCH));
+        assert_eq!(data.tags, vec!["baz".to_string()]);
+        assert_eq!(data.label.as_deref(), Some("qux"));
+        assert_eq!(data.visibility, Visibility::Shown);
     }
 }
@@ -0,0 +1,241 @@
+// This module contains the health check run in the background when a pinboard is opened.
+
+use crate::graph::{self, Blob, BlobType, PinboardGraph, SearchScope};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// Problems found in a pinboard
+#[derive(Clone, Default)]
+pub struct HealthReport {
+    // Missing blobs along with the unique file matching their hash, which they can be repaired to
+    pub repairable: Vec<(PathBuf, PathBuf)>,
+    // Missing blobs without a unique file matching their hash
+    pub lost: Vec<PathBuf>,
+    // Blobs whose file content no longer matches the recorded hash
+    pub drifted: Vec<PathBuf>,
+    // Blobs with uncommitted or untracked changes in their git repository
+    pub uncommitted: Vec<PathBuf>,
+    // Labels of nodes without any edge
+    pub isolated: Vec<String>,
+    // Labels of nodes referring to the pinboard itself
+    pub self_links: Vec<String>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.repairable.is_empty()
+            && self.lost.is_empty()
+            && self.drifted.is_empty()
+            && self.uncommitted.is_empty()
+            && self.isolated.is_empty()
+            && self.self_links.is_empty()
+    }
+
+    // One line summary like "3 lost links, 2 isolated nodes"
+    pub fn summary(&self) -> String {
+        [
+            (self.repairable.len(), "repairable link"),
+            (self.lost.len(), "lost link"),
+            (self.drifted.len(), "drifted file"),
+            (self.uncommitted.len(), "uncommitted file"),
+            (self.isolated.len(), "isolated node"),
+            (self.self_links.len(), "self-referential link"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, what)| format!("{} {}{}", n, what, if n > 1 { "s" } else { "" }))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+// Everything the health check needs from a pinboard, so that the file system part can be done
+// away from the UI thread
+pub struct HealthCheck {
+    blobs: Vec<Blob>,
+    check_git: bool,
+    // Where to look for files matching missing blobs, missing blobs are lost without any root
+    scope: SearchScope,
+    report: HealthReport,
+}
+
+// Whether git reports changes of the file, files outside of git repositories or without git
+// installed count as clean
+fn is_uncommitted(path: &Path) -> bool {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain", "--untracked-files=all", "--"])
+        .arg(name)
+        .output()
+        .is_ok_and(|o| o.status.success() && !o.stdout.is_empty())
+}
+
+impl HealthCheck {
+    // Collect blobs of the graph and do the checks which don't touch the file system
+    pub fn collect(
+        graph: &PinboardGraph,
+        own_path: Option<&Path>,
+        scope: SearchScope,
+        check_git: bool,
+    ) -> Self {
+        let own_path = own_path.and_then(|p| p.canonicalize().ok());
+        let mut report = HealthReport::default();
+        for (id, n) in graph.nodes_iter() {
+            if graph.edges_num(id) == 0 {
+                report.isolated.push(n.label());
+            }
+        }
+        let mut blobs: Vec<Blob> = graph
+            .edges_iter()
+            .filter_map(|(_, e)| e.payload().comment.clone())
+            .collect();
+        // Links aren't checked, opening them tells whether they are still alive
+        blobs.retain(Blob::is_local);
+        for (_, n) in graph.nodes_iter() {
+            if let Some(blob) = &n.payload().blob {
+                if *blob.ty() == BlobType::PinboardGraph
+                    && own_path.is_some()
+                    && blob.path().canonicalize().ok() == own_path
+                {
+                    report.self_links.push(n.label());
+                }
+                if blob.is_local() {
+                    blobs.push(blob.clone());
+                }
+            }
+        }
+        Self {
+            blobs,
+            check_git,
+            scope,
+            report,
+        }
+    }
+
+    // Check blobs against the file system, this should be spawned as blocking
+    pub fn run(self) -> HealthReport {
+        let mut report = self.report;
+        let mut seen = HashSet::new();
+        for blob in self.blobs {
+            // Blobs shared by several nodes are reported once
+            if !seen.insert(blob.path().clone()) {
+                continue;
+            }
+            match graph::hash_file(blob.path()) {
+                Ok((hash, _)) => {
+                    if hash != *blob.hash() {
+                        report.drifted.push(blob.path().clone());
+                    }
+                    if self.check_git && is_uncommitted(blob.path()) {
+                        report.uncommitted.push(blob.path().clone());
+                    }
+                }
+                Err(_) => {
+                    let matches = blob.find_matches(&self.scope);
+                    if let [found] = matches.as_slice() {
+                        report.repairable.push((blob.path().clone(), found.clone()));
+                    } else {
+                        report.lost.push(blob.path().clone());
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(roots: Vec<PathBuf>) -> SearchScope {
+        SearchScope {
+            roots,
+            ..Default::default()
+        }
+    }
+    use crate::graph::{Conn, NodeData, Relation};
+    use petgraph::stable_graph::StableGraph;
+
+    #[tokio::test]
+    async fn health_check() {
+        let path = PathBuf::from("./tests/misc/foo.txt");
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let a = graph.add_node_with_label(
+            NodeData {
+                blob: Some(Blob::new(BlobType::File, path.clone()).await.unwrap()),
+                ..Default::default()
+            },
+            "a".to_string(),
+        );
+        let b = graph.add_node_with_label(NodeData::default(), "b".to_string());
+        graph.add_node_with_label(
+            NodeData {
+                blob: Some(
+                    Blob::new(BlobType::PinboardGraph, path.clone())
+                        .await
+                        .unwrap(),
+                ),
+                ..Default::default()
+            },
+            "c".to_string(),
+        );
+        graph.add_edge(a, b, Conn::new(Relation::Related));
+
+        let report = HealthCheck::collect(&graph, Some(&path), SearchScope::default(), true).run();
+        assert!(report.repairable.is_empty());
+        assert!(report.lost.is_empty());
+        assert!(report.drifted.is_empty());
+        // The test file is committed
+        assert!(report.uncommitted.is_empty());
+        assert_eq!(report.isolated, vec!["c".to_string()]);
+        assert_eq!(report.self_links, vec!["c".to_string()]);
+        assert_eq!(report.summary(), "1 isolated node, 1 self-referential link");
+    }
+
+    #[tokio::test]
+    async fn missing_blobs() {
+        let mut blob = Blob::new(BlobType::File, PathBuf::from("./tests/misc/foo.txt"))
+            .await
+            .unwrap();
+        blob.set_path(PathBuf::from("./tests/misc/match/gone"));
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        graph.add_node(NodeData {
+            blob: Some(blob),
+            ..Default::default()
+        });
+
+        let root = PathBuf::from("./tests/misc/match");
+        let report = HealthCheck::collect(&graph, None, scope(vec![root]), false).run();
+        assert_eq!(report.repairable.len(), 1);
+        assert!(report.repairable[0].1.ends_with("foo/baz"));
+
+        // Several files match, so the blob cannot be repaired automatically
+        let root = PathBuf::from("./tests/misc/multi_match");
+        let report = HealthCheck::collect(&graph, None, scope(vec![root]), false).run();
+        assert_eq!(report.lost.len(), 1);
+
+        // Roots are searched one after another
+        let roots = vec![
+            PathBuf::from("./tests/misc/nothing_here"),
+            PathBuf::from("./tests/misc/match"),
+        ];
+        let report = HealthCheck::collect(&graph, None, scope(roots), false).run();
+        assert_eq!(report.repairable.len(), 1);
+        // Nothing is searched without roots
+        let report = HealthCheck::collect(&graph, None, SearchScope::default(), false).run();
+        assert_eq!(report.lost.len(), 1);
+    }
+}
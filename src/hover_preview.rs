@@ -0,0 +1,124 @@
+// This module contains previews of files shown when the pointer rests on their nodes. First pages
+// of PDFs are rendered by poppler's `pdftoppm` and cached on disk by the hash of the file.
+
+use crate::graph::{Blob, BlobType};
+use anyhow::{anyhow, Result};
+use blake3::Hash as BlakeHash;
+use egui::Context;
+use log::warn;
+use petgraph::graph::NodeIndex;
+use poll_promise::Promise;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+// How long the pointer has to rest on a node before its preview is shown
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+// Width of rendered pages in pixels
+const PAGE_WIDTH: u32 = 300;
+
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+// Directory storing rendered pages, one per file named after its hash
+fn cache_dir() -> Result<PathBuf> {
+    eframe::storage_dir("Pinlab")
+        .map(|d| d.join("pdf_previews"))
+        .ok_or(anyhow!("cannot determine the preview directory"))
+}
+
+// Render the first page of the PDF to a PNG file unless it has been rendered before
+fn render_first_page(pdf: &Path, hash: &BlakeHash) -> Result<PathBuf> {
+    let dir = cache_dir()?;
+    let stem = dir.join(hash.to_hex().as_str());
+    let png = stem.with_extension("png");
+    if png.try_exists()? {
+        return Ok(png);
+    }
+    std::fs::create_dir_all(&dir)?;
+    // `-singlefile` makes pdftoppm append only the extension to the output name
+    let status = Command::new("pdftoppm")
+        .args(["-png", "-f", "1", "-l", "1", "-singlefile", "-scale-to-x"])
+        .arg(PAGE_WIDTH.to_string())
+        .args(["-scale-to-y", "-1"])
+        .arg(pdf)
+        .arg(&stem)
+        .status()
+        .map_err(|e| anyhow!("cannot run pdftoppm, is poppler installed? {}", e))?;
+    if !status.success() {
+        return Err(anyhow!("pdftoppm failed with {}", status));
+    }
+    Ok(png)
+}
+
+// Node the pointer rests on and previews rendered so far
+#[derive(Default)]
+pub struct HoverPreviews {
+    hovered: Option<(NodeIndex, Instant)>,
+    // Resolves to `None` if the page cannot be rendered
+    rendered: HashMap<BlakeHash, Promise<Option<PathBuf>>>,
+}
+
+impl HoverPreviews {
+    // URI of the image to show for the hovered node, once the pointer has rested on it long enough
+    // and its preview is rendered
+    pub fn poll(
+        &mut self,
+        ctx: &Context,
+        node: Option<NodeIndex>,
+        blob: Option<&Blob>,
+    ) -> Option<String> {
+        let Some((node, blob)) = node
+            .zip(blob)
+            .filter(|(_, b)| *b.ty() == BlobType::File && is_pdf(b.path()))
+        else {
+            self.hovered = None;
+            return None;
+        };
+        let since = match self.hovered {
+            Some((n, since)) if n == node => since,
+            _ => {
+                self.hovered = Some((node, Instant::now()));
+                ctx.request_repaint_after(HOVER_DELAY);
+                return None;
+            }
+        };
+        if since.elapsed() < HOVER_DELAY {
+            ctx.request_repaint_after(HOVER_DELAY - since.elapsed());
+            return None;
+        }
+        let promise = self.rendered.entry(*blob.hash()).or_insert_with(|| {
+            let (path, hash) = (blob.path().clone(), *blob.hash());
+            Promise::spawn_blocking(move || {
+                render_first_page(&path, &hash)
+                    .map_err(|e| warn!("cannot preview {:?}: {}", path, e))
+                    .ok()
+            })
+        });
+        match promise.ready() {
+            Some(png) => png.as_ref().map(|p| format!("file://{}", p.display())),
+            None => {
+                ctx.request_repaint();
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdf_extension() {
+        assert!(is_pdf(Path::new("papers/attention.pdf")));
+        assert!(is_pdf(Path::new("SCAN.PDF")));
+        assert!(!is_pdf(Path::new("notes.md")));
+    }
+}
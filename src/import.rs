@@ -0,0 +1,99 @@
+// This module contains importers of structure from other formats into pinboards.
+
+use crate::graph::{Conn, PinboardGraph, Relation};
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
+
+// Split a CSV line into fields, quoted fields may contain commas and doubled quotes
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// Connect existing nodes matched by their labels, following `source,target,relation` rows. The
+// relation may be omitted, in which case the nodes are simply related. A header row is skipped.
+// Returns the rows (line number and content) that couldn't be imported, including self loops and
+// edges which exist already.
+pub fn import_edges_csv(graph: &mut PinboardGraph, content: &str) -> Vec<(usize, String)> {
+    // Labels shared by several nodes are ambiguous and never matched
+    let mut labels: HashMap<String, Option<NodeIndex>> = HashMap::new();
+    for (id, n) in graph.nodes_iter() {
+        labels
+            .entry(n.label())
+            .and_modify(|e| *e = None)
+            .or_insert(Some(id));
+    }
+
+    let mut unmatched = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = csv_fields(line);
+        if i == 0 && fields.first().map(|f| f.trim().to_lowercase()) == Some("source".to_string()) {
+            continue;
+        }
+        let node = |f: Option<&String>| f.and_then(|f| labels.get(f.trim()).copied().flatten());
+        let relation = match fields.get(2).map(|f| f.trim()) {
+            None | Some("") => Some(Relation::Related),
+            Some(r) => Relation::from_label(r),
+        };
+        match (node(fields.first()), node(fields.get(1)), relation) {
+            (Some(a), Some(b), Some(relation)) if a != b && graph.g.find_edge(a, b).is_none() => {
+                let label = relation.label();
+                graph.add_edge_with_label(a, b, Conn::new(relation), label);
+            }
+            _ => unmatched.push((i + 1, line.to_string())),
+        }
+    }
+    unmatched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::NodeData;
+    use petgraph::stable_graph::StableGraph;
+
+    #[test]
+    fn import_edges() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        graph.add_node_with_label(NodeData::default(), "foo".to_string());
+        graph.add_node_with_label(NodeData::default(), "bar, baz".to_string());
+        graph.add_node_with_label(NodeData::default(), "dup".to_string());
+        graph.add_node_with_label(NodeData::default(), "dup".to_string());
+
+        let unmatched = import_edges_csv(
+            &mut graph,
+            "source,target,relation\n\
+             foo,\"bar, baz\",insight\n\
+             foo,dup,related\n\
+             foo,missing\n\
+             \n\
+             \"bar, baz\",foo\n\
+             foo,foo,unknown\n\
+             foo,\"bar, baz\"\n\
+             foo,foo\n",
+        );
+        assert_eq!(
+            unmatched.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![3, 4, 6, 7, 8, 9]
+        );
+        assert_eq!(graph.edge_count(), 1);
+    }
+}
@@ -0,0 +1,327 @@
+// This module contains the keyboard shortcuts of the app. Every command has a default shortcut,
+// which can be rebound in the keymap section of the config file.
+
+use anyhow::{anyhow, Result};
+use egui::{Key, KeyboardShortcut, Modifiers};
+use log::warn;
+use serde::{
+    de::{value::StrDeserializer, IntoDeserializer},
+    Deserialize, Serialize,
+};
+use std::{collections::HashMap, sync::OnceLock};
+
+// Commands bound to shortcuts, named in snake case in the config file
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Save,
+    SaveAll,
+    Undo,
+    Redo,
+    Rename,
+    AddNode,
+    AddConnectedNode,
+    RelationPicker,
+    SelectAll,
+    DeselectAll,
+    Fit,
+    FitSelection,
+    Switcher,
+}
+
+impl Command {
+    pub const ALL: [Command; 13] = [
+        Command::Save,
+        Command::SaveAll,
+        Command::Undo,
+        Command::Redo,
+        Command::Rename,
+        Command::AddNode,
+        Command::AddConnectedNode,
+        Command::RelationPicker,
+        Command::SelectAll,
+        Command::DeselectAll,
+        Command::Fit,
+        Command::FitSelection,
+        Command::Switcher,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Command::Save => "Save",
+            Command::SaveAll => "Save all",
+            Command::Undo => "Undo",
+            Command::Redo => "Redo",
+            Command::Rename => "Rename",
+            Command::AddNode => "Add node",
+            Command::AddConnectedNode => "Add connected node",
+            Command::RelationPicker => "Pick relation",
+            Command::SelectAll => "Select all nodes",
+            Command::DeselectAll => "Deselect all",
+            Command::Fit => "Fit all",
+            Command::FitSelection => "Fit selection",
+            Command::Switcher => "Quick switcher",
+        }
+    }
+
+    // Command of the snake case name used in the config file
+    pub fn from_name(name: &str) -> Option<Command> {
+        let name: StrDeserializer<serde::de::value::Error> = name.into_deserializer();
+        Command::deserialize(name).ok()
+    }
+
+    // Commands handled by the app before any pinboard gets to see the key press
+    pub fn is_global(self) -> bool {
+        matches!(self, Command::SaveAll | Command::Switcher)
+    }
+
+    fn default_shortcut(self) -> KeyboardShortcut {
+        let (modifiers, key) = match self {
+            Command::Save => (Modifiers::CTRL, Key::S),
+            Command::SaveAll => (Modifiers::CTRL.plus(Modifiers::ALT), Key::S),
+            Command::Undo => (Modifiers::CTRL, Key::Z),
+            Command::Redo => (Modifiers::CTRL.plus(Modifiers::SHIFT), Key::Z),
+            Command::Rename => (Modifiers::NONE, Key::F2),
+            Command::AddNode => (Modifiers::CTRL, Key::N),
+            Command::AddConnectedNode => (Modifiers::CTRL.plus(Modifiers::SHIFT), Key::N),
+            Command::RelationPicker => (Modifiers::NONE, Key::R),
+            Command::SelectAll => (Modifiers::CTRL, Key::A),
+            Command::DeselectAll => (Modifiers::NONE, Key::Escape),
+            Command::Fit => (Modifiers::NONE, Key::F),
+            Command::FitSelection => (Modifiers::NONE, Key::Z),
+            Command::Switcher => (Modifiers::CTRL, Key::P),
+        };
+        KeyboardShortcut::new(modifiers, key)
+    }
+}
+
+// Parse shortcuts written like "Ctrl+Shift+Z", modifiers are case insensitive
+pub fn parse_shortcut(s: &str) -> Result<KeyboardShortcut> {
+    let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+    let key = parts
+        .pop()
+        .filter(|k| !k.is_empty())
+        .ok_or(anyhow!("no key"))?;
+    let key = Key::from_name(key).ok_or(anyhow!("unknown key {}", key))?;
+    let mut modifiers = Modifiers::NONE;
+    for m in parts {
+        modifiers = modifiers.plus(match m.to_lowercase().as_str() {
+            "ctrl" => Modifiers::CTRL,
+            "shift" => Modifiers::SHIFT,
+            "alt" => Modifiers::ALT,
+            "cmd" => Modifiers::COMMAND,
+            _ => return Err(anyhow!("unknown modifier {}", m)),
+        });
+    }
+    Ok(KeyboardShortcut::new(modifiers, key))
+}
+
+// Number of modifiers to be held, Ctrl and Cmd counting as one
+fn specificity(shortcut: &KeyboardShortcut) -> usize {
+    let m = shortcut.modifiers;
+    [m.ctrl || m.command || m.mac_cmd, m.alt, m.shift]
+        .iter()
+        .filter(|set| **set)
+        .count()
+}
+
+// Whether a press of the first shortcut also triggers the second one. Extra Shift and Alt are
+// ignored when consuming a shortcut, Ctrl and Cmd have to match.
+fn triggers(pressed: &KeyboardShortcut, other: &KeyboardShortcut) -> bool {
+    pressed.logical_key == other.logical_key && pressed.modifiers.matches_logically(other.modifiers)
+}
+
+// Inverse of `parse_shortcut`, for writing the config file
+pub fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+    let m = shortcut.modifiers;
+    let mut parts = Vec::new();
+    for (set, name) in [
+        (m.ctrl, "Ctrl"),
+        (m.alt, "Alt"),
+        (m.shift, "Shift"),
+        (m.command && !m.ctrl, "Cmd"),
+    ] {
+        if set {
+            parts.push(name);
+        }
+    }
+    parts.push(shortcut.logical_key.name());
+    parts.join("+")
+}
+
+// Shortcuts of all commands, the defaults overridden by the config file
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<Command, KeyboardShortcut>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Command::ALL
+                .iter()
+                .map(|c| (*c, c.default_shortcut()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    // Invalid bindings and unknown commands are reported, commands keep the default shortcut
+    pub fn new(overrides: &HashMap<String, String>) -> Self {
+        let mut keymap = Self::default();
+        for (name, s) in overrides {
+            let Some(command) = Command::from_name(name) else {
+                warn!("unknown command {:?} in the keymap", name);
+                continue;
+            };
+            match parse_shortcut(s) {
+                Ok(shortcut) => {
+                    keymap.bindings.insert(command, shortcut);
+                }
+                Err(e) => warn!("invalid shortcut {:?} for {:?}: {}", s, command, e),
+            }
+        }
+        keymap
+    }
+
+    pub fn get(&self, command: Command) -> KeyboardShortcut {
+        self.bindings[&command]
+    }
+
+    // All commands, the ones with more modifiers first. Shortcuts have to be consumed in this
+    // order, otherwise e.g. Z would consume Shift+Z as well.
+    pub fn by_specificity(&self) -> Vec<Command> {
+        let mut commands = Command::ALL.to_vec();
+        commands.sort_by_key(|c| std::cmp::Reverse(specificity(&self.get(*c))));
+        commands
+    }
+
+    // Commands a single key press triggers both of, paired with an earlier command. Checking the
+    // more specific one first tells them apart only if both are handled by the same part of the
+    // app, global commands are handled before all others.
+    pub fn conflicts(&self) -> Vec<(Command, Command)> {
+        // Whether `a` is consumed before `b`
+        let first = |a: Command, b: Command| {
+            if a.is_global() != b.is_global() {
+                a.is_global()
+            } else {
+                specificity(&self.get(a)) > specificity(&self.get(b))
+            }
+        };
+        // Whether pressing the shortcut of `a` runs `b` instead
+        let shadows = |a: Command, b: Command| first(b, a) && triggers(&self.get(a), &self.get(b));
+        // Whether holding the modifiers of both, e.g. Ctrl+Shift+Alt for Ctrl+Shift and Ctrl+Alt,
+        // runs whichever comes first
+        let combined = |a: KeyboardShortcut, b: KeyboardShortcut| {
+            let both = KeyboardShortcut::new(a.modifiers.plus(b.modifiers), a.logical_key);
+            both != a && both != b && triggers(&both, &a) && triggers(&both, &b)
+        };
+        let mut res = Vec::new();
+        for (i, a) in Command::ALL.iter().enumerate() {
+            for b in &Command::ALL[i + 1..] {
+                let same = self.get(*a) == self.get(*b);
+                if same
+                    || shadows(*a, *b)
+                    || shadows(*b, *a)
+                    || combined(self.get(*a), self.get(*b))
+                {
+                    res.push((*a, *b));
+                }
+            }
+        }
+        res
+    }
+}
+
+// Keymap loaded from the config file at startup
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+pub fn set_keymap(keymap: Keymap) {
+    if KEYMAP.set(keymap).is_err() {
+        warn!("keymap has been set already");
+    }
+}
+
+pub fn keymap() -> &'static Keymap {
+    KEYMAP.get_or_init(Keymap::default)
+}
+
+pub fn shortcut(command: Command) -> KeyboardShortcut {
+    keymap().get(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcut_roundtrip() {
+        let redo = parse_shortcut("ctrl + Shift+Z").unwrap();
+        assert_eq!(redo, Command::Redo.default_shortcut());
+        assert_eq!(format_shortcut(&redo), "Ctrl+Shift+Z");
+        assert_eq!(
+            parse_shortcut("F2").unwrap(),
+            Command::Rename.default_shortcut()
+        );
+        assert!(parse_shortcut("Ctrl+").is_err());
+        assert!(parse_shortcut("Hyper+S").is_err());
+        for c in Command::ALL {
+            let s = c.default_shortcut();
+            assert_eq!(parse_shortcut(&format_shortcut(&s)).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn keymap_overrides() {
+        let overrides = HashMap::from([
+            ("save".to_string(), "Ctrl+W".to_string()),
+            ("fit".to_string(), "Nonsense".to_string()),
+            ("fly".to_string(), "F".to_string()),
+        ]);
+        let keymap = Keymap::new(&overrides);
+        assert_eq!(keymap.get(Command::Save), parse_shortcut("Ctrl+W").unwrap());
+        // Invalid bindings fall back to the default, unknown commands are skipped
+        assert_eq!(keymap.get(Command::Fit), Command::Fit.default_shortcut());
+        assert!(Keymap::default().conflicts().is_empty());
+        assert_eq!(
+            Command::from_name("fit_selection"),
+            Some(Command::FitSelection)
+        );
+        let clash = Keymap::new(&HashMap::from([("fit".to_string(), "Z".to_string())]));
+        assert_eq!(
+            clash.conflicts(),
+            vec![(Command::Fit, Command::FitSelection)]
+        );
+    }
+
+    #[test]
+    fn logical_conflicts() {
+        let keymap = |overrides: &[(&str, &str)]| {
+            Keymap::new(
+                &overrides
+                    .iter()
+                    .map(|(c, s)| (c.to_string(), s.to_string()))
+                    .collect(),
+            )
+        };
+        // Shift+F is consumed before F, so either runs its own command
+        let shift = keymap(&[("fit_selection", "Shift+F")]);
+        assert!(shift.conflicts().is_empty());
+        let order = shift.by_specificity();
+        let pos = |c| order.iter().position(|o| *o == c).unwrap();
+        assert!(pos(Command::FitSelection) < pos(Command::Fit));
+        // Ctrl+Shift+Alt+F triggers both
+        let both = keymap(&[("fit", "Ctrl+Alt+F"), ("fit_selection", "Ctrl+Shift+F")]);
+        assert_eq!(
+            both.conflicts(),
+            vec![(Command::Fit, Command::FitSelection)]
+        );
+        // The app consumes Ctrl+Shift+P as the switcher before the pinboard sees it
+        let global = keymap(&[("add_node", "Ctrl+Shift+P")]);
+        assert_eq!(
+            global.conflicts(),
+            vec![(Command::AddNode, Command::Switcher)]
+        );
+    }
+}
@@ -1,22 +1,53 @@
-use anyhow::anyhow;
 use clap::Parser;
-use eframe::{run_native, App, CreationContext, NativeOptions};
-use egui::{Context, TopBottomPanel};
-use graph::{BlobType, PinboardGraph};
-use log::error;
+use config::Config;
+use eframe::{run_native, App, CreationContext, NativeOptions, Storage};
+use egui::{Button, CentralPanel, Context, Id, Modal, SidePanel, TopBottomPanel, ViewportCommand};
+use graph::{Blob, BlobType, PinboardGraph};
+use keymap::{keymap, shortcut, Command, Keymap};
+use log::{error, info, warn};
 use petgraph::stable_graph::StableGraph;
 use pinboard::*;
 use poll_promise::Promise;
-use pretty_env_logger::env_logger::Env;
+use recovery::Recovered;
 use rfd::FileDialog;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
+use switcher::{Switcher, Target};
 use uuid::Uuid;
 
+mod config;
+mod export;
 mod graph;
+mod health;
+mod hover_preview;
+mod import;
+mod keymap;
+mod node_list;
+mod notify;
+mod opener;
 mod pinboard;
+mod preview;
+mod progress;
+mod recovery;
+mod session;
+mod settings;
+mod switcher;
+
+// Interval between writing pinboards with unsaved changes to the recovery directory
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+// Number of recently opened pinboards to remember
+const RECENT_LIMIT: usize = 10;
+const RECENT_KEY: &str = "recent_pinboards";
+const TAG_INDEX_KEY: &str = "show_tag_index";
+// Autosave interval remembered by older versions, moved into the config file
+const AUTOSAVE_KEY: &str = "autosave_secs";
+const OPEN_BOARDS_KEY: &str = "open_pinboards";
+const RESTORE_KEY: &str = "restore_pinboards";
+const TABS_KEY: &str = "tabs";
 
 pub struct PinlabApp {
     // Each pinboard is identified with an UUID, no matter it's saved or not. When saving, the uuid
@@ -24,22 +55,169 @@ pub struct PinlabApp {
     // NOTE: The bool represents if the pinboard window is open
     pinboards: HashMap<Uuid, (PinboardBuffer, bool)>,
 
-    boards_to_open: Vec<Option<Promise<anyhow::Result<PinboardBuffer>>>>,
+    // Resolve to `None` if the user canceled picking a pinboard
+    boards_to_open: Vec<Option<Promise<anyhow::Result<Option<PinboardBuffer>>>>>,
 
     nvim_ext: Vec<String>,
     nvim_srv: Option<String>,
+
+    // Pinboards left behind by a previous crashed session, waiting for user's decision
+    recovered: Vec<Recovered>,
+    last_recovery: Instant,
+
+    // Interval between saving pinboards with unsaved changes in place, zero disables autosave
+    autosave_secs: u64,
+    last_autosave: Instant,
+
+    // Paths of recently opened pinboards, most recent first
+    recent: Vec<PathBuf>,
+    // Title and information of recent pinboards, loaded when the overview is first shown
+    recent_info: HashMap<PathBuf, Promise<Option<(String, BoardInfo)>>>,
+    // Reopen the pinboards loaded at exit on the next launch
+    restore_boards: bool,
+
+    session_export: Option<Promise<anyhow::Result<()>>>,
+    // Resolves to the restored pinboard files, which are then opened like any other pinboard
+    session_import: Option<Promise<anyhow::Result<Vec<PathBuf>>>>,
+
+    switcher: Option<Switcher>,
+
+    // Panel listing tags across all open pinboards, stays open across sessions
+    show_tag_index: bool,
+    // Tag whose nodes are selected in every pinboard
+    active_tag: Option<String>,
+
+    show_shortcuts: bool,
+
+    // Config as written to the config file, without the command line overrides
+    config: Config,
+    // Why the config file couldn't be loaded, it's only replaced once the user confirmed it
+    config_error: Option<String>,
+    // The autosave interval remembered by an older version has been moved into the config file
+    autosave_migrated: bool,
+    settings: Option<settings::Settings>,
+
+    // Pinboard with unsaved changes whose window the user tried to close
+    closing: Option<Uuid>,
+    // The user tried to quit while some pinboards had unsaved changes
+    quitting: bool,
+
+    notifications: notify::Notifications,
+
+    // Nodes copied from any pinboard, pasted into any other
+    clipboard: Option<Clipboard>,
+
+    // Show open pinboards as tabs of the central panel instead of windows
+    tabs: bool,
+    active_tab: Option<Uuid>,
+    // Open pinboards in the order of their tabs, new ones are appended
+    tab_order: Vec<Uuid>,
 }
 
 impl PinlabApp {
-    fn new(cc: &CreationContext<'_>, args: Args) -> Self {
-        cc.egui_ctx.set_theme(egui::Theme::Dark);
+    fn new(
+        cc: &CreationContext<'_>,
+        args: Args,
+        mut config: Config,
+        config_error: Option<String>,
+    ) -> Self {
+        cc.egui_ctx.set_theme(config.theme.preference());
+        notify::set_context(&cc.egui_ctx);
+        // Thumbnails of image nodes are loaded from files
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+        let recent = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, RECENT_KEY))
+            .unwrap_or_default();
+        let show_tag_index = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, TAG_INDEX_KEY))
+            .unwrap_or_default();
+        let old_autosave = cc
+            .storage
+            .and_then(|s| eframe::get_value::<u64>(s, AUTOSAVE_KEY))
+            .filter(|secs| *secs > 0);
+        let autosave_migrated = match old_autosave {
+            // The config file might have been set up already
+            Some(secs) if config_error.is_none() && config.autosave_secs == 0 => {
+                config.autosave_secs = secs;
+                config
+                    .save()
+                    .map_err(|e| error!("cannot save the config file: {}", e))
+                    .is_ok()
+            }
+            Some(_) => config_error.is_none(),
+            None => false,
+        };
+        // The command line takes precedence over the config file
+        let autosave_secs = args.autosave_secs.unwrap_or(config.autosave_secs);
+        let restore_boards = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, RESTORE_KEY))
+            .unwrap_or(true);
+        let tabs = args.tabs
+            || cc
+                .storage
+                .and_then(|s| eframe::get_value(s, TABS_KEY))
+                .unwrap_or_default();
+        // Pinboards given on the command line replace the ones of the last session
+        let boards = if args.boards.is_empty() && restore_boards {
+            cc.storage
+                .and_then(|s| eframe::get_value(s, OPEN_BOARDS_KEY))
+                .unwrap_or_default()
+        } else {
+            args.boards
+        };
+        let recovered = recovery::list().unwrap_or_else(|e| {
+            error!("cannot list recovery files: {}", e);
+            Vec::new()
+        });
+        let boards_to_open = boards
+            .into_iter()
+            .map(|path| {
+                let focus = args.focus_node.clone();
+                let fit = args.fit;
+                Some(Promise::spawn_async(async move {
+                    let mut buf = Self::open_pinboard_from_path(&path).await?;
+                    if let Some(label) = focus {
+                        buf.focus_label(&label);
+                    }
+                    if fit {
+                        buf.fit();
+                    }
+                    Ok(Some(buf))
+                }))
+            })
+            .collect();
         Self {
             pinboards: HashMap::new(),
-            boards_to_open: Vec::default(),
-            nvim_srv: args.nvim_srv,
-            nvim_ext: args
-                .nvim_ext
-                .unwrap_or(vec!["md".into(), "markdown".into(), "typ".into()]),
+            boards_to_open,
+            recovered,
+            last_recovery: Instant::now(),
+            autosave_secs,
+            last_autosave: Instant::now(),
+            recent,
+            recent_info: HashMap::new(),
+            restore_boards,
+            session_export: None,
+            session_import: None,
+            switcher: None,
+            show_tag_index,
+            active_tag: None,
+            show_shortcuts: false,
+            settings: None,
+            closing: None,
+            quitting: false,
+            notifications: Default::default(),
+            clipboard: None,
+            tabs,
+            active_tab: None,
+            tab_order: Vec::new(),
+            nvim_srv: args.nvim_srv.or(config.nvim_srv.clone()),
+            nvim_ext: args.nvim_ext.unwrap_or(config.nvim_ext.clone()),
+            config,
+            config_error,
+            autosave_migrated,
         }
     }
 
@@ -52,25 +230,34 @@ impl PinlabApp {
             None,
             true,
         );
-        self.pinboards
-            .insert(*pinboard.pinboard.get_uuid(), (pinboard, true));
+        let uuid = *pinboard.pinboard.get_uuid();
+        self.pinboards.insert(uuid, (pinboard, true));
+        self.active_tab = Some(uuid);
     }
 
-    async fn open_pinboard() -> anyhow::Result<PinboardBuffer> {
+    async fn open_pinboard() -> anyhow::Result<Option<PinboardBuffer>> {
         if let Some(path) = FileDialog::new()
             // https://github.com/PolyMeilex/rfd/issues/235
-            .set_directory(Path::new(".").canonicalize()?)
+            .set_directory(config::dialog_dir()?)
             .add_filter("Pinboard", &["pinbrd"])
+            .add_filter("Pinboard bundle", &["pinbrdz"])
             .pick_file()
         {
-            return Ok(Self::open_pinboard_from_path(&path).await?);
+            return Ok(Some(Self::open_pinboard_from_path(&path).await?));
         }
-        Err(anyhow!("user canceled opening"))
+        Ok(None)
     }
 
     async fn open_pinboard_from_path(path: &PathBuf) -> anyhow::Result<PinboardBuffer> {
-        let pinboard =
-            serde_json::from_str::<Pinboard>(tokio::fs::read_to_string(&path).await?.as_str())?;
+        // Bundles are opened by extracting them next to themselves
+        let path = &if path.extension().is_some_and(|e| e == "pinbrdz") {
+            session::extract_bundle(path).await?
+        } else {
+            path.clone()
+        };
+        let mut pinboard = Pinboard::from_bytes(&tokio::fs::read(&path).await?)?;
+        pinboard.resolve_paths(path)?;
+        pinboard.load_snippets();
         Ok(PinboardBuffer::new(
             pinboard,
             Some(path.to_path_buf()),
@@ -78,6 +265,521 @@ impl PinlabApp {
         ))
     }
 
+    // Write pinboards with unsaved changes to the recovery directory
+    fn write_recovery(&mut self) {
+        if self.last_recovery.elapsed() < RECOVERY_INTERVAL {
+            return;
+        }
+        self.last_recovery = Instant::now();
+        for (p, _) in self.pinboards.values().filter(|(p, _)| p.unsaved()) {
+            let recovered = Recovered {
+                path: p.path().cloned(),
+                pinboard: p.pinboard.clone(),
+            };
+            tokio::spawn(async move {
+                if let Err(e) = recovery::write(recovered).await {
+                    error!("cannot write recovery file: {}", e);
+                }
+            });
+        }
+    }
+
+    // Save pinboards with unsaved changes which already have a file, new pinboards are only
+    // covered by the recovery files
+    fn autosave(&mut self) {
+        if self.autosave_secs == 0
+            || self.last_autosave.elapsed() < Duration::from_secs(self.autosave_secs)
+        {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        for (p, _) in self.pinboards.values_mut() {
+            p.autosave();
+        }
+    }
+
+    // Drop the pinboard along with its unsaved changes
+    fn discard(&mut self, uuid: &Uuid) {
+        self.pinboards.remove(uuid);
+        if let Err(e) = recovery::remove(uuid) {
+            error!("cannot remove recovery file: {}", e);
+        }
+    }
+
+    fn show_close_dialog(&mut self, ctx: &Context) {
+        let Some(uuid) = self.closing else {
+            return;
+        };
+        let Some((p, _)) = self.pinboards.get_mut(&uuid) else {
+            self.closing = None;
+            return;
+        };
+        let (mut save, mut discard, mut cancel) = (false, false, false);
+        Modal::new(Id::new("close_modal")).show(ctx, |ui| {
+            ui.label(format!(
+                "Save changes to {} before closing?",
+                p.pinboard.title
+            ));
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                save = ui
+                    .add_enabled(!p.read_only(), Button::new("Save"))
+                    .clicked();
+                discard = ui.button("Discard").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        // The window is closed once saving succeeded
+        if save {
+            p.save_and_close();
+        }
+        if discard {
+            self.discard(&uuid);
+        }
+        if save || discard || cancel {
+            self.closing = None;
+        }
+    }
+
+    // Ask about each pinboard with unsaved changes before quitting, and quit once none is left
+    fn show_quit_dialog(&mut self, ctx: &Context) {
+        if !self.quitting {
+            return;
+        }
+        let mut unsaved: Vec<_> = self
+            .pinboards
+            .iter()
+            .filter(|(_, (p, _))| p.unsaved())
+            .map(|(uuid, (p, _))| (*uuid, p.pinboard.title.clone(), p.read_only()))
+            .collect();
+        if unsaved.is_empty() {
+            self.quitting = false;
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+            return;
+        }
+        unsaved.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut to_save = None;
+        let mut to_discard = None;
+        let mut cancel = false;
+        Modal::new(Id::new("quit_modal")).show(ctx, |ui| {
+            ui.label("The following pinboards have unsaved changes:");
+            ui.add_space(10.0);
+            for (uuid, title, read_only) in &unsaved {
+                ui.horizontal(|ui| {
+                    ui.label(title);
+                    if ui.add_enabled(!read_only, Button::new("Save")).clicked() {
+                        to_save = Some(*uuid);
+                    }
+                    if ui.button("Discard").clicked() {
+                        to_discard = Some(*uuid);
+                    }
+                });
+            }
+            ui.add_space(10.0);
+            cancel = ui.button("Cancel").clicked();
+        });
+        if let Some((p, _)) = to_save.and_then(|uuid| self.pinboards.get_mut(&uuid)) {
+            p.save();
+        }
+        if let Some(uuid) = to_discard {
+            self.discard(&uuid);
+        }
+        if cancel {
+            self.quitting = false;
+        }
+    }
+
+    fn show_recovery_dialog(&mut self, ctx: &Context) {
+        if self.recovered.is_empty() {
+            return;
+        }
+        Modal::new(Id::new("recovery_modal")).show(ctx, |ui| {
+            ui.label("The following pinboards were not saved before Pinlab exited:");
+            ui.add_space(10.0);
+            let mut handled = None;
+            for (i, r) in self.recovered.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(match &r.path {
+                        Some(path) => format!("{} ({})", r.pinboard.title, path.display()),
+                        None => r.pinboard.title.clone(),
+                    });
+                    if ui.button("Restore").clicked() {
+                        handled = Some((i, true));
+                    }
+                    if ui.button("Discard").clicked() {
+                        handled = Some((i, false));
+                    }
+                });
+            }
+            if let Some((i, restore)) = handled {
+                let r = self.recovered.remove(i);
+                if restore {
+                    let uuid = *r.pinboard.get_uuid();
+                    let mut pinboard = r.pinboard;
+                    pinboard.load_snippets();
+                    self.pinboards
+                        .insert(uuid, (PinboardBuffer::new(pinboard, r.path, true), true));
+                } else if let Err(e) = recovery::remove(r.pinboard.get_uuid()) {
+                    error!("cannot remove recovery file: {}", e);
+                }
+            }
+        });
+    }
+
+    fn add_recent(&mut self, path: PathBuf) {
+        // The pinboard might have changed since its information was loaded
+        self.recent_info.remove(&path);
+        self.recent.retain(|p| *p != path);
+        self.recent.insert(0, path);
+        self.recent.truncate(RECENT_LIMIT);
+    }
+
+    fn open_recent(&mut self, path: PathBuf) {
+        self.boards_to_open
+            .push(Some(Promise::spawn_async(async move {
+                Self::open_pinboard_from_path(&path).await.map(Some)
+            })));
+    }
+
+    // Open the window or tab of the pinboard and bring it to front
+    fn focus(&mut self, ctx: &Context, uuid: Uuid) {
+        if let Some((_, open)) = self.pinboards.get_mut(&uuid) {
+            *open = true;
+        }
+        self.active_tab = Some(uuid);
+        // Pinboard windows use their UUID as Id
+        ctx.move_to_top(egui::LayerId::new(egui::Order::Middle, Id::new(uuid)));
+    }
+
+    // Titles of the pinboards the pinboard has been drilled into from, outermost first. Cycles of
+    // pinboards linking to each other end the chain.
+    fn ancestors(&self, uuid: Uuid) -> Vec<(Uuid, String)> {
+        let mut res: Vec<(Uuid, String)> = Vec::new();
+        let mut current = self.pinboards.get(&uuid).and_then(|(p, _)| p.parent());
+        while let Some(parent) = current.filter(|u| *u != uuid && res.iter().all(|(a, _)| a != u)) {
+            let Some((p, _)) = self.pinboards.get(&parent) else {
+                break;
+            };
+            res.push((parent, p.pinboard.title.clone()));
+            current = p.parent();
+        }
+        res.reverse();
+        res
+    }
+
+    // Pinboard already loaded from the file, no matter which UUID it has
+    fn find_by_path(&self, path: &Path) -> Option<Uuid> {
+        let path = path.canonicalize().ok()?;
+        self.pinboards
+            .iter()
+            .find(|(_, (p, _))| {
+                p.path().and_then(|p| p.canonicalize().ok()).as_ref() == Some(&path)
+            })
+            .map(|(uuid, _)| *uuid)
+    }
+
+    fn show_switcher(&mut self, ctx: &Context) {
+        let Some(switcher) = self.switcher.as_mut() else {
+            return;
+        };
+        let mut entries: Vec<switcher::Entry> = self
+            .pinboards
+            .iter()
+            .map(|(uuid, (p, _))| switcher::Entry {
+                title: p.pinboard.title.clone(),
+                path: p.path().cloned(),
+                board: None,
+                target: Target::Open(*uuid),
+            })
+            .collect();
+        let open: Vec<_> = entries.iter().filter_map(|e| e.path.clone()).collect();
+        entries.extend(
+            self.recent
+                .iter()
+                .filter(|path| !open.contains(path))
+                .map(|path| switcher::Entry {
+                    // Titles are only known for recent pinboards already shown in the overview
+                    title: match self.recent_info.get(path).and_then(|i| i.ready()) {
+                        Some(Some((title, _))) => title.clone(),
+                        _ => path
+                            .file_stem()
+                            .map(|s| s.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                    },
+                    path: Some(path.clone()),
+                    board: None,
+                    target: Target::Recent(path.clone()),
+                }),
+        );
+        for (uuid, (p, _)) in &self.pinboards {
+            let graph = &p.pinboard.graph;
+            let blob_path = |b: &Blob| b.is_local().then(|| b.path().clone());
+            entries.extend(graph.nodes_iter().map(|(id, n)| switcher::Entry {
+                title: n.label(),
+                path: n.payload().blob.as_ref().and_then(blob_path),
+                board: Some(p.pinboard.title.clone()),
+                target: Target::Node(*uuid, id),
+            }));
+            // Edges are labeled by their relations unless they carry comments
+            entries.extend(graph.edges_iter().filter_map(|(id, e)| {
+                let comment = e.payload().comment.as_ref()?;
+                Some(switcher::Entry {
+                    title: e.label(),
+                    path: blob_path(comment),
+                    board: Some(p.pinboard.title.clone()),
+                    target: Target::Edge(*uuid, id),
+                })
+            }));
+        }
+        match switcher.show(ctx, &entries) {
+            switcher::Action::None => {}
+            switcher::Action::Close => self.switcher = None,
+            switcher::Action::Pick(target) => {
+                self.switcher = None;
+                match target {
+                    Target::Open(uuid) => self.focus(ctx, uuid),
+                    Target::Recent(path) => self.open_recent(path),
+                    Target::Node(uuid, node) => {
+                        self.focus(ctx, uuid);
+                        if let Some((p, _)) = self.pinboards.get_mut(&uuid) {
+                            p.focus_node(node);
+                        }
+                    }
+                    Target::Edge(uuid, edge) => {
+                        self.focus(ctx, uuid);
+                        if let Some((p, _)) = self.pinboards.get_mut(&uuid) {
+                            p.focus_edge(edge);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // List the shortcuts in effect, they are changed in the config file
+    fn show_shortcuts(&mut self, ctx: &Context) {
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_shortcuts)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for command in Command::ALL {
+                        ui.label(command.label());
+                        let s = shortcut(command);
+                        ui.monospace(ctx.format_shortcut(&s)).on_hover_text(format!(
+                            "{} = \"{}\"",
+                            serde_json::to_string(&command)
+                                .unwrap_or_default()
+                                .trim_matches('"'),
+                            keymap::format_shortcut(&s)
+                        ));
+                        ui.end_row();
+                    }
+                });
+                for (a, b) in keymap().conflicts() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("{} and {} share a shortcut", a.label(), b.label()),
+                    );
+                }
+                ui.separator();
+                match config::path() {
+                    Some(path) => ui.weak(format!(
+                        "Rebind them in the [keymap] section of {}",
+                        path.display()
+                    )),
+                    None => ui.weak("Cannot determine the config directory"),
+                };
+            });
+    }
+
+    fn show_settings(&mut self, ctx: &Context) {
+        let Some(settings) = self.settings.as_mut() else {
+            return;
+        };
+        match settings.show(ctx) {
+            settings::Action::None => {}
+            settings::Action::Close => self.settings = None,
+            settings::Action::Save(config) => {
+                self.apply_config(ctx, config);
+                self.settings = None;
+            }
+        }
+    }
+
+    // Take over the config saved from the settings window, replacing command line arguments
+    fn apply_config(&mut self, ctx: &Context, config: Config) {
+        ctx.set_theme(config.theme.preference());
+        self.autosave_secs = config.autosave_secs;
+        self.nvim_srv = config.nvim_srv.clone();
+        self.nvim_ext = config.nvim_ext.clone();
+        config::set_dialog_dir(config.dialog_dir.clone());
+        set_repair_roots(config.repair_roots.clone());
+        match config.save() {
+            Ok(()) => self.config_error = None,
+            Err(e) => error!("cannot save the config file: {}", e),
+        }
+        self.config = config;
+    }
+
+    fn show_tag_index(&mut self, ctx: &Context) {
+        if !self.show_tag_index {
+            return;
+        }
+        // Number of nodes carrying each tag, sorted by tag
+        let mut tags: BTreeMap<String, usize> = BTreeMap::new();
+        for (p, _) in self.pinboards.values() {
+            for tag in p.tags() {
+                *tags.entry(tag.to_string()).or_default() += 1;
+            }
+        }
+        let mut clicked = None;
+        SidePanel::left("tag_index").show(ctx, |ui| {
+            ui.heading("Tags");
+            ui.separator();
+            if tags.is_empty() {
+                ui.weak("No tagged nodes");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (tag, count) in &tags {
+                    let active = self.active_tag.as_ref() == Some(tag);
+                    if ui
+                        .selectable_label(active, format!("{} ({})", tag, count))
+                        .clicked()
+                    {
+                        clicked = Some(tag.clone());
+                    }
+                }
+            });
+        });
+        if let Some(tag) = clicked {
+            // Clicking the active tag again clears the highlight
+            self.active_tag = if self.active_tag.as_ref() == Some(&tag) {
+                None
+            } else {
+                Some(tag)
+            };
+            for (p, _) in self.pinboards.values_mut() {
+                p.select_tagged(self.active_tag.as_deref());
+            }
+        }
+    }
+
+    // Tabs of the open pinboards when they aren't shown as windows, new ones are added sorted by
+    // title
+    fn show_tab_bar(&mut self, ctx: &Context) {
+        self.tab_order
+            .retain(|uuid| self.pinboards.get(uuid).is_some_and(|(_, open)| *open));
+        let mut new: Vec<_> = self
+            .pinboards
+            .iter()
+            .filter(|(uuid, (_, open))| *open && !self.tab_order.contains(uuid))
+            .map(|(uuid, (p, _))| (p.pinboard.title.clone(), *uuid))
+            .collect();
+        new.sort();
+        self.tab_order.extend(new.into_iter().map(|(_, uuid)| uuid));
+        if self
+            .active_tab
+            .is_none_or(|uuid| !self.tab_order.contains(&uuid))
+        {
+            self.active_tab = self.tab_order.first().copied();
+        }
+        if !self.tabs || self.tab_order.is_empty() {
+            return;
+        }
+        let mut to_close = None;
+        TopBottomPanel::top("tab_bar").show(ctx, |ui| {
+            egui::ScrollArea::horizontal().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for uuid in &self.tab_order {
+                        let Some((p, _)) = self.pinboards.get(uuid) else {
+                            continue;
+                        };
+                        let active = self.active_tab == Some(*uuid);
+                        let response = ui.selectable_label(active, p.title());
+                        if response.clicked() {
+                            self.active_tab = Some(*uuid);
+                        }
+                        if ui.small_button("×").on_hover_text("Close").clicked()
+                            || response.middle_clicked()
+                        {
+                            to_close = Some(*uuid);
+                        }
+                        ui.separator();
+                    }
+                });
+            });
+        });
+        if let Some(uuid) = to_close {
+            if let Some((p, open)) = self.pinboards.get_mut(&uuid) {
+                // Keep the tab open until the user decided what to do with the unsaved changes
+                if p.unsaved() {
+                    self.closing = Some(uuid);
+                } else {
+                    *open = false;
+                }
+            }
+        }
+    }
+
+    // Home screen shown when no pinboard window is open
+    fn show_overview(&mut self, ctx: &Context) {
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(40.0);
+                ui.heading("Pinlab");
+                ui.add_space(20.0);
+                ui.horizontal(|ui| {
+                    if ui.button("New pinboard").clicked() {
+                        self.new_pinboard();
+                    }
+                    if ui.button("Open...").clicked() {
+                        self.boards_to_open
+                            .push(Some(Promise::spawn_async(Self::open_pinboard())));
+                    }
+                });
+                if !self.recent.is_empty() {
+                    ui.add_space(20.0);
+                    ui.label("Recent pinboards");
+                    ui.separator();
+                    let mut to_open = None;
+                    for path in &self.recent {
+                        let info = self.recent_info.entry(path.clone()).or_insert_with(|| {
+                            let path = path.clone();
+                            Promise::spawn_async(async move {
+                                let pinboard =
+                                    Pinboard::from_bytes(&tokio::fs::read(&path).await.ok()?)
+                                        .ok()?;
+                                Some((pinboard.title, pinboard.info))
+                            })
+                        });
+                        let resp = match info.ready() {
+                            Some(Some((title, info))) => ui
+                                .link(format!("{} ({})", title, path.display()))
+                                .on_hover_text(info.summary()),
+                            _ => ui.link(path.display().to_string()),
+                        };
+                        if resp.clicked() {
+                            to_open = Some(path.clone());
+                        }
+                    }
+                    if let Some(path) = to_open {
+                        self.open_recent(path);
+                    }
+                }
+            });
+        });
+    }
+
+    // Save all pinboards with unsaved changes. Pinboards never saved are skipped as they would
+    // each pop up a file dialog.
+    fn save_all(&mut self) {
+        for (p, _) in self.pinboards.values_mut() {
+            if p.unsaved() && p.path().is_some() && !p.read_only() {
+                p.save();
+            }
+        }
+    }
+
     fn show_menu_bar(&mut self, ctx: &Context) {
         TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
@@ -92,6 +794,96 @@ impl PinlabApp {
                             .push(Some(Promise::spawn_async(Self::open_pinboard())));
                         ui.close_menu();
                     }
+
+                    ui.add_enabled_ui(!self.recent.is_empty(), |ui| {
+                        ui.menu_button("Recent", |ui| {
+                            let mut to_open = None;
+                            for path in &self.recent {
+                                if ui.button(path.display().to_string()).clicked() {
+                                    to_open = Some(path.clone());
+                                    ui.close_menu();
+                                }
+                            }
+                            ui.separator();
+                            if ui.button("Clear").clicked() {
+                                self.recent.clear();
+                                self.recent_info.clear();
+                                ui.close_menu();
+                            }
+                            if let Some(path) = to_open {
+                                // Pinboards open already are brought to front once loaded
+                                self.open_recent(path);
+                            }
+                        });
+                    });
+
+                    if ui
+                        .add(
+                            Button::new("Save All")
+                                .shortcut_text(ctx.format_shortcut(&shortcut(Command::SaveAll))),
+                        )
+                        .clicked()
+                    {
+                        self.save_all();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui
+                        .add_enabled(!self.pinboards.is_empty(), Button::new("Export session..."))
+                        .clicked()
+                    {
+                        let boards = self
+                            .pinboards
+                            .values()
+                            .map(|(p, _)| (p.path().cloned(), p.pinboard.clone()))
+                            .collect();
+                        self.session_export =
+                            Some(Promise::spawn_async(session::export_dialog(boards)));
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Import session...").clicked() {
+                        let taken = self.pinboards.keys().copied().collect();
+                        self.session_import =
+                            Some(Promise::spawn_async(session::import_dialog(taken)));
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    ui.checkbox(&mut self.restore_boards, "Reopen pinboards on launch")
+                        .on_hover_text("Open the pinboards which were open at exit again");
+
+                    if ui
+                        .button("Clear hash cache")
+                        .on_hover_text("Read all files again the next time they are hashed")
+                        .clicked()
+                    {
+                        if let Err(e) = graph::clear_hash_cache() {
+                            error!("cannot clear the hash cache: {}", e);
+                        }
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Settings", |ui| {
+                    if ui.button("Settings...").clicked() {
+                        self.settings = Some(settings::Settings::new(
+                            &self.config,
+                            self.config_error.clone(),
+                        ));
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.show_shortcuts, "Keyboard shortcuts");
+                });
+
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.show_tag_index, "Tag index");
+                    ui.checkbox(&mut self.tabs, "Tabs")
+                        .on_hover_text("Show pinboards as tabs instead of windows");
+                    ui.checkbox(&mut self.notifications.show_history, "Notifications");
                 });
             });
         });
@@ -121,57 +913,148 @@ fn handle_promise<T: Send + 'static, R>(
 
 impl App for PinlabApp {
     fn update(&mut self, ctx: &Context, _: &mut eframe::Frame) {
+        // NOTE: This must be handled before pinboards consume their own save shortcut
+        if ctx.input_mut(|i| i.consume_shortcut(&shortcut(Command::SaveAll))) {
+            self.save_all();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&shortcut(Command::Switcher))) {
+            self.switcher = match self.switcher {
+                Some(_) => None,
+                None => Some(Switcher::default()),
+            };
+        }
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.pinboards.values().any(|(p, _)| p.unsaved())
+        {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            self.quitting = true;
+        }
         self.show_menu_bar(ctx);
+        self.show_tab_bar(ctx);
+        self.show_tag_index(ctx);
+        self.show_shortcuts(ctx);
+        self.show_settings(ctx);
+        self.show_switcher(ctx);
+        self.show_recovery_dialog(ctx);
+        self.show_close_dialog(ctx);
+        self.show_quit_dialog(ctx);
+        self.notifications.show(ctx);
+        if self.pinboards.values().all(|(_, open)| !open) {
+            self.show_overview(ctx);
+        }
+        self.write_recovery();
+        self.autosave();
 
-        for (p, open) in self.pinboards.values_mut() {
-            if let Some(b) = p.show(ctx, open) {
-                async fn _h(path: PathBuf) -> anyhow::Result<PinboardBuffer> {
-                    PinlabApp::open_pinboard_from_path(&path).await
+        let ancestors: Vec<_> = self
+            .pinboards
+            .keys()
+            .map(|uuid| (*uuid, self.ancestors(*uuid)))
+            .collect();
+        for (uuid, chain) in ancestors {
+            if let Some((p, _)) = self.pinboards.get_mut(&uuid) {
+                p.set_ancestors(chain);
+            }
+        }
+        let mut navigate_to = None;
+        // Dialogs are only shown by the active tab, one waiting elsewhere brings its tab to front
+        // once the active tab has none of its own
+        let mut attention = self
+            .active_tab
+            .and_then(|uuid| self.pinboards.get(&uuid))
+            .is_some_and(|(p, _)| p.needs_attention());
+        for (uuid, (p, open)) in self.pinboards.iter_mut() {
+            let was_open = *open;
+            let blobs = if !self.tabs {
+                p.show(ctx, open, &mut self.clipboard)
+            } else if *open && self.active_tab == Some(*uuid) {
+                CentralPanel::default()
+                    .show(ctx, |ui| p.show_tab(ctx, Some(ui), &mut self.clipboard))
+                    .inner
+            } else {
+                p.show_tab(ctx, None, &mut self.clipboard)
+            };
+            navigate_to = navigate_to.or(p.take_navigation());
+            if self.tabs && *open && !attention && p.needs_attention() {
+                attention = true;
+                navigate_to = navigate_to.or(Some(*uuid));
+            }
+            if p.take_close() {
+                *open = false;
+            }
+            // Keep the window open until the user decided what to do with the unsaved changes
+            if was_open && !*open && p.unsaved() {
+                *open = true;
+                self.closing = Some(*uuid);
+            }
+            for b in blobs {
+                async fn _h(
+                    path: PathBuf,
+                    read_only: bool,
+                    parent: Uuid,
+                ) -> anyhow::Result<Option<PinboardBuffer>> {
+                    let mut buf = PinlabApp::open_pinboard_from_path(&path).await?;
+                    buf.set_read_only(read_only);
+                    buf.set_parent(Some(parent));
+                    Ok(Some(buf))
                 }
                 match b.ty() {
                     BlobType::File => {
-                        match if let Some(srv) = &self.nvim_srv {
-                            // If matches any of the extension we want to launch in neovim
-                            if Some(true)
-                                == b.path()
-                                    .extension()
-                                    .map(|s| s.to_str())
-                                    .flatten()
-                                    .map(|ext| self.nvim_ext.iter().any(|e| e.as_str() == ext))
-                            {
-                                std::process::Command::new("nvim")
-                                    .arg("--server")
-                                    .arg(srv)
-                                    .arg("--remote")
-                                    .arg(b.path())
-                                    .spawn()
-                                    .map(|_| ())
-                            } else {
-                                // if not matched, open in default as well
-                                open::that(b.path())
-                            }
-                        } else {
-                            open::that(b.path())
-                        } {
-                            // print out error if any
-                            Err(e) => error!("cannot open file: {}", e),
-                            _ => {}
+                        // Board's own neovim settings take precedence over the global ones
+                        let srv = p.pinboard.nvim.srv.as_ref().or(self.nvim_srv.as_ref());
+                        let ext = p.pinboard.nvim.ext.as_ref().unwrap_or(&self.nvim_ext);
+                        if let Err(e) = p.open_with().open(b.path(), srv, ext) {
+                            error!("cannot open file: {}", e);
+                        }
+                    }
+                    BlobType::PinboardGraph => self.boards_to_open.push(Some(
+                        Promise::spawn_async(_h(b.path().to_path_buf(), p.open_read_only(), *uuid)),
+                    )),
+                    BlobType::Url => {
+                        if let Err(e) = open::that(b.path()) {
+                            error!("cannot open link: {}", e);
                         }
                     }
-                    BlobType::PinboardGraph => self
-                        .boards_to_open
-                        .push(Some(Promise::spawn_async(_h(b.path().to_path_buf())))),
+                    // Notes are edited inside their pinboard
+                    BlobType::Note => {}
                 }
             }
         }
 
+        if let Some(uuid) = navigate_to {
+            self.focus(ctx, uuid);
+        }
+
+        handle_promise(&mut self.session_export, |res| {
+            if let Err(e) = res {
+                error!("failed to export session: {}", e);
+            }
+        });
+        if let Some(res) = handle_promise(&mut self.session_import, |res| match res {
+            Ok(paths) => paths.clone(),
+            Err(e) => {
+                error!("failed to import session: {}", e);
+                Vec::new()
+            }
+        }) {
+            for path in res {
+                self.boards_to_open
+                    .push(Some(Promise::spawn_async(async move {
+                        Self::open_pinboard_from_path(&path).await.map(Some)
+                    })));
+            }
+        }
+
         // Handle board opening
         // WARN: we need to do some terrible workaround...
         let mut indices_to_remove = Vec::with_capacity(self.boards_to_open.len());
         for (i, p) in self.boards_to_open.iter_mut().enumerate() {
             if let Some(promise) = p {
                 match promise.ready() {
-                    Some(Ok(_)) => indices_to_remove.push(i),
+                    Some(Ok(Some(_))) => indices_to_remove.push(i),
+                    Some(Ok(None)) => {
+                        info!("user canceled opening");
+                        *p = None;
+                    }
                     Some(Err(e)) => {
                         error!("failed to open pinboard: {}", e);
                         *p = None;
@@ -182,26 +1065,99 @@ impl App for PinlabApp {
         }
 
         // We have already removed these indices so we wouldn't need to replace them with None
-        for i in indices_to_remove {
-            let buf = self
+        // Removing from the back so that the remaining indices stay valid
+        for i in indices_to_remove.into_iter().rev() {
+            let mut buf = self
                 .boards_to_open
                 .remove(i)
                 .unwrap()
                 .try_take()
                 .unwrap_or_else(|_| panic!("this shouldn't happened!"))
+                .unwrap()
                 .unwrap();
-            if let Some(p) = self.pinboards.get_mut(&buf.pinboard.get_uuid()) {
-                p.1 = true;
-            } else {
-                self.pinboards.insert(*buf.pinboard.get_uuid(), (buf, true));
+            if let Some(path) = buf.path().cloned() {
+                self.add_recent(path.clone());
+                // The file may be open already under a different UUID, e.g. after being copied
+                // and edited elsewhere, so files are identified by their path first
+                if let Some(uuid) = self.find_by_path(&path) {
+                    // Drilling into an open pinboard makes it a child unless it has a parent
+                    // already
+                    if let Some((existing, _)) = self.pinboards.get_mut(&uuid) {
+                        if existing.parent().is_none() && buf.parent() != Some(uuid) {
+                            existing.set_parent(buf.parent());
+                        }
+                    }
+                    self.focus(ctx, uuid);
+                    continue;
+                }
             }
+            let mut uuid = *buf.pinboard.get_uuid();
+            // Pinboards open already come from other files, so this is a copy of one of them
+            if self.pinboards.contains_key(&uuid) {
+                buf.renew_uuid();
+                warn!(
+                    "another pinboard with UUID {} is already open, opening this one as {}",
+                    uuid,
+                    buf.pinboard.get_uuid()
+                );
+                uuid = *buf.pinboard.get_uuid();
+            }
+            buf.check_health();
+            self.pinboards.insert(uuid, (buf, true));
+            self.active_tab = Some(uuid);
         }
         self.boards_to_open.retain(Option::is_some);
     }
 
-    // fn save(&mut self, storage: &mut dyn Storage) {
-    //     // eframe::set_value(storage, "pinlab_state", &self.g);
-    // }
+    fn on_exit(&mut self, _: Option<&eframe::glow::Context>) {
+        if let Err(e) = graph::save_hash_cache() {
+            error!("cannot save the hash cache: {}", e);
+        }
+        // Recovery files are only meant to survive crashes
+        for uuid in self.pinboards.keys() {
+            if let Err(e) = recovery::remove(uuid) {
+                error!("cannot remove recovery file: {}", e);
+            }
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn Storage) {
+        // Pinboards saved for the first time during this session count as recent as well
+        let paths: Vec<PathBuf> = self
+            .pinboards
+            .values()
+            .filter_map(|(p, _)| p.path().cloned())
+            .filter(|p| !self.recent.contains(p))
+            .collect();
+        for path in paths {
+            self.add_recent(path);
+        }
+        eframe::set_value(storage, RECENT_KEY, &self.recent);
+        // Zero is never migrated again
+        if self.autosave_migrated {
+            eframe::set_value(storage, AUTOSAVE_KEY, &0u64);
+        }
+        eframe::set_value(storage, TAG_INDEX_KEY, &self.show_tag_index);
+        eframe::set_value(storage, RESTORE_KEY, &self.restore_boards);
+        eframe::set_value(storage, TABS_KEY, &self.tabs);
+        // Open pinboards in tab order, the others sorted by path so that they reopen the same way
+        let mut others: Vec<PathBuf> = self
+            .pinboards
+            .iter()
+            .filter(|(uuid, (_, open))| *open && !self.tab_order.contains(uuid))
+            .filter_map(|(_, (p, _))| p.path().cloned())
+            .collect();
+        others.sort();
+        let open: Vec<PathBuf> = self
+            .tab_order
+            .iter()
+            .filter_map(|uuid| self.pinboards.get(uuid))
+            .filter(|(_, open)| *open)
+            .filter_map(|(p, _)| p.path().cloned())
+            .chain(others)
+            .collect();
+        eframe::set_value(storage, OPEN_BOARDS_KEY, &open);
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -214,18 +1170,61 @@ struct Args {
     /// types of files to launch in neovim remotely
     #[arg(short, long)]
     nvim_ext: Option<Vec<String>>,
+
+    /// pinboards to open on startup
+    boards: Vec<PathBuf>,
+
+    /// select and center on the node with this label in the pinboards opened on startup
+    #[arg(long)]
+    focus_node: Option<String>,
+
+    /// fit the graph to the window in the pinboards opened on startup
+    #[arg(long)]
+    fit: bool,
+
+    /// save pinboards with unsaved changes every this many seconds, 0 disables autosave
+    #[arg(long)]
+    autosave_secs: Option<u64>,
+
+    /// directory to search for missing files of every pinboard, can be given several times
+    #[arg(long)]
+    repair_root: Vec<PathBuf>,
+
+    /// show pinboards as tabs instead of separate windows
+    #[arg(long)]
+    tabs: bool,
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
-    pretty_env_logger::env_logger::Builder::from_env(Env::default().default_filter_or("info"))
-        .init();
+    notify::init();
+    let (config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(e) => {
+            error!("cannot load the config file: {}", e);
+            (Config::default(), Some(e.to_string()))
+        }
+    };
+    keymap::set_keymap(Keymap::new(&config.keymap));
+    for (a, b) in keymap().conflicts() {
+        warn!("{:?} and {:?} share a shortcut", a, b);
+    }
+    config::set_dialog_dir(config.dialog_dir.clone());
+    // Roots given on the command line replace the configured ones
+    set_repair_roots(if args.repair_root.is_empty() {
+        config.repair_roots.clone()
+    } else {
+        args.repair_root.clone()
+    });
+    if let Err(e) = graph::load_hash_cache() {
+        warn!("cannot load the hash cache: {}", e);
+    }
 
     run_native(
         "Pinlab",
         NativeOptions::default(),
-        Box::new(|cc| Ok(Box::new(PinlabApp::new(cc, args)))),
+        Box::new(|cc| Ok(Box::new(PinlabApp::new(cc, args, config, config_error)))),
     )
     .unwrap();
 }
@@ -0,0 +1,217 @@
+// This module contains the list view, a flat alternative presentation of a pinboard's nodes.
+
+use crate::graph::PinboardGraph;
+use egui::{Modifiers, Sense, Ui};
+use egui_extras::{Column, TableBuilder};
+use petgraph::graph::NodeIndex;
+use std::{collections::HashSet, path::PathBuf};
+
+// Seconds between checking which blobs are missing, rather than touching the disk every frame
+const STATUS_REFRESH_SECS: f64 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum SortBy {
+    #[default]
+    Label,
+    Type,
+    Status,
+}
+
+struct Row {
+    id: NodeIndex,
+    label: String,
+    ty: &'static str,
+    status: &'static str,
+    selected: bool,
+}
+
+// UI states of the list view
+pub struct NodeList {
+    filter: String,
+    sort_by: SortBy,
+    ascending: bool,
+    // Last row clicked without shift, where range selections start from
+    anchor: Option<NodeIndex>,
+    // Local blobs missing on disk as of the last check
+    missing: HashSet<PathBuf>,
+    // Time of the last check for missing blobs
+    checked_at: Option<f64>,
+}
+
+impl Default for NodeList {
+    fn default() -> Self {
+        Self {
+            filter: String::new(),
+            sort_by: SortBy::default(),
+            ascending: true,
+            anchor: None,
+            missing: HashSet::new(),
+            checked_at: None,
+        }
+    }
+}
+
+impl NodeList {
+    fn refresh_missing(&mut self, graph: &PinboardGraph, now: f64) {
+        if self
+            .checked_at
+            .is_some_and(|t| now - t < STATUS_REFRESH_SECS)
+        {
+            return;
+        }
+        self.missing = graph
+            .nodes_iter()
+            .filter_map(|(_, n)| n.payload().blob.as_ref())
+            .filter(|b| b.is_local() && !b.path().exists())
+            .map(|b| b.path().clone())
+            .collect();
+        self.checked_at = Some(now);
+    }
+
+    fn rows(&self, graph: &PinboardGraph) -> Vec<Row> {
+        let filter = self.filter.to_lowercase();
+        let mut rows: Vec<Row> = graph
+            .nodes_iter()
+            .filter(|(_, n)| {
+                n.label().to_lowercase().contains(&filter) || n.payload().alias_matches(&filter)
+            })
+            .map(|(id, n)| Row {
+                id,
+                label: n.label(),
+                ty: n
+                    .payload()
+                    .blob
+                    .as_ref()
+                    .map_or("Empty", |b| b.ty().label()),
+                status: match &n.payload().blob {
+                    Some(b) if self.missing.contains(b.path()) => "Broken",
+                    Some(_) => "OK",
+                    None => "",
+                },
+                selected: n.selected(),
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            let ord = match self.sort_by {
+                SortBy::Label => a.label.cmp(&b.label),
+                SortBy::Type => a.ty.cmp(b.ty),
+                SortBy::Status => a.status.cmp(b.status),
+            };
+            if self.ascending {
+                ord
+            } else {
+                ord.reverse()
+            }
+        });
+        rows
+    }
+
+    fn sort_header(&mut self, ui: &mut Ui, sort_by: SortBy, name: &str) {
+        let arrow = match (self.sort_by == sort_by, self.ascending) {
+            (true, true) => " ⏶",
+            (true, false) => " ⏷",
+            (false, _) => "",
+        };
+        if ui
+            .selectable_label(self.sort_by == sort_by, format!("{}{}", name, arrow))
+            .clicked()
+        {
+            if self.sort_by == sort_by {
+                self.ascending = !self.ascending;
+            } else {
+                self.sort_by = sort_by;
+                self.ascending = true;
+            }
+        }
+    }
+
+    // Apply a click on the row to the selection of the graph
+    fn click(&mut self, graph: &mut PinboardGraph, rows: &[Row], clicked: usize, mods: Modifiers) {
+        let anchor = self
+            .anchor
+            .and_then(|a| rows.iter().position(|r| r.id == a))
+            .filter(|_| mods.shift);
+
+        let mut selected: Vec<NodeIndex> = if mods.command {
+            rows.iter().filter(|r| r.selected).map(|r| r.id).collect()
+        } else {
+            Vec::new()
+        };
+        if let Some(anchor) = anchor {
+            let range = anchor.min(clicked)..=anchor.max(clicked);
+            selected.extend(rows[range].iter().map(|r| r.id));
+        } else {
+            let id = rows[clicked].id;
+            if mods.command && rows[clicked].selected {
+                selected.retain(|n| *n != id);
+            } else {
+                selected.push(id);
+            }
+            self.anchor = Some(id);
+        }
+        selected.sort();
+        selected.dedup();
+
+        for i in graph.g.node_indices().collect::<Vec<_>>() {
+            if let Some(n) = graph.node_mut(i) {
+                n.set_selected(selected.contains(&i));
+            }
+        }
+        graph.set_selected_nodes(selected);
+    }
+
+    // Display the list. Clicking a row selects the node, with ctrl toggling and shift selecting a
+    // range. Double clicking returns the node to open.
+    pub fn show(&mut self, ui: &mut Ui, graph: &mut PinboardGraph) -> Option<NodeIndex> {
+        let mut to_open = None;
+        let mut clicked = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+
+        self.refresh_missing(graph, ui.input(|i| i.time));
+        let rows = self.rows(graph);
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        TableBuilder::new(ui)
+            .striped(true)
+            .sense(Sense::click())
+            .column(Column::remainder())
+            .column(Column::auto())
+            .column(Column::auto())
+            .header(row_height, |mut header| {
+                header.col(|ui| self.sort_header(ui, SortBy::Label, "Label"));
+                header.col(|ui| self.sort_header(ui, SortBy::Type, "Type"));
+                header.col(|ui| self.sort_header(ui, SortBy::Status, "Status"));
+            })
+            .body(|body| {
+                body.rows(row_height, rows.len(), |mut row| {
+                    let r = &rows[row.index()];
+                    row.set_selected(r.selected);
+                    row.col(|ui| {
+                        ui.label(&r.label);
+                    });
+                    row.col(|ui| {
+                        ui.label(r.ty);
+                    });
+                    row.col(|ui| {
+                        ui.label(r.status);
+                    });
+
+                    let resp = row.response();
+                    if resp.double_clicked() {
+                        to_open = Some(r.id);
+                    } else if resp.clicked() {
+                        clicked = Some((row.index(), resp.ctx.input(|i| i.modifiers)));
+                    }
+                });
+            });
+
+        if let Some((i, mods)) = clicked {
+            self.click(graph, &rows, i, mods);
+        }
+
+        to_open
+    }
+}
@@ -0,0 +1,211 @@
+// This module contains in-app notifications. Warnings and errors logged by the app are shown as
+// toasts in addition to being printed, so that they aren't lost in a GUI session.
+
+use egui::{Align2, Color32, Context, RichText, Vec2};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pretty_env_logger::env_logger::{self, Env};
+use std::{
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+// Number of notifications kept in the history
+const HISTORY_LIMIT: usize = 200;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self) -> Color32 {
+        match self {
+            Self::Warning => Color32::from_rgb(0xe5, 0xc0, 0x7b),
+            Self::Error => Color32::from_rgb(0xe0, 0x6c, 0x75),
+        }
+    }
+
+    // Errors stay longer as they usually need to be acted on
+    fn lifetime(self) -> Duration {
+        match self {
+            Self::Warning => Duration::from_secs(5),
+            Self::Error => Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    pub time: SystemTime,
+}
+
+// Notifications not yet picked up by the UI thread
+static PENDING: Mutex<Vec<Notification>> = Mutex::new(Vec::new());
+// Context to wake up when something is logged from a background task
+static CONTEXT: OnceLock<Context> = OnceLock::new();
+
+// Logs as configured by the environment and queues warnings and errors of this crate
+struct Logger {
+    inner: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata) || is_notified(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.inner.log(record);
+        if !is_notified(record.metadata()) {
+            return;
+        }
+        let severity = match record.level() {
+            Level::Error => Severity::Error,
+            _ => Severity::Warning,
+        };
+        push(severity, record.args().to_string());
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+// Messages of dependencies are left to the terminal
+fn is_notified(metadata: &Metadata) -> bool {
+    metadata.level() <= Level::Warn && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+}
+
+// Install the logger, this replaces initializing `env_logger`
+pub fn init() {
+    let inner = env_logger::Builder::from_env(Env::default().default_filter_or("info")).build();
+    log::set_max_level(inner.filter().max(LevelFilter::Warn));
+    log::set_boxed_logger(Box::new(Logger { inner })).expect("logger is initialized once");
+}
+
+// Repaint the context when a notification arrives
+pub fn set_context(ctx: &Context) {
+    let _ = CONTEXT.set(ctx.clone());
+}
+
+pub fn push(severity: Severity, message: String) {
+    PENDING.lock().unwrap().push(Notification {
+        severity,
+        message,
+        time: SystemTime::now(),
+    });
+    if let Some(ctx) = CONTEXT.get() {
+        ctx.request_repaint();
+    }
+}
+
+// Toasts currently shown and the history of all notifications
+#[derive(Default)]
+pub struct Notifications {
+    toasts: Vec<(Notification, Instant)>,
+    history: Vec<Notification>,
+    pub show_history: bool,
+}
+
+impl Notifications {
+    pub fn show(&mut self, ctx: &Context) {
+        let now = Instant::now();
+        for n in PENDING.lock().unwrap().drain(..) {
+            self.toasts.push((n.clone(), now));
+            self.history.push(n);
+        }
+        let excess = self.history.len().saturating_sub(HISTORY_LIMIT);
+        self.history.drain(..excess);
+        self.toasts
+            .retain(|(n, shown)| now.duration_since(*shown) < n.severity.lifetime());
+        self.show_toasts(ctx);
+        self.show_history(ctx);
+        // Expire the remaining toasts without any input
+        if let Some(remaining) = self
+            .toasts
+            .iter()
+            .map(|(n, shown)| {
+                n.severity
+                    .lifetime()
+                    .saturating_sub(now.duration_since(*shown))
+            })
+            .min()
+        {
+            ctx.request_repaint_after(remaining);
+        }
+    }
+
+    fn show_toasts(&mut self, ctx: &Context) {
+        if self.toasts.is_empty() {
+            return;
+        }
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("notification_toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, Vec2::new(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(400.0);
+                for (i, (n, _)) in self.toasts.iter().enumerate() {
+                    let response = egui::Frame::popup(ui.style())
+                        .stroke((1.0, n.severity.color()))
+                        .show(ui, |ui| {
+                            ui.label(RichText::new(&n.message).color(n.severity.color()));
+                        })
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss");
+                    if response.clicked() {
+                        dismissed = Some(i);
+                    }
+                }
+            });
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+    }
+
+    fn show_history(&mut self, ctx: &Context) {
+        let mut open = self.show_history;
+        egui::Window::new("Notifications")
+            .open(&mut open)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                if ui.button("Clear").clicked() {
+                    self.history.clear();
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        if self.history.is_empty() {
+                            ui.weak("Nothing has happened yet");
+                        }
+                        for n in &self.history {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.weak(humantime::format_rfc3339_seconds(n.time).to_string());
+                                ui.label(RichText::new(&n.message).color(n.severity.color()));
+                            });
+                        }
+                    });
+            });
+        self.show_history = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notified_levels() {
+        let metadata = |level, target| Metadata::builder().level(level).target(target).build();
+        let own = concat!(env!("CARGO_CRATE_NAME"), "::pinboard");
+        assert!(is_notified(&metadata(Level::Error, own)));
+        assert!(is_notified(&metadata(Level::Warn, own)));
+        assert!(!is_notified(&metadata(Level::Info, own)));
+        assert!(!is_notified(&metadata(Level::Error, "winit::platform")));
+    }
+}
@@ -0,0 +1,70 @@
+// This module contains the ways file blobs can be opened with.
+
+use std::{io, path::Path};
+
+// How to open a file blob
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Opener {
+    // Neovim if the server is given and the extension matches, the default app otherwise
+    #[default]
+    Auto,
+    DefaultApp,
+    Neovim,
+    // Open the folder containing the file
+    RevealInFolder,
+}
+
+impl Opener {
+    // Openers the user can explicitly choose from
+    pub const CHOICES: [Opener; 3] = [Opener::DefaultApp, Opener::Neovim, Opener::RevealInFolder];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Opener::Auto => "Auto",
+            Opener::DefaultApp => "Default app",
+            Opener::Neovim => "Neovim",
+            Opener::RevealInFolder => "Reveal in folder",
+        }
+    }
+
+    pub fn open(
+        self,
+        path: &Path,
+        nvim_srv: Option<&String>,
+        nvim_ext: &[String],
+    ) -> io::Result<()> {
+        match self {
+            Opener::Auto => {
+                // If matches any of the extension we want to launch in neovim
+                let matched = path
+                    .extension()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|ext| nvim_ext.iter().any(|e| e.as_str() == ext));
+                match nvim_srv {
+                    Some(srv) if matched => open_in_nvim(path, srv),
+                    // if not matched, open in default as well
+                    _ => open::that(path),
+                }
+            }
+            Opener::DefaultApp => open::that(path),
+            Opener::Neovim => match nvim_srv {
+                Some(srv) => open_in_nvim(path, srv),
+                None => Err(io::Error::other("no neovim server is configured")),
+            },
+            Opener::RevealInFolder => match path.parent() {
+                Some(dir) => open::that(dir),
+                None => Err(io::Error::other("file has no parent folder")),
+            },
+        }
+    }
+}
+
+fn open_in_nvim(path: &Path, srv: &str) -> io::Result<()> {
+    std::process::Command::new("nvim")
+        .arg("--server")
+        .arg(srv)
+        .arg("--remote")
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+}
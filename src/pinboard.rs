@@ -1,19 +1,276 @@
 use crate::{
-    graph::{Blob, BlobType, Conn, PinboardGraph, PinboardGraphView, Relation},
+    config, export,
+    graph::{
+        self, Arrow, Blob, BlobType, Conn, MultipleMatches, NodeData, PinboardGraph,
+        PinboardGraphView, Relation, RelationKind, SearchScope, Visibility,
+    },
     handle_promise,
+    health::{HealthCheck, HealthReport},
+    hover_preview::HoverPreviews,
+    import,
+    keymap::{keymap, shortcut, Command},
+    node_list::NodeList,
+    opener::Opener,
+    preview::{self, MarkdownPreview},
+    progress::{self, RelationCounts},
+    recovery, session,
 };
 use anyhow::{anyhow, Result};
 use crossbeam::channel::{unbounded, Receiver, Sender};
-use egui::{Button, Context, Id, Key, KeyboardShortcut, Modal, Modifiers, Pos2, Ui, Window};
-use egui_graphs::{events::Event, Metadata, SettingsInteraction, SettingsNavigation};
-use log::error;
+use egui::{
+    Button, Color32, ColorImage, Context, CursorIcon, Id, Key, KeyboardShortcut, Modal, Modifiers,
+    Pos2, Rect, Response, Ui, UserData, Vec2, ViewportCommand, Window,
+};
+use egui_graphs::{
+    events::Event, AgeFade, LabelPosition, Metadata, SettingsInteraction, SettingsNavigation,
+    SettingsStyle,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::{error, info, warn};
 use petgraph::{graph::NodeIndex, prelude::EdgeIndex, stable_graph::StableGraph};
 use poll_promise::Promise;
+use rand::seq::SliceRandom;
 use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
 use uuid::Uuid;
 
+// Offset (in canvas coordinates) of a newly created connected node relative to its parent
+const CONNECTED_NODE_OFFSET: Vec2 = Vec2::new(40.0, 40.0);
+// Number of edits that can be undone
+const HISTORY_LIMIT: usize = 100;
+// Steps of the force-directed layout in total and per frame
+const FORCE_LAYOUT_STEPS: usize = 150;
+const FORCE_LAYOUT_STEPS_PER_FRAME: usize = 3;
+// Duration of panning to a focused node in seconds
+const FOCUS_ANIMATION_DURATION: f64 = 0.35;
+// Space kept around the nodes framed by fitting, in canvas units
+const FIT_MARGIN: f32 = 60.0;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Updated blobs to open, each with the node or edge it belongs to
+type BlobUpdates = Vec<(Either, Result<Blob>)>;
+// Nodes being extracted, their copy and the blob of the sub-board they were saved to
+type Extraction = (Vec<NodeIndex>, Clipboard, Result<Option<Blob>>);
+// Lost blob path and the file picked for it, `None` if the user canceled
+type Relocation = (PathBuf, Option<PathBuf>);
+
+// Keys picking a relation in the relation picker, in the order of `Pinboard::relations`
+const RELATION_PICKER_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+// Saving is attempted this many times, waiting twice as long after each failure
+const SAVE_ATTEMPTS: u32 = 3;
+const SAVE_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+// Opening more blobs than this at once requires confirmation
+const OPEN_ALL_CONFIRM_THRESHOLD: usize = 5;
+
+// Directories searched for missing blobs of every pinboard, from the config or the command line
+static REPAIR_ROOTS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+pub fn set_repair_roots(roots: Vec<PathBuf>) {
+    *REPAIR_ROOTS.write().unwrap() = roots;
+}
+
+// Display preferences of a pinboard, saved alongside the graph
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewSettings {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub show_grid: bool,
+    // spacing between minor grid lines in canvas coordinates
+    #[serde(skip_serializing_if = "is_default::grid_spacing")]
+    pub grid_spacing: f32,
+    // Move dragged nodes to the nearest grid intersection when dropped, even if the grid is hidden
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub snap_to_grid: bool,
+    #[serde(skip_serializing_if = "is_default::show_edge_labels")]
+    pub show_edge_labels: bool,
+    #[serde(skip_serializing_if = "is_default::node_label_position")]
+    pub node_label_position: LabelPosition,
+    // Let edges end at node centers rather than node boundaries
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub edges_to_center: bool,
+    // Range the zoom factor is clamped to
+    #[serde(skip_serializing_if = "is_default::min_zoom")]
+    pub min_zoom: f32,
+    #[serde(skip_serializing_if = "is_default::max_zoom")]
+    pub max_zoom: f32,
+    // Background of the canvas, colors of nodes and edges are adjusted to stay legible on it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<Color32>,
+    // Draw a pill behind edge labels so that they stay readable over edges
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub edge_label_background: bool,
+    // Scale of distances between nodes placed in bulk or next to another node
+    #[serde(skip_serializing_if = "is_default::node_spacing")]
+    pub node_spacing: f32,
+    // Show icon buttons with the menus collapsed behind one button instead of the menu bar
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub compact_toolbar: bool,
+    // Pan smoothly to focused nodes instead of jumping, optionally zooming back to 1:1 on the way
+    #[serde(skip_serializing_if = "is_default::animate_focus")]
+    pub animate_focus: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub zoom_on_focus: bool,
+    // Fade nodes and edges not edited for a while, fully opaque for the first `fade_after_days`
+    // and then linearly down to `fade_min_opacity` over `fade_span_days`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fade_old: bool,
+    #[serde(skip_serializing_if = "is_default::fade_after_days")]
+    pub fade_after_days: f32,
+    #[serde(skip_serializing_if = "is_default::fade_span_days")]
+    pub fade_span_days: f32,
+    #[serde(skip_serializing_if = "is_default::fade_min_opacity")]
+    pub fade_min_opacity: f32,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            show_grid: false,
+            grid_spacing: 20.0,
+            snap_to_grid: false,
+            show_edge_labels: true,
+            node_label_position: LabelPosition::default(),
+            edges_to_center: false,
+            min_zoom: 0.05,
+            max_zoom: 10.0,
+            background: None,
+            edge_label_background: false,
+            node_spacing: 1.0,
+            compact_toolbar: false,
+            animate_focus: true,
+            zoom_on_focus: false,
+            fade_old: false,
+            fade_after_days: 7.0,
+            fade_span_days: 30.0,
+            fade_min_opacity: 0.25,
+        }
+    }
+}
+
+// Settings still at their defaults are left out of pinboard files, `is_default::field` checks
+// the field against `ViewSettings::default()`
+macro_rules! default_checks {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        mod is_default {
+            use super::*;
+            $(
+                pub fn $field(v: &$ty) -> bool {
+                    *v == ViewSettings::default().$field
+                }
+            )*
+        }
+    };
+}
+
+default_checks! {
+    grid_spacing: f32,
+    show_edge_labels: bool,
+    node_label_position: LabelPosition,
+    min_zoom: f32,
+    max_zoom: f32,
+    node_spacing: f32,
+    animate_focus: bool,
+    fade_after_days: f32,
+    fade_span_days: f32,
+    fade_min_opacity: f32,
+}
+
+impl ViewSettings {
+    fn style(&self) -> SettingsStyle {
+        SettingsStyle::new()
+            .with_grid(self.show_grid.then_some(self.grid_spacing))
+            .with_edge_labels_hidden(!self.show_edge_labels)
+            .with_node_label_position(self.node_label_position)
+            .with_edges_to_center(self.edges_to_center)
+            .with_background(self.background)
+            .with_edge_label_background(self.edge_label_background)
+            .with_age_fade(self.fade_old.then(|| AgeFade {
+                threshold: days(self.fade_after_days),
+                span: days(self.fade_span_days),
+                min_opacity: self.fade_min_opacity,
+            }))
+    }
+}
+
+fn days(n: f32) -> Duration {
+    Duration::from_secs_f32(n.max(0.0) * 24.0 * 60.0 * 60.0)
+}
+
+// Neovim integration preferences of a pinboard, overriding the ones from command line
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NvimSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub srv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ext: Option<Vec<String>>,
+}
+
+impl NvimSettings {
+    fn is_empty(&self) -> bool {
+        self.srv.is_none() && self.ext.is_none()
+    }
+}
+
+// Descriptive information about a pinboard
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoardInfo {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    // Pinboards created before this was introduced have no timestamps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<SystemTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<SystemTime>,
+}
+
+impl BoardInfo {
+    fn format_time(time: Option<SystemTime>) -> String {
+        time.map(|t| humantime::format_rfc3339_seconds(t).to_string())
+            .unwrap_or("unknown".to_string())
+    }
+
+    // Multiline summary shown in tooltips
+    pub fn summary(&self) -> String {
+        let mut res = String::new();
+        if !self.author.is_empty() {
+            res.push_str(&format!("Author: {}\n", self.author));
+        }
+        res.push_str(&format!(
+            "Created: {}\nModified: {}",
+            Self::format_time(self.created),
+            Self::format_time(self.modified)
+        ));
+        if !self.description.is_empty() {
+            res.push_str(&format!("\n\n{}", self.description));
+        }
+        res
+    }
+}
+
 // A single pinboard
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Pinboard {
@@ -21,19 +278,269 @@ pub struct Pinboard {
     // this must be the same as the key in HashMap storing the pinboard.
     uuid: Uuid,
     pub title: String,
-    // underlying graph
+    // underlying graph, node positions are saved as part of it in canvas coordinates while the view
+    // transform (pan and zoom) lives in egui memory
     pub graph: PinboardGraph,
+    // Older pinboard files don't have this field
+    #[serde(default)]
+    pub view: ViewSettings,
+    #[serde(default, skip_serializing_if = "NvimSettings::is_empty")]
+    pub nvim: NvimSettings,
+    // Renumber nodes and edges densely on every save
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compact_on_save: bool,
+    // Save the pinboard file gzip compressed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compress: bool,
+    // Keep the hashes of existing blobs as they were added instead of refreshing them on open, so
+    // that content drifts are noticed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub freeze_hashes: bool,
+    // Keep nodes where they are while still allowing edits of content and relations
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lock_layout: bool,
+    // Store the real path of symlinked files when adding them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resolve_symlinks: bool,
+    // Open nested pinboards read-only, Ctrl+double-click does the opposite
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub open_children_read_only: bool,
+    // Warn about blobs with uncommitted changes in their git repository on open
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub check_git: bool,
+    // Kinds of relations defined for this pinboard in addition to the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relation_kinds: Vec<RelationKind>,
+    // Directories searched for missing blobs besides the project root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub search_roots: Vec<PathBuf>,
+    // Files larger than this many bytes are skipped when searching for missing blobs, `None` uses
+    // the default limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_search_size: Option<u64>,
+    // Whether relative blob paths are relative to the pinboard file. Loaded pinboards have their
+    // paths resolved, older files have them relative to the working directory instead.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub relative_paths: bool,
+    // Directory chosen for blob paths to be stored relative to instead of the directory of the
+    // pinboard file. Blobs outside of it keep their absolute paths. It is stored relative to the
+    // pinboard file as well.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_base: Option<PathBuf>,
+    #[serde(default)]
+    pub info: BoardInfo,
 }
 
 impl Pinboard {
     pub fn get_uuid(&self) -> &Uuid {
         &self.uuid
     }
+
+    // Give a copy of a pinboard an identity of its own
+    pub fn renew_uuid(&mut self) {
+        self.uuid = Uuid::new_v4();
+    }
+
     pub fn new(title: String, graph: PinboardGraph) -> Self {
         Self {
             title,
             graph,
             uuid: Uuid::new_v4(),
+            view: ViewSettings::default(),
+            nvim: NvimSettings::default(),
+            compact_on_save: false,
+            compress: false,
+            freeze_hashes: false,
+            lock_layout: false,
+            resolve_symlinks: false,
+            open_children_read_only: false,
+            check_git: false,
+            relation_kinds: Vec::new(),
+            search_roots: Vec::new(),
+            max_search_size: None,
+            relative_paths: false,
+            path_base: None,
+            info: BoardInfo {
+                created: Some(SystemTime::now()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Pinboard {
+    // Built-in relations followed by the user-defined ones
+    pub fn relations(&self) -> Vec<Relation> {
+        Relation::ALL
+            .into_iter()
+            .chain(self.relation_kinds.iter().cloned().map(Relation::Custom))
+            .collect()
+    }
+
+    // Replace the relation kind of every connection using it, `None` turns them into `Related`
+    pub fn replace_relation_kind(&mut self, old: &RelationKind, new: Option<RelationKind>) {
+        let new = new.map_or(Relation::Related, Relation::Custom);
+        for edge in self.graph.g.edge_weights_mut() {
+            if !matches!(&edge.payload().relation, Relation::Custom(k) if k.id == old.id) {
+                continue;
+            }
+            // Labels of commented edges show the comment instead
+            if edge.payload().comment.is_none() {
+                edge.set_label(new.label());
+            }
+            edge.payload_mut().relation = new.clone();
+        }
+    }
+
+    // Call the function on blobs of every node and edge
+    pub fn for_each_blob(&mut self, mut f: impl FnMut(&mut Blob)) {
+        for n in self.graph.g.node_weights_mut() {
+            if let Some(blob) = n.payload_mut().blob.as_mut() {
+                f(blob);
+            }
+        }
+        for e in self.graph.g.edge_weights_mut() {
+            if let Some(blob) = e.payload_mut().comment.as_mut() {
+                f(blob);
+            }
+        }
+    }
+
+    // Read snippets of files shown on cards, which aren't saved in pinboard files
+    pub fn load_snippets(&mut self) {
+        for n in self.graph.g.node_weights_mut() {
+            if let Some(blob) = n.payload_mut().blob.as_mut() {
+                blob.load_snippet();
+            }
+        }
+    }
+
+    // Store paths of blobs relative to the directory of the pinboard file, so that moving the
+    // directory along with the files keeps them. Blobs sharing nothing with it but the root keep
+    // their absolute paths. With a path base, paths are relative to the base instead.
+    pub fn relativize_paths(&mut self, file: &Path) {
+        let Some(dir) = file.parent() else {
+            return;
+        };
+        match self.path_base.take() {
+            Some(base) => {
+                self.for_each_blob(|blob| {
+                    if let Some(path) = blob.relative_to(&base).filter(|_| blob.is_local()) {
+                        blob.set_path(path);
+                    }
+                });
+                self.path_base = Some(graph::relative_path(&base, dir).unwrap_or(base));
+            }
+            None => self.for_each_blob(|blob| {
+                if let Some(path) =
+                    graph::relative_path(blob.path(), dir).filter(|_| blob.is_local())
+                {
+                    blob.set_path(path);
+                }
+            }),
+        }
+        self.relative_paths = true;
+    }
+
+    // Make relative paths of blobs absolute after loading the pinboard from the file
+    pub fn resolve_paths(&mut self, file: &Path) -> Result<()> {
+        let base = if std::mem::take(&mut self.relative_paths) {
+            let dir = std::path::absolute(file)?
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            match &mut self.path_base {
+                Some(base) => {
+                    *base = graph::resolve_path(&dir, base);
+                    base.clone()
+                }
+                None => dir,
+            }
+        } else {
+            std::env::current_dir()?
+        };
+        self.for_each_blob(|blob| {
+            if blob.is_local() && blob.path().is_relative() {
+                blob.set_path(graph::resolve_path(&base, blob.path()));
+            }
+        });
+        Ok(())
+    }
+
+    // Add copied nodes around the canvas position, returning the new nodes
+    pub fn insert(&mut self, clipboard: &Clipboard, pos: Pos2) -> Vec<NodeIndex> {
+        let ids: Vec<NodeIndex> = clipboard
+            .nodes
+            .iter()
+            .map(|(data, label, offset)| {
+                let mut data = data.clone();
+                data.touch();
+                let id = self.graph.add_node_with_location(data, pos + *offset);
+                if let Some(n) = self.graph.node_mut(id) {
+                    n.set_label(label.clone());
+                }
+                id
+            })
+            .collect();
+        for (a, b, conn, label) in &clipboard.edges {
+            // Custom relations come along into pinboards which don't define them
+            if let Relation::Custom(kind) = &conn.relation {
+                if !self.relation_kinds.contains(kind) {
+                    self.relation_kinds.push(kind.clone());
+                }
+            }
+            self.graph
+                .add_edge_with_label(ids[*a], ids[*b], conn.clone(), label.clone());
+        }
+        ids
+    }
+
+    // Serialize the pinboard into the content of a pinboard file
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let content = serde_json::to_vec(self)?;
+        if !self.compress {
+            return Ok(content);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&content)?;
+        Ok(encoder.finish()?)
+    }
+
+    // Deserialize a pinboard file, which might be compressed
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut pinboard: Self = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut content = Vec::new();
+            GzDecoder::new(bytes).read_to_end(&mut content)?;
+            serde_json::from_slice(&content)?
+        } else {
+            serde_json::from_slice(bytes)?
+        };
+        pinboard.identify_relation_kinds();
+        Ok(pinboard)
+    }
+
+    // Older files have relation kinds without ids, connections are matched to them by name and
+    // color once
+    fn identify_relation_kinds(&mut self) {
+        for kind in &mut self.relation_kinds {
+            if kind.id.is_nil() {
+                kind.id = Uuid::new_v4();
+            }
+        }
+        for edge in self.graph.g.edge_weights_mut() {
+            let Relation::Custom(old) = &edge.payload().relation else {
+                continue;
+            };
+            if !old.id.is_nil() {
+                continue;
+            }
+            if let Some(kind) = self
+                .relation_kinds
+                .iter()
+                .find(|k| k.name == old.name && k.color == old.color)
+            {
+                edge.payload_mut().relation = Relation::Custom(kind.clone());
+            }
         }
     }
 }
@@ -44,15 +551,209 @@ impl Default for Pinboard {
             uuid: Uuid::default(),
             title: String::new(),
             graph: PinboardGraph::from(&StableGraph::default()),
+            view: ViewSettings::default(),
+            nvim: NvimSettings::default(),
+            compact_on_save: false,
+            compress: false,
+            freeze_hashes: false,
+            lock_layout: false,
+            resolve_symlinks: false,
+            open_children_read_only: false,
+            check_git: false,
+            relation_kinds: Vec::new(),
+            search_roots: Vec::new(),
+            max_search_size: None,
+            relative_paths: false,
+            path_base: None,
+            info: BoardInfo::default(),
+        }
+    }
+}
+
+// Actions available both from the menus and the compact toolbar
+#[derive(Clone, Copy)]
+enum Action {
+    Save,
+    Rename,
+    AddNode,
+    Fit,
+    RandomNode,
+    ResetView,
+}
+
+impl Action {
+    const TOOLBAR: [Action; 6] = [
+        Action::Save,
+        Action::Rename,
+        Action::AddNode,
+        Action::Fit,
+        Action::RandomNode,
+        Action::ResetView,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Action::Save => "Save",
+            Action::Rename => "Rename",
+            Action::AddNode => "Add node",
+            Action::Fit => "Fit to screen",
+            Action::RandomNode => "Random node",
+            Action::ResetView => "Reset View",
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            Action::Save => "💾",
+            Action::Rename => "✏",
+            Action::AddNode => "➕",
+            Action::Fit => "⛶",
+            Action::RandomNode => "🎲",
+            Action::ResetView => "⟲",
+        }
+    }
+
+    // Whether the action changes the pinboard, which read-only pinboards don't allow
+    fn edits(&self) -> bool {
+        matches!(self, Action::Rename | Action::AddNode)
+    }
+
+    fn shortcut(&self) -> Option<KeyboardShortcut> {
+        match self {
+            Action::Save => Some(shortcut(Command::Save)),
+            Action::Rename => Some(shortcut(Command::Rename)),
+            Action::AddNode => Some(shortcut(Command::AddNode)),
+            Action::Fit => Some(shortcut(Command::Fit)),
+            Action::RandomNode | Action::ResetView => None,
+        }
+    }
+
+    fn shortcut_text(&self, ctx: &Context) -> String {
+        self.shortcut()
+            .map(|s| ctx.format_shortcut(&s))
+            .unwrap_or_default()
+    }
+
+    fn hover_text(&self, ctx: &Context) -> String {
+        match self.shortcut() {
+            Some(s) => format!("{} ({})", self.label(), ctx.format_shortcut(&s)),
+            None => self.label().to_string(),
         }
     }
 }
 
+#[derive(Clone, Copy)]
 enum Either {
     Edge(EdgeIndex),
     Node(NodeIndex),
 }
 
+// Nodes copied from a pinboard along with the edges among them, shared by all pinboards
+#[derive(Clone)]
+pub struct Clipboard {
+    // Payload, label and position relative to the center of the copied nodes
+    nodes: Vec<(NodeData, String, Vec2)>,
+    // Indices into `nodes` of the endpoints, connection and label
+    edges: Vec<(usize, usize, Conn, String)>,
+}
+
+impl Clipboard {
+    // Text put on the OS clipboard along with the nodes, never empty
+    fn text(&self) -> String {
+        let labels: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|(_, label, _)| label.as_str())
+            .filter(|l| !l.is_empty())
+            .collect();
+        if labels.is_empty() {
+            format!("{} nodes", self.nodes.len())
+        } else {
+            labels.join("\n")
+        }
+    }
+}
+
+// Action on problems listed by the health bar, `None` paths apply to every problem of the kind
+enum HealthAction {
+    Accept(Option<PathBuf>),
+    Repair(Option<PathBuf>),
+    RemoveLost,
+    Relocate(PathBuf),
+    Jump(PathBuf),
+    JumpLabel(String),
+}
+
+// What the relation picked in the relation picker applies to
+enum RelationTarget {
+    // Connect the two nodes with a new edge
+    Connect(NodeIndex, NodeIndex),
+    // Change the relation of existing edges
+    Edges(Vec<EdgeIndex>),
+}
+
+// Popup picking a relation by number keys or by typing
+struct RelationPicker {
+    target: RelationTarget,
+    query: String,
+    pos: Pos2,
+}
+
+// Link being entered for a new link node
+#[derive(Default)]
+struct LinkDraft {
+    // Canvas position of the new node
+    pos: Option<Pos2>,
+    link: String,
+    title: String,
+}
+
+// What the view is moved to
+#[derive(Clone, PartialEq, Debug)]
+enum FocusTarget {
+    // Center on the node
+    Node(NodeIndex),
+    // Pan and zoom so that all of the nodes are in view
+    Frame(Vec<NodeIndex>),
+}
+
+// Canvas position to center and zoom factor which frame the rects in a view of the size
+fn fit_frame(rects: impl IntoIterator<Item = Rect>, view: Vec2) -> Option<(Pos2, f32)> {
+    let bounds = rects
+        .into_iter()
+        .reduce(|a, b| a.union(b))?
+        .expand(FIT_MARGIN);
+    let zoom = (view.x / bounds.width()).min(view.y / bounds.height());
+    Some((bounds.center(), zoom))
+}
+
+// Ongoing pan (and zoom) of the view towards a focused node or nodes to frame
+struct FocusAnimation {
+    target: FocusTarget,
+    // Canvas position at the center of the view and zoom factor when the animation started
+    from: Pos2,
+    from_zoom: f32,
+    start: f64,
+}
+
+impl FocusAnimation {
+    // Canvas position to be centered and zoom factor at time `t` in [0, 1], eased in and out
+    fn at(&self, t: f32, to: Pos2, to_zoom: f32) -> (Pos2, f32) {
+        let t = t.clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+        (
+            self.from.lerp(to, t),
+            self.from_zoom + (to_zoom - self.from_zoom) * t,
+        )
+    }
+}
+
+// State of a pinboard restored by undo and redo
+struct Snapshot {
+    graph: PinboardGraph,
+    relation_kinds: Vec<RelationKind>,
+}
+
 // A single pinboard buffer, handles the opening etc
 pub struct PinboardBuffer {
     pub pinboard: Pinboard,
@@ -67,11 +768,97 @@ pub struct PinboardBuffer {
 
     // UI related states
     show_rename_modal: bool,
+    // Node being renamed and the label being edited
+    renaming_node: Option<(NodeIndex, String)>,
+    show_properties_modal: bool,
+    show_layout_modal: bool,
+    show_relations_modal: bool,
+    relation_picker: Option<RelationPicker>,
+    link_draft: Option<LinkDraft>,
+    // Node whose note is being edited
+    editing_note: Option<NodeIndex>,
+    // Tags selected in the filter bar, nodes carrying none of them are dimmed or hidden
+    tag_filter: BTreeSet<String>,
+    hide_filtered: bool,
+    // Relation the last edge was created with, offered as the default for new edges
+    last_relation: Relation,
+    // Nodes waiting for user's confirmation to be opened together
+    nodes_to_open: Option<Vec<NodeIndex>>,
+    // Accumulated movement of each node during the current drag
+    drag_moves: Option<HashMap<NodeIndex, Vec2>>,
+    // Graphs before each edit, most recent last
+    undo_stack: Vec<Snapshot>,
+    // Graphs before each undo, most recent last, cleared by new edits
+    redo_stack: Vec<Snapshot>,
+    // Widget being edited since the last checkpoint
+    edit_checkpoint: Option<Id>,
+    // Node to center the view on or nodes to frame in the next frame
+    focus: Option<FocusTarget>,
+    focus_animation: Option<FocusAnimation>,
+    // Steps of the running force-directed layout done so far
+    force_layout: Option<usize>,
+    // Show the list view instead of the graph
+    show_list: bool,
+    // Show the panel listing comments of all edges
+    show_comments: bool,
+    // Show the understanding progress computed from relations
+    show_progress: bool,
+    // Show the pane rendering the markdown file of the selected node
+    show_preview: bool,
+    preview: MarkdownPreview,
+    hover_previews: HoverPreviews,
+    node_list: NodeList,
+    // Screen rect of the graph view in the last frame, used to crop screenshots
+    graph_rect: Option<Rect>,
+    // Screen rect of the window in the last frame
+    window_rect: Option<Rect>,
+    // Disallow editing and saving the pinboard
+    read_only: bool,
+    // Pinboard whose node this one has been opened from
+    parent: Option<Uuid>,
+    // Titles of the pinboards up the parent chain, kept up to date by the app
+    ancestors: Vec<(Uuid, String)>,
+    navigate_to: Option<Uuid>,
+    // Whether the window is to be closed once the save in progress succeeds
+    close_after_save: bool,
+    // Set once that save succeeded, for the app to close the window
+    saved_for_close: bool,
+    // Whether pinboards among the blobs being opened should be opened read-only
+    open_read_only: bool,
+    // How files among the blobs being opened should be opened
+    open_with: Opener,
+    // Rows of the last edge import which couldn't be imported, with their line numbers
+    unmatched_rows: Option<Vec<(usize, String)>>,
+    // Base directory of the last rewrite of blob paths and the blobs which were outside of it
+    outside_base: Option<(PathBuf, Vec<PathBuf>)>,
+    // Problems found by the health check, until dismissed
+    health: Option<HealthReport>,
+    show_health_details: bool,
+    // Whether the running health check has been requested by the user
+    validating: bool,
+    // Missing blob whose hash matches several files, waiting for the user to pick one
+    ambiguous_match: Option<(Either, Vec<PathBuf>)>,
+    // Error of the last failed save, until the user decides what to do
+    save_error: Option<String>,
 
     // Promises
-    save_file_promise: Option<Promise<Result<PathBuf>>>,
-    update_blob_promise: Option<Promise<(Either, Result<Blob>)>>,
-    update_blob_and_open_promise: Option<Promise<(Either, Result<Blob>)>>,
+    // Resolves to `None` if the user canceled picking a path
+    save_file_promise: Option<Promise<Result<Option<PathBuf>>>>,
+    export_promise: Option<Promise<Result<()>>>,
+    import_promise: Option<Promise<Result<Option<String>>>>,
+    // Resolves to `None` if the user canceled picking a file
+    update_blob_promise: Option<Promise<(Either, Result<Option<Blob>>)>>,
+    update_blob_and_open_promise: Option<Promise<BlobUpdates>>,
+    health_promise: Option<Promise<HealthReport>>,
+    // Blobs of files dropped onto the graph, with the canvas position they were dropped at
+    drop_promise: Option<Promise<(Pos2, Vec<Result<Blob>>)>>,
+    // Resolves to `None` if the user canceled picking a directory
+    base_dir_promise: Option<Promise<Result<Option<PathBuf>>>>,
+    search_root_promise: Option<Promise<Result<Option<PathBuf>>>>,
+    // Nodes being extracted and the blob of the sub-board they were saved to, `None` if the user
+    // canceled picking the file
+    extract_promise: Option<Promise<Extraction>>,
+    relocate_promise: Option<Promise<Result<Relocation>>>,
 }
 
 impl Default for PinboardBuffer {
@@ -83,9 +870,59 @@ impl Default for PinboardBuffer {
             event_publisher,
             event_receiver,
             show_rename_modal: false,
+            renaming_node: None,
+            show_properties_modal: false,
+            show_layout_modal: false,
+            show_relations_modal: false,
+            relation_picker: None,
+            link_draft: None,
+            editing_note: None,
+            tag_filter: BTreeSet::new(),
+            hide_filtered: false,
+            last_relation: Relation::Related,
+            nodes_to_open: None,
+            drag_moves: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            edit_checkpoint: None,
+            focus: None,
+            focus_animation: None,
+            force_layout: None,
+            show_list: false,
+            show_comments: false,
+            show_progress: false,
+            show_preview: false,
+            preview: MarkdownPreview::default(),
+            hover_previews: HoverPreviews::default(),
+            node_list: NodeList::default(),
+            graph_rect: None,
+            window_rect: None,
+            read_only: false,
+            parent: None,
+            ancestors: Vec::new(),
+            navigate_to: None,
+            close_after_save: false,
+            saved_for_close: false,
+            open_read_only: false,
+            open_with: Opener::Auto,
+            unmatched_rows: None,
+            outside_base: None,
+            health: None,
+            show_health_details: false,
+            validating: false,
+            ambiguous_match: None,
+            save_error: None,
             save_file_promise: None,
+            export_promise: None,
+            import_promise: None,
             update_blob_promise: None,
             update_blob_and_open_promise: None,
+            drop_promise: None,
+            health_promise: None,
+            base_dir_promise: None,
+            search_root_promise: None,
+            relocate_promise: None,
+            extract_promise: None,
             unsaved: false,
         }
     }
@@ -100,40 +937,231 @@ impl PinboardBuffer {
             ..Default::default()
         }
     }
-    async fn save_as(pinboard: Pinboard) -> anyhow::Result<PathBuf> {
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    // Tell the pinboard apart from another one sharing its UUID, e.g. a copy of its file
+    pub fn renew_uuid(&mut self) {
+        self.pinboard.renew_uuid();
+        self.unsaved = true;
+    }
+
+    // Look for broken links and other problems in the background
+    pub fn check_health(&mut self) {
+        let check = HealthCheck::collect(
+            &self.pinboard.graph,
+            self.path.as_deref(),
+            self.search_scope(),
+            self.pinboard.check_git,
+        );
+        self.health_promise = Some(Promise::spawn_blocking(move || check.run()));
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn parent(&self) -> Option<Uuid> {
+        self.parent
+    }
+
+    pub fn set_parent(&mut self, parent: Option<Uuid>) {
+        self.parent = parent;
+    }
+
+    // Pinboards this one has been drilled into from, starting from the outermost one
+    pub fn set_ancestors(&mut self, ancestors: Vec<(Uuid, String)>) {
+        self.ancestors = ancestors;
+    }
+
+    // Ancestor the user clicked in the breadcrumb bar, if any
+    pub fn take_navigation(&mut self) -> Option<Uuid> {
+        self.navigate_to.take()
+    }
+
+    // Save the pinboard and have its window closed once saving succeeded
+    pub fn save_and_close(&mut self) {
+        self.save();
+        self.close_after_save = self.save_file_promise.is_some();
+    }
+
+    // Whether the pinboard has been saved to be closed
+    pub fn take_close(&mut self) -> bool {
+        std::mem::take(&mut self.saved_for_close)
+    }
+
+    // Whether a dialog raised by a background task is waiting for the user, e.g. after an autosave
+    // failed. Pinboards in background tabs are brought to front to show it.
+    pub fn needs_attention(&self) -> bool {
+        self.save_error.is_some()
+            || self.unmatched_rows.is_some()
+            || self.outside_base.is_some()
+            || self.ambiguous_match.is_some()
+    }
+
+    fn show_breadcrumbs(&mut self, ui: &mut Ui) {
+        if self.ancestors.is_empty() {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            for (uuid, title) in &self.ancestors {
+                if ui.link(title).clicked() {
+                    self.navigate_to = Some(*uuid);
+                }
+                ui.label("›");
+            }
+            ui.strong(&self.pinboard.title);
+        });
+        ui.separator();
+    }
+
+    pub fn open_read_only(&self) -> bool {
+        self.open_read_only
+    }
+
+    pub fn open_with(&self) -> Opener {
+        self.open_with
+    }
+
+    // Ask for a path and save there, `None` if the user canceled
+    async fn save_as(pinboard: Pinboard) -> anyhow::Result<Option<PathBuf>> {
         if let Some(path) = FileDialog::new()
             // https://github.com/PolyMeilex/rfd/issues/235
-            .set_directory(Path::new(".").canonicalize()?)
+            .set_directory(config::dialog_dir()?)
             .add_filter("Pinboard", &["pinbrd"])
             .save_file()
         {
-            Self::save_to_path(pinboard, path).await
+            Self::save_to_path(pinboard, path).await.map(Some)
         } else {
-            Err(anyhow::anyhow!(
-                "user didn't select path to save pinboard {}",
-                pinboard.title
-            ))
+            Ok(None)
+        }
+    }
+
+    // Write to a temporary file next to the target first and rename it over the target, so that a
+    // failed write never leaves a truncated pinboard behind. Transient failures are retried with
+    // backoff.
+    async fn save_to_path(mut pinboard: Pinboard, path: PathBuf) -> anyhow::Result<PathBuf> {
+        pinboard.relativize_paths(&path);
+        let bytes = pinboard.to_bytes()?;
+        let tmp = path.with_file_name(format!(
+            ".{}.tmp",
+            path.file_name()
+                .ok_or(anyhow!("{:?} is not a file path", path))?
+                .to_string_lossy()
+        ));
+        let mut delay = SAVE_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let res = async {
+                tokio::fs::write(&tmp, &bytes).await?;
+                tokio::fs::rename(&tmp, &path).await
+            }
+            .await;
+            match res {
+                Ok(()) => return Ok(path),
+                Err(e) if attempt < SAVE_ATTEMPTS => {
+                    warn!(
+                        "saving to {:?} failed (attempt {}/{}): {}, retrying",
+                        path, attempt, SAVE_ATTEMPTS, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    // Don't leave the temporary file around, it may not exist at all
+                    let _ = tokio::fs::remove_file(&tmp).await;
+                    return Err(e.into());
+                }
+            }
         }
     }
 
-    async fn save_to_path(pinboard: Pinboard, path: PathBuf) -> anyhow::Result<PathBuf> {
-        let content = serde_json::to_string(&pinboard)?;
-        tokio::fs::write(&path, &content).await?;
-        Ok(path)
+    // Save to a new path picked by the user
+    fn save_as_new(&mut self) {
+        let pinboard = self.pinboard.clone();
+        self.save_file_promise = Some(Promise::spawn_async(Self::save_as(pinboard)));
+    }
+
+    pub fn save(&mut self) {
+        if self.read_only {
+            warn!("pinboard {} is read-only, not saving", self.pinboard.title);
+            return;
+        }
+        // Pending promises refer to the old indices, so we postpone compacting to the next save
+        if self.pinboard.compact_on_save
+            && self.update_blob_promise.is_none()
+            && self.update_blob_and_open_promise.is_none()
+            && self.extract_promise.is_none()
+        {
+            self.pinboard.graph = graph::compact(&self.pinboard.graph);
+            self.nodes_to_open = None;
+            self.editing_note = None;
+        }
+        self.write();
     }
 
-    fn save(&mut self) {
+    // Save the pinboard as it is, without compacting it or resetting anything shown
+    fn write(&mut self) {
+        self.pinboard.info.modified = Some(SystemTime::now());
         let path = self.path.clone();
         let pinboard = self.pinboard.clone();
         self.save_file_promise = Some(Promise::spawn_async(async {
             if let Some(path) = path {
-                Self::save_to_path(pinboard, path).await
+                Self::save_to_path(pinboard, path).await.map(Some)
             } else {
                 Self::save_as(pinboard).await
             }
         }));
     }
 
+    // Save the pinboard to its file if it has unsaved changes, never asking for a path. Pinboards
+    // whose last save failed are left alone until the user dealt with it.
+    pub fn autosave(&mut self) {
+        if self.unsaved
+            && self.path.is_some()
+            && !self.read_only
+            && self.save_file_promise.is_none()
+            && self.save_error.is_none()
+        {
+            self.write();
+        }
+    }
+
+    fn show_save_failed_dialog(&mut self, ui: &Ui) {
+        let Some(error) = &self.save_error else {
+            return;
+        };
+        let (mut retry, mut save_as, mut cancel) = (false, false, false);
+        Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+            ui.label(format!("Saving {} failed:", self.pinboard.title));
+            ui.colored_label(ui.visuals().error_fg_color, error);
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                retry = ui.button("Retry").clicked();
+                save_as = ui.button("Save As...").clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if retry || save_as || cancel {
+            self.save_error = None;
+        }
+        if retry {
+            self.save();
+        } else if save_as {
+            self.save_as_new();
+        }
+    }
+
     fn show_rename_dialog(&mut self, ui: &Ui) {
         if self.show_rename_modal {
             Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
@@ -151,314 +1179,3301 @@ impl PinboardBuffer {
         }
     }
 
-    // Get project root for the pinboard
-    fn get_root(&self) -> PathBuf {
-        giro::git_root(self.path.as_ref().unwrap())
-            .unwrap_or(None)
-            .unwrap_or(Path::new(".").to_path_buf())
+    fn start_renaming_node(&mut self, node: NodeIndex) {
+        if let Some(n) = self.pinboard.graph.node(node) {
+            self.renaming_node = Some((node, n.label()));
+        }
     }
 
-    fn handle_events(&mut self) {
-        for e in self.event_receiver.try_iter() {
-            match e {
-                Event::EdgeDoubleClick(payload) => {
-                    let root = self.get_root();
-                    let edge_id = EdgeIndex::new(payload.id);
-
-                    if let Some(mut blob) = self
-                        .pinboard
-                        .graph
-                        .edge(edge_id)
-                        .map(|e| e.payload().comment.clone())
-                        .flatten()
-                    {
-                        self.update_blob_and_open_promise =
-                            Some(Promise::spawn_blocking(move || -> _ {
-                                match blob.update(&root) {
-                                    Ok(()) => (Either::Edge(edge_id), Ok(blob)),
-                                    Err(e) => (Either::Edge(edge_id), Err(e)),
-                                }
-                            }));
-                        return;
-                    }
-                }
-                Event::NodeDoubleClick(payload) => {
-                    let root = self.get_root();
-                    let node_id = NodeIndex::new(payload.id);
+    // Set a custom label of the node, an empty label goes back to the name of its blob
+    fn rename_node(&mut self, node: NodeIndex, label: &str) {
+        let label = label.trim();
+        self.checkpoint();
+        let Some(n) = self.pinboard.graph.node_mut(node) else {
+            return;
+        };
+        let name = n
+            .payload()
+            .blob
+            .as_ref()
+            .map(Blob::name)
+            .unwrap_or_else(|| node.index().to_string());
+        n.payload_mut().label = Some(label.to_string()).filter(|l| !l.is_empty() && *l != name);
+        let label = n.payload().label_or(name);
+        n.set_label(label);
+        n.payload_mut().touch();
+        self.unsaved = true;
+    }
 
-                    if let Some(mut blob) = self
-                        .pinboard
-                        .graph
-                        .node(node_id)
-                        .map(|n| n.payload().clone())
-                        .flatten()
-                    {
-                        self.update_blob_and_open_promise =
-                            Some(Promise::spawn_blocking(move || -> _ {
-                                match blob.update(&root) {
-                                    Ok(()) => (Either::Node(node_id), Ok(blob)),
-                                    Err(e) => (Either::Node(node_id), Err(e)),
-                                }
-                            }));
-                        return;
-                    }
-                }
-                Event::NodeMove(_) => self.unsaved = true,
-                _ => {}
+    fn show_rename_node_dialog(&mut self, ui: &Ui) {
+        let Some((node, label)) = self.renaming_node.as_mut() else {
+            return;
+        };
+        let node = *node;
+        let blob = self
+            .pinboard
+            .graph
+            .node(node)
+            .and_then(|n| n.payload().blob.as_ref());
+        let (mut done, mut cancelled) = (false, false);
+        Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+            ui.label("Enter the label of the node, leave it empty to use the name of its content:");
+            ui.add_space(10.0);
+            let resp = ui.text_edit_singleline(label);
+            resp.request_focus();
+            done = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            if let Some(blob) = blob {
+                ui.weak(format!("Content: {}", blob.name()));
             }
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                done |= ui.button("Done").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+        if done {
+            let label = label.clone();
+            self.rename_node(node, &label);
+            self.renaming_node = None;
+        } else if cancelled {
+            self.renaming_node = None;
         }
     }
 
-    async fn add_blob(root: PathBuf) -> Result<Blob> {
-        let path = FileDialog::new()
-            // https://github.com/PolyMeilex/rfd/issues/235
-            .set_directory(root.canonicalize()?)
-            .pick_file()
-            .ok_or(anyhow!("user didn't select file"))?;
-
-        match path.extension().map(|s| s.to_str()).flatten() {
-            Some("pinbrd") => Blob::new(BlobType::PinboardGraph, path.to_path_buf()).await,
-            _ => Blob::new(BlobType::File, path.to_path_buf()).await,
+    fn show_link_dialog(&mut self, ui: &Ui) {
+        let Some(draft) = self.link_draft.as_mut() else {
+            return;
+        };
+        let mut done = false;
+        let mut cancel = false;
+        Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+            egui::Grid::new("link").num_columns(2).show(ui, |ui| {
+                ui.label("URL");
+                ui.text_edit_singleline(&mut draft.link).request_focus();
+                ui.end_row();
+                ui.label("Title");
+                ui.add(egui::TextEdit::singleline(&mut draft.title).hint_text("Optional"));
+                ui.end_row();
+            });
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                done = ui
+                    .add_enabled(!draft.link.trim().is_empty(), Button::new("Add"))
+                    .clicked();
+                cancel = ui.button("Cancel").clicked();
+            });
+        });
+        if done {
+            let draft = self.link_draft.take().unwrap();
+            self.add_link_node(draft);
+        } else if cancel {
+            self.link_draft = None;
+        }
+    }
+
+    fn add_link_node(&mut self, draft: LinkDraft) {
+        let blob = Blob::url(draft.link.trim(), Some(draft.title.trim().to_string()));
+        let label = blob.name();
+        let data = NodeData {
+            blob: Some(blob),
+            ..NodeData::new()
+        };
+        self.checkpoint();
+        let id = match draft.pos {
+            Some(pos) => self.pinboard.graph.add_node_with_location(data, pos),
+            None => self.pinboard.graph.add_node(data),
+        };
+        if let Some(n) = self.pinboard.graph.node_mut(id) {
+            n.set_label(label);
+        }
+        self.unsaved = true;
+    }
+
+    fn add_note_node(&mut self, pos: Option<Pos2>) -> NodeIndex {
+        let data = NodeData {
+            blob: Some(Blob::note(String::new())),
+            ..NodeData::new()
+        };
+        self.checkpoint();
+        let id = match pos {
+            Some(pos) => self.pinboard.graph.add_node_with_location(data, pos),
+            None => self.pinboard.graph.add_node(data),
+        };
+        if let Some(n) = self.pinboard.graph.node_mut(id) {
+            n.set_label("Note".to_string());
+        }
+        self.editing_note = Some(id);
+        self.unsaved = true;
+        id
+    }
+
+    fn show_note_editor(&mut self, ctx: &Context, id: Id) {
+        let Some(node) = self.editing_note else {
+            return;
+        };
+        // The node might have been deleted or undone in the meantime
+        let Some((label, mut text)) = self.pinboard.graph.node(node).and_then(|n| {
+            let text = n.payload().blob.as_ref()?.note_text()?.to_string();
+            Some((n.label(), text))
+        }) else {
+            self.editing_note = None;
+            return;
+        };
+        let mut open = true;
+        let mut resp = None;
+        Window::new(format!("Note: {}", label))
+            .id(id.with(("note", node)))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                resp = Some(
+                    ui.add_enabled(
+                        !self.read_only,
+                        egui::TextEdit::multiline(&mut text)
+                            .desired_rows(12)
+                            .desired_width(f32::INFINITY),
+                    ),
+                );
+            });
+        if resp.is_some_and(|resp| self.edited(&resp)) {
+            let n = self.pinboard.graph.node_mut(node).unwrap();
+            if let Some(blob) = n.payload_mut().blob.as_mut() {
+                blob.set_note(text);
+                let name = blob.name();
+                let label = n.payload().label_or(name);
+                n.set_label(label);
+            }
+            n.payload_mut().touch();
+            self.unsaved = true;
+        }
+        if !open {
+            self.editing_note = None;
+        }
+    }
+
+    // Get project root for the pinboard
+    fn get_root(&self) -> PathBuf {
+        self.path
+            .as_ref()
+            .and_then(|p| giro::git_root(p).unwrap_or(None))
+            .unwrap_or(Path::new(".").to_path_buf())
+    }
+
+    // Where to look for missing blobs: the project root, then the directories configured for the
+    // pinboard and on the command line. Unsaved pinboards have no project root.
+    fn search_scope(&self) -> SearchScope {
+        let mut roots: Vec<PathBuf> = self.path.iter().map(|_| self.get_root()).collect();
+        roots.extend(self.pinboard.search_roots.iter().cloned());
+        roots.extend(REPAIR_ROOTS.read().unwrap().iter().cloned());
+        SearchScope {
+            roots,
+            max_size: self
+                .pinboard
+                .max_search_size
+                .unwrap_or(graph::DEFAULT_MAX_SEARCH_SIZE),
+        }
+    }
+
+    // Update the given blobs and hand them over to be opened once done
+    fn open_blobs(&mut self, mut blobs: Vec<(Either, Blob)>) {
+        // Notes of nodes are opened in the editor instead
+        blobs.retain(|(either, blob)| match either {
+            Either::Node(id) if *blob.ty() == BlobType::Note => {
+                self.editing_note = Some(*id);
+                false
+            }
+            _ => true,
+        });
+        self.open_read_only = self.pinboard.open_children_read_only;
+        self.open_with = Opener::Auto;
+        let scope = self.search_scope();
+        let refresh_hash = !self.pinboard.freeze_hashes;
+        self.update_blob_and_open_promise = Some(Promise::spawn_blocking(move || -> _ {
+            blobs
+                .into_iter()
+                .map(
+                    |(either, mut blob)| match blob.update(&scope, refresh_hash) {
+                        Ok(()) => (either, Ok(blob)),
+                        Err(e) => (either, Err(e)),
+                    },
+                )
+                .collect()
+        }));
+    }
+
+    // Open blobs of all the given nodes, nodes without blobs are skipped
+    fn open_nodes(&mut self, nodes: &[NodeIndex]) {
+        let blobs = nodes
+            .iter()
+            .filter_map(|id| {
+                self.pinboard
+                    .graph
+                    .node(*id)
+                    .and_then(|n| n.payload().blob.clone())
+                    .map(|b| (Either::Node(*id), b))
+            })
+            .collect();
+        self.open_blobs(blobs);
+    }
+
+    fn export_manifest(&mut self) {
+        let entries = export::manifest(&self.pinboard.graph);
+        self.export_promise = Some(Promise::spawn_async(async move {
+            let Some(path) = FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .add_filter("JSON", &["json"])
+                .add_filter("CSV", &["csv"])
+                .save_file()
+            else {
+                info!("user didn't select path to export manifest");
+                return Ok(());
+            };
+            export::export_manifest(entries, path).await
+        }));
+    }
+
+    // Ask for a screenshot of the whole viewport, which arrives as an event in a later frame
+    fn request_screenshot(&self, ctx: &Context) {
+        ctx.send_viewport_cmd(ViewportCommand::Screenshot(UserData::new(
+            self.pinboard.uuid,
+        )));
+    }
+
+    fn handle_screenshot(&mut self, ctx: &Context) {
+        let image = ctx.input(|i| {
+            i.raw.events.iter().find_map(|e| match e {
+                egui::Event::Screenshot {
+                    user_data, image, ..
+                } if user_data
+                    .data
+                    .as_ref()
+                    .and_then(|d| d.downcast_ref::<Uuid>())
+                    == Some(&self.pinboard.uuid) =>
+                {
+                    Some(image.clone())
+                }
+                _ => None,
+            })
+        });
+        if let Some(image) = image {
+            self.save_screenshot(image, ctx.pixels_per_point());
+        }
+    }
+
+    fn save_screenshot(&mut self, image: Arc<ColorImage>, pixels_per_point: f32) {
+        let shot = match self.graph_rect {
+            Some(rect) => image.region(&rect, Some(pixels_per_point)),
+            None => (*image).clone(),
+        };
+        self.export_promise = Some(Promise::spawn_blocking(move || {
+            let Some(path) = FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .add_filter("PNG", &["png"])
+                .save_file()
+            else {
+                info!("user didn't select path to save screenshot");
+                return Ok(());
+            };
+            image::save_buffer(
+                path,
+                shot.as_raw(),
+                shot.width() as u32,
+                shot.height() as u32,
+                image::ExtendedColorType::Rgba8,
+            )?;
+            Ok(())
+        }));
+    }
+
+    fn import_edges(&mut self) {
+        self.import_promise = Some(Promise::spawn_async(async move {
+            let Some(path) = FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .add_filter("CSV", &["csv"])
+                .pick_file()
+            else {
+                info!("user didn't select file to import edges from");
+                return Ok(None);
+            };
+            Ok(Some(tokio::fs::read_to_string(path).await?))
+        }));
+    }
+
+    fn show_unmatched_rows_dialog(&mut self, ui: &Ui) {
+        if let Some(rows) = &self.unmatched_rows {
+            let mut close = false;
+            Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+                ui.label(format!("{} rows couldn't be imported:", rows.len()));
+                ui.add_space(10.0);
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (i, row) in rows {
+                            ui.monospace(format!("{}: {}", i, row));
+                        }
+                    });
+                ui.add_space(10.0);
+                close = ui.button("Close").clicked();
+            });
+            if close {
+                self.unmatched_rows = None;
+            }
+        }
+    }
+
+    // Ask for a directory to rewrite blob paths relative to
+    fn make_paths_relative(&mut self) {
+        self.base_dir_promise = Some(Promise::spawn_async(async move {
+            Ok(FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .pick_folder())
+        }));
+    }
+
+    // Save the paths of blobs under the base relative to it from now on, returning the paths of
+    // blobs outside of it, which keep their absolute paths
+    fn rebase_paths(&mut self, base: &Path) -> Vec<PathBuf> {
+        let mut outside = Vec::new();
+        self.pinboard.for_each_blob(|blob| {
+            if blob.is_local() && blob.relative_to(base).is_none() {
+                outside.push(blob.path().clone());
+            }
+        });
+        if self.pinboard.path_base.as_deref() != Some(base) {
+            self.pinboard.path_base = Some(base.to_path_buf());
+            self.unsaved = true;
+        }
+        outside.sort();
+        outside.dedup();
+        outside
+    }
+
+    fn show_outside_base_dialog(&mut self, ui: &Ui) {
+        if let Some((base, paths)) = &self.outside_base {
+            let mut close = false;
+            Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+                ui.label(format!(
+                    "{} blobs are outside of {} and kept their paths:",
+                    paths.len(),
+                    base.display()
+                ));
+                ui.add_space(10.0);
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for path in paths {
+                            ui.monospace(path.display().to_string());
+                        }
+                    });
+                ui.add_space(10.0);
+                close = ui.button("Close").clicked();
+            });
+            if close {
+                self.outside_base = None;
+            }
+        }
+    }
+
+    fn export_jsonld(&mut self) {
+        let document = export::jsonld(&self.pinboard.graph);
+        self.export_promise = Some(Promise::spawn_async(async move {
+            let Some(path) = FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .add_filter("JSON-LD", &["jsonld", "json"])
+                .save_file()
+            else {
+                info!("user didn't select path to export JSON-LD");
+                return Ok(());
+            };
+            export::export_jsonld(document, path).await
+        }));
+    }
+
+    fn export_dot(&mut self) {
+        let document = export::dot(&self.pinboard.graph, &self.pinboard.title);
+        self.export_promise = Some(Promise::spawn_async(async move {
+            let Some(path) = FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .add_filter("GraphViz DOT", &["dot", "gv"])
+                .add_filter("SVG (requires GraphViz)", &["svg"])
+                .save_file()
+            else {
+                info!("user didn't select path to export DOT");
+                return Ok(());
+            };
+            export::export_dot(document, path).await
+        }));
+    }
+
+    fn export_bundle(&mut self) {
+        self.export_promise = Some(Promise::spawn_async(session::export_bundle_dialog(
+            self.pinboard.clone(),
+        )));
+    }
+
+    fn export_adjacency(&mut self) {
+        let adjacency = export::adjacency(&self.pinboard.graph);
+        self.export_promise = Some(Promise::spawn_async(async move {
+            let Some(path) = FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir()?)
+                .add_filter("CSV", &["csv"])
+                .save_file()
+            else {
+                info!("user didn't select path to export adjacency matrix");
+                return Ok(());
+            };
+            export::export_adjacency(adjacency, path).await
+        }));
+    }
+
+    fn show_nvim_menu(&mut self, ui: &mut Ui) {
+        ui.label("Server (empty to use global):");
+        let mut srv = self.pinboard.nvim.srv.clone().unwrap_or_default();
+        if ui.text_edit_singleline(&mut srv).changed() {
+            self.pinboard.nvim.srv = (!srv.is_empty()).then_some(srv);
+            self.unsaved = true;
+        }
+        ui.label("Extensions, comma separated (empty to use global):");
+        let mut ext = self
+            .pinboard
+            .nvim
+            .ext
+            .as_ref()
+            .map(|v| v.join(","))
+            .unwrap_or_default();
+        if ui.text_edit_singleline(&mut ext).changed() {
+            self.pinboard.nvim.ext =
+                (!ext.is_empty()).then(|| ext.split(',').map(|e| e.trim().to_string()).collect());
+            self.unsaved = true;
+        }
+    }
+
+    fn show_properties_dialog(&mut self, ui: &Ui) {
+        if self.show_properties_modal {
+            Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+                let info = &mut self.pinboard.info;
+                egui::Grid::new("properties").num_columns(2).show(ui, |ui| {
+                    ui.label("Author");
+                    if ui.text_edit_singleline(&mut info.author).changed() {
+                        self.unsaved = true;
+                    }
+                    ui.end_row();
+                    ui.label("Created");
+                    ui.label(BoardInfo::format_time(info.created));
+                    ui.end_row();
+                    ui.label("Modified");
+                    ui.label(BoardInfo::format_time(info.modified));
+                    ui.end_row();
+                });
+                ui.label("Description");
+                if ui.text_edit_multiline(&mut info.description).changed() {
+                    self.unsaved = true;
+                }
+                ui.add_space(10.0);
+                ui.label("Folders searched for missing files besides the project root");
+                let mut removed = None;
+                for (i, root) in self.pinboard.search_roots.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.monospace(root.display().to_string());
+                        if ui
+                            .add_enabled(!self.read_only, Button::new("Remove").small())
+                            .clicked()
+                        {
+                            removed = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = removed {
+                    self.pinboard.search_roots.remove(i);
+                    self.unsaved = true;
+                }
+                if ui
+                    .add_enabled(!self.read_only, Button::new("Add folder..."))
+                    .clicked()
+                {
+                    self.search_root_promise = Some(Promise::spawn_async(async move {
+                        Ok(FileDialog::new()
+                            // https://github.com/PolyMeilex/rfd/issues/235
+                            .set_directory(config::dialog_dir()?)
+                            .pick_folder())
+                    }));
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Skip files larger than");
+                    let mut mib = self
+                        .pinboard
+                        .max_search_size
+                        .unwrap_or(graph::DEFAULT_MAX_SEARCH_SIZE)
+                        / (1024 * 1024);
+                    if ui
+                        .add_enabled(
+                            !self.read_only,
+                            egui::DragValue::new(&mut mib)
+                                .range(1..=1024 * 1024)
+                                .suffix(" MiB"),
+                        )
+                        .changed()
+                    {
+                        self.pinboard.max_search_size = Some(mib * 1024 * 1024);
+                        self.unsaved = true;
+                    }
+                });
+                ui.add_space(10.0);
+                if ui.button("Done").clicked() {
+                    self.show_properties_modal = false;
+                }
+            });
+        }
+    }
+
+    fn show_health_bar(&mut self, ui: &mut Ui) {
+        let Some(report) = &self.health else {
+            return;
+        };
+        let mut dismissed = false;
+        ui.horizontal(|ui| {
+            if report.is_healthy() {
+                ui.label("No broken links or other problems found");
+            } else {
+                ui.colored_label(ui.visuals().warn_fg_color, report.summary());
+                ui.toggle_value(&mut self.show_health_details, "Details");
+            }
+            dismissed = ui.small_button("Dismiss").clicked();
+        });
+        let mut action = None;
+        if self.show_health_details {
+            let editable = !self.read_only;
+            // Buttons shared by every problem with a path
+            let jump = |ui: &mut Ui, path: &PathBuf, action: &mut Option<HealthAction>| {
+                if ui.small_button("Jump to node").clicked() {
+                    *action = Some(HealthAction::Jump(path.clone()));
+                }
+            };
+            egui::ScrollArea::vertical()
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    if !report.drifted.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.strong("Drifted");
+                            if ui
+                                .add_enabled(editable, Button::new("Accept all").small())
+                                .on_hover_text("Record the current content of the files")
+                                .clicked()
+                            {
+                                action = Some(HealthAction::Accept(None));
+                            }
+                        });
+                        for path in &report.drifted {
+                            ui.horizontal(|ui| {
+                                ui.label(path.display().to_string());
+                                if ui
+                                    .add_enabled(editable, Button::new("Accept").small())
+                                    .clicked()
+                                {
+                                    action = Some(HealthAction::Accept(Some(path.clone())));
+                                }
+                                jump(ui, path, &mut action);
+                            });
+                        }
+                    }
+                    if !report.repairable.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.strong("Repairable");
+                            if ui
+                                .add_enabled(editable, Button::new("Repair all").small())
+                                .on_hover_text("Point the blobs at the files matching them")
+                                .clicked()
+                            {
+                                action = Some(HealthAction::Repair(None));
+                            }
+                        });
+                        for (old, new) in &report.repairable {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} → {}", old.display(), new.display()));
+                                if ui
+                                    .add_enabled(editable, Button::new("Repair").small())
+                                    .clicked()
+                                {
+                                    action = Some(HealthAction::Repair(Some(old.clone())));
+                                }
+                                jump(ui, old, &mut action);
+                            });
+                        }
+                    }
+                    if !report.lost.is_empty() {
+                        ui.horizontal(|ui| {
+                            ui.strong("Lost");
+                            if ui
+                                .add_enabled(editable, Button::new("Remove all").small())
+                                .on_hover_text(
+                                    "Remove nodes of lost blobs and lost comments of edges",
+                                )
+                                .clicked()
+                            {
+                                action = Some(HealthAction::RemoveLost);
+                            }
+                        });
+                        for path in &report.lost {
+                            ui.horizontal(|ui| {
+                                ui.label(path.display().to_string());
+                                if ui
+                                    .add_enabled(editable, Button::new("Relocate...").small())
+                                    .on_hover_text("Pick the file the blob has been moved to")
+                                    .clicked()
+                                {
+                                    action = Some(HealthAction::Relocate(path.clone()));
+                                }
+                                jump(ui, path, &mut action);
+                            });
+                        }
+                    }
+                    for path in &report.uncommitted {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Uncommitted: {}", path.display()));
+                            jump(ui, path, &mut action);
+                        });
+                    }
+                    let labeled = report.isolated.iter().map(|l| ("Isolated", l)).chain(
+                        report
+                            .self_links
+                            .iter()
+                            .map(|l| ("Links to this pinboard", l)),
+                    );
+                    for (problem, label) in labeled {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{}: {}", problem, label));
+                            if ui.small_button("Jump to node").clicked() {
+                                action = Some(HealthAction::JumpLabel(label.clone()));
+                            }
+                        });
+                    }
+                });
+        }
+        ui.separator();
+        match action {
+            Some(HealthAction::Accept(path)) => self.accept_drift(path.as_deref()),
+            Some(HealthAction::Repair(path)) => self.apply_repairs(path.as_deref()),
+            Some(HealthAction::RemoveLost) => self.remove_lost(),
+            Some(HealthAction::Relocate(path)) => self.relocate(path),
+            Some(HealthAction::Jump(path)) => self.focus_path(&path),
+            Some(HealthAction::JumpLabel(label)) => self.focus_label(&label),
+            None => {}
+        }
+        if dismissed {
+            self.health = None;
+        }
+    }
+
+    // Check the pinboard for problems and show the result even if there are none
+    fn validate(&mut self) {
+        self.validating = true;
+        self.check_health();
+    }
+
+    // Record the current content of drifted blobs, all of them without a path
+    fn accept_drift(&mut self, only: Option<&Path>) {
+        let Some(report) = self.health.as_mut() else {
+            return;
+        };
+        let (drifted, kept): (Vec<PathBuf>, Vec<PathBuf>) = std::mem::take(&mut report.drifted)
+            .into_iter()
+            .partition(|p| only.is_none_or(|o| o == p));
+        report.drifted = kept;
+        if drifted.is_empty() {
+            return;
+        }
+        self.checkpoint();
+        self.pinboard.for_each_blob(|blob| {
+            if drifted.contains(blob.path()) {
+                if let Err(e) = blob.refresh_hash() {
+                    error!("cannot hash {:?}: {}", blob.path(), e);
+                }
+            }
+        });
+        self.unsaved = true;
+    }
+
+    // Point missing blobs at the unique files matching their hashes, all of them without a path
+    fn apply_repairs(&mut self, only: Option<&Path>) {
+        let Some(report) = self.health.as_mut() else {
+            return;
+        };
+        let (repairs, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut report.repairable)
+            .into_iter()
+            .partition(|(p, _)| only.is_none_or(|o| o == p));
+        report.repairable = kept;
+        let repairs: HashMap<PathBuf, PathBuf> = repairs.into_iter().collect();
+        if repairs.is_empty() {
+            return;
+        }
+        self.checkpoint();
+        self.pinboard.for_each_blob(|blob| {
+            if let Some(new) = repairs.get(blob.path()) {
+                blob.set_path(new.clone());
+            }
+        });
+        self.unsaved = true;
+    }
+
+    // Ask the user where the lost blob has been moved to
+    fn relocate(&mut self, path: PathBuf) {
+        self.relocate_promise = Some(Promise::spawn_async(async move {
+            let mut dialog = FileDialog::new();
+            // Start next to where the file used to be if that still exists
+            if let Some(dir) = path.parent().and_then(|d| d.canonicalize().ok()) {
+                dialog = dialog.set_directory(dir);
+            }
+            let new = dialog.pick_file();
+            Ok((path, new))
+        }));
+    }
+
+    // Point blobs at the file picked for them, which needn't have the same content
+    fn apply_relocation(&mut self, old: &Path, new: PathBuf) {
+        let mut relocated = false;
+        let before = self.pinboard.graph.clone();
+        self.pinboard.for_each_blob(|blob| {
+            if blob.path() == old {
+                blob.set_path(new.clone());
+                if let Err(e) = blob.refresh_hash() {
+                    error!("cannot hash {:?}: {}", blob.path(), e);
+                }
+                relocated = true;
+            }
+        });
+        if let Some(report) = self.health.as_mut() {
+            report.lost.retain(|p| p != old);
+        }
+        if relocated {
+            self.push_history(before);
+            self.unsaved = true;
+        }
+    }
+
+    // Select the first node whose blob has the path and center the view on it, edges whose comment
+    // has the path are only looked at if no node has
+    fn focus_path(&mut self, path: &Path) {
+        let has_path = |b: &Option<Blob>| b.as_ref().is_some_and(|b| b.path() == path);
+        let graph = &self.pinboard.graph;
+        let node = graph
+            .nodes_iter()
+            .find(|(_, n)| has_path(&n.payload().blob))
+            .map(|(id, _)| id);
+        let edge = graph
+            .edges_iter()
+            .find(|(_, e)| has_path(&e.payload().comment))
+            .map(|(id, _)| id);
+        if let Some(id) = node {
+            self.focus_node(id);
+        } else if let Some(id) = edge {
+            self.focus_edge(id);
+        } else {
+            warn!(
+                "nothing in pinboard {} refers to {:?}",
+                self.pinboard.title, path
+            );
+        }
+    }
+
+    // Remove nodes whose blob is lost, edges only lose their comment
+    fn remove_lost(&mut self) {
+        let Some(report) = self.health.as_mut() else {
+            return;
+        };
+        let lost = std::mem::take(&mut report.lost);
+        let is_lost = |b: &Option<Blob>| b.as_ref().is_some_and(|b| lost.contains(b.path()));
+        let nodes: Vec<NodeIndex> = self
+            .pinboard
+            .graph
+            .nodes_iter()
+            .filter(|(_, n)| is_lost(&n.payload().blob))
+            .map(|(id, _)| id)
+            .collect();
+        let edges: Vec<EdgeIndex> = self
+            .pinboard
+            .graph
+            .edges_iter()
+            .filter(|(_, e)| is_lost(&e.payload().comment))
+            .map(|(id, _)| id)
+            .collect();
+        if nodes.is_empty() && edges.is_empty() {
+            return;
+        }
+        self.checkpoint();
+        for e in edges {
+            if let Some(e) = self.pinboard.graph.edge_mut(e) {
+                e.payload_mut().comment = None;
+            }
+        }
+        self.remove_nodes(&nodes);
+    }
+
+    fn show_ambiguous_match_dialog(&mut self, ui: &Ui) {
+        let Some((either, candidates)) = &self.ambiguous_match else {
+            return;
+        };
+        let mut picked = None;
+        let mut cancelled = false;
+        Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+            ui.label("Several files match the missing blob, pick the one to use:");
+            ui.add_space(10.0);
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for path in candidates {
+                        if ui.button(path.display().to_string()).clicked() {
+                            picked = Some(path.clone());
+                        }
+                    }
+                });
+            ui.add_space(10.0);
+            cancelled = ui.button("Cancel").clicked();
+        });
+        if let Some(path) = picked {
+            let either = *either;
+            let blob = match either {
+                Either::Node(id) => self
+                    .pinboard
+                    .graph
+                    .node(id)
+                    .and_then(|n| n.payload().blob.clone()),
+                Either::Edge(id) => self
+                    .pinboard
+                    .graph
+                    .edge(id)
+                    .and_then(|e| e.payload().comment.clone()),
+            };
+            if let Some(mut blob) = blob {
+                blob.set_path(path);
+                self.open_blobs(vec![(either, blob)]);
+            }
+            self.ambiguous_match = None;
+        } else if cancelled {
+            self.ambiguous_match = None;
+        }
+    }
+
+    fn show_open_all_dialog(&mut self, ui: &Ui) {
+        if let Some(nodes) = self.nodes_to_open.clone() {
+            Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+                ui.label(format!("Open {} files at once?", nodes.len()));
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        self.open_nodes(&nodes);
+                        self.nodes_to_open = None;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.nodes_to_open = None;
+                    }
+                })
+            });
+        }
+    }
+
+    // Remove the nodes along with all their edges
+    fn delete_nodes(&mut self, nodes: &[NodeIndex]) {
+        self.checkpoint();
+        self.remove_nodes(nodes);
+    }
+
+    // Like `delete_nodes`, for callers which have taken the checkpoint already
+    fn remove_nodes(&mut self, nodes: &[NodeIndex]) {
+        for n in nodes {
+            // egui_graphs removes the incident edges before the node itself
+            self.pinboard.graph.remove_node(*n);
+        }
+        // Don't keep references to the removed nodes and edges around until the next frame
+        let graph = &mut self.pinboard.graph;
+        let selected_nodes = graph
+            .selected_nodes()
+            .iter()
+            .copied()
+            .filter(|n| graph.node(*n).is_some())
+            .collect();
+        let selected_edges = graph
+            .selected_edges()
+            .iter()
+            .copied()
+            .filter(|e| graph.edge(*e).is_some())
+            .collect();
+        graph.set_selected_nodes(selected_nodes);
+        graph.set_selected_edges(selected_edges);
+        self.unsaved = true;
+    }
+
+    // Selected nodes and the edges among them, `None` if nothing is selected
+    fn copy_selection(&self) -> Option<Clipboard> {
+        self.copy_nodes(self.pinboard.graph.selected_nodes())
+    }
+
+    // The nodes and the edges among them, `None` if there's no node
+    fn copy_nodes(&self, selected: &[NodeIndex]) -> Option<Clipboard> {
+        let graph = &self.pinboard.graph;
+        // Edges refer to the nodes by their position among the ones found
+        let found: Vec<_> = selected
+            .iter()
+            .filter_map(|n| Some((*n, graph.node(*n)?)))
+            .collect();
+        if found.is_empty() {
+            return None;
+        }
+        let center = found
+            .iter()
+            .fold(Vec2::ZERO, |sum, (_, n)| sum + n.location().to_vec2())
+            / found.len() as f32;
+        let nodes = found
+            .iter()
+            .map(|(_, n)| {
+                (
+                    n.payload().clone(),
+                    n.label(),
+                    n.location().to_vec2() - center,
+                )
+            })
+            .collect();
+        let edges = graph
+            .edges_iter()
+            .filter_map(|(id, e)| {
+                let (a, b) = graph.edge_endpoints(id)?;
+                let a = found.iter().position(|(n, _)| *n == a)?;
+                let b = found.iter().position(|(n, _)| *n == b)?;
+                Some((a, b, e.payload().clone(), e.label()))
+            })
+            .collect();
+        Some(Clipboard { nodes, edges })
+    }
+
+    // Copy the selected nodes and remove them from the board
+    fn cut_selection(&mut self) -> Option<Clipboard> {
+        let copied = self.copy_selection()?;
+        let nodes = Vec::from(self.pinboard.graph.selected_nodes());
+        self.delete_nodes(&nodes);
+        Some(copied)
+    }
+
+    // Whether the command can be run by its shortcut right now
+    fn can_run(&self, command: Command) -> bool {
+        match command {
+            // Selection doesn't modify the pinboard, so it's available on read-only ones as well
+            Command::SelectAll | Command::DeselectAll | Command::Fit | Command::FitSelection => {
+                true
+            }
+            Command::RelationPicker => !self.read_only && self.relation_picker.is_none(),
+            Command::AddConnectedNode => {
+                !self.read_only && self.pinboard.graph.selected_nodes().len() == 1
+            }
+            _ => !self.read_only,
+        }
+    }
+
+    fn run_command(&mut self, command: Command, ui: &Ui, metadata: &Metadata) {
+        let pos = ui.input(|i| i.pointer.hover_pos());
+        match command {
+            Command::SelectAll => self.select_all(),
+            Command::DeselectAll => self.select_only(&[]),
+            Command::Fit => self.fit(),
+            Command::FitSelection => self.fit_selection(),
+            Command::Save => self.save(),
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            // Rename the selected node, or the pinboard if there's none
+            Command::Rename => match self.pinboard.graph.selected_nodes() {
+                [node] => self.start_renaming_node(*node),
+                _ => self.show_rename_modal = true,
+            },
+            Command::RelationPicker => {
+                if let Some(pos) = pos {
+                    self.open_relation_picker(pos);
+                }
+            }
+            Command::AddConnectedNode => {
+                let from = self.pinboard.graph.selected_nodes()[0];
+                self.add_connected_node(from);
+            }
+            Command::AddNode => self.add_node(pos, metadata),
+            // Handled by the app
+            Command::SaveAll | Command::Switcher => {}
+        }
+    }
+
+    // Copy, cut and paste nodes, pasting them around the canvas position
+    fn handle_clipboard_events(
+        &mut self,
+        ctx: &Context,
+        clipboard: &mut Option<Clipboard>,
+        pos: Pos2,
+    ) {
+        let events = ctx.input(|i| i.events.clone());
+        for event in events {
+            let copied = match event {
+                egui::Event::Copy => self.copy_selection(),
+                egui::Event::Cut if !self.read_only => self.cut_selection(),
+                egui::Event::Paste(_) if !self.read_only => {
+                    if let Some(copied) = clipboard.as_ref() {
+                        self.paste(copied, pos);
+                    }
+                    None
+                }
+                _ => None,
+            };
+            if let Some(copied) = copied {
+                // The paste shortcut only turns into an event while the OS clipboard holds text
+                ctx.copy_text(copied.text());
+                *clipboard = Some(copied);
+            }
+        }
+    }
+
+    // Add the copied nodes around the canvas position and select them
+    fn paste(&mut self, clipboard: &Clipboard, pos: Pos2) {
+        if clipboard.nodes.is_empty() {
+            return;
+        }
+        self.checkpoint();
+        let ids = self.pinboard.insert(clipboard, pos);
+        self.select_only(&ids);
+        self.unsaved = true;
+    }
+
+    // Save the selected nodes to a new pinboard picked by the user, they are replaced by a node
+    // of the new pinboard once it's saved
+    fn extract_selection(&mut self) {
+        let nodes = Vec::from(self.pinboard.graph.selected_nodes());
+        let Some(copied) = self.copy_nodes(&nodes) else {
+            return;
+        };
+        let mut sub = Pinboard::new(String::new(), PinboardGraph::from(&StableGraph::default()));
+        sub.view = self.pinboard.view.clone();
+        sub.insert(&copied, Pos2::ZERO);
+        let dir = self
+            .path
+            .as_ref()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .unwrap_or(PathBuf::from("."));
+        self.extract_promise = Some(Promise::spawn_async(async move {
+            let res = async {
+                let Some(path) = FileDialog::new()
+                    // https://github.com/PolyMeilex/rfd/issues/235
+                    .set_directory(dir.canonicalize()?)
+                    .add_filter("Pinboard", &["pinbrd"])
+                    .save_file()
+                else {
+                    return Ok(None);
+                };
+                if let Some(stem) = path.file_stem() {
+                    sub.title = stem.to_string_lossy().to_string();
+                }
+                let path = Self::save_to_path(sub, path).await?;
+                Ok(Some(Blob::new(BlobType::PinboardGraph, path).await?))
+            }
+            .await;
+            // The copy tells whether the nodes have been edited while the user picked the file
+            (nodes, copied, res)
+        }));
+    }
+
+    // Replace the nodes by a node of the pinboard they were extracted to, edges to the rest of the
+    // board are kept on the new node, one per neighbor and relation. Nodes which changed since
+    // they were extracted are left alone.
+    fn replace_with_sub_board(&mut self, nodes: &[NodeIndex], copied: &Clipboard, blob: Blob) {
+        let graph = &self.pinboard.graph;
+        let unchanged = nodes.len() == copied.nodes.len()
+            && nodes
+                .iter()
+                .zip(&copied.nodes)
+                .all(|(n, (data, label, _))| {
+                    graph.node(*n).is_some_and(|n| {
+                        n.label() == *label
+                            && serde_json::to_value(n.payload()).ok()
+                                == serde_json::to_value(data).ok()
+                    })
+                });
+        if !unchanged {
+            warn!("the extracted nodes have changed meanwhile, keeping them on the board");
+            return;
+        }
+        let center = nodes
+            .iter()
+            .filter_map(|n| graph.node(*n))
+            .fold(Vec2::ZERO, |sum, n| sum + n.location().to_vec2())
+            / nodes.len() as f32;
+        let mut outside: Vec<(NodeIndex, Conn, String)> = Vec::new();
+        for (id, e) in graph.edges_iter() {
+            let Some((a, b)) = graph.edge_endpoints(id) else {
+                continue;
+            };
+            let neighbor = match (nodes.contains(&a), nodes.contains(&b)) {
+                (true, false) => b,
+                (false, true) => a,
+                _ => continue,
+            };
+            let relation = &e.payload().relation;
+            if !outside
+                .iter()
+                .any(|(n, c, _)| *n == neighbor && c.relation == *relation)
+            {
+                outside.push((neighbor, e.payload().clone(), e.label()));
+            }
+        }
+        // Deleting takes the snapshot the whole extraction is undone to
+        self.delete_nodes(nodes);
+        let label = blob.name();
+        let id = self.pinboard.graph.add_node_with_location(
+            NodeData {
+                blob: Some(blob),
+                ..NodeData::new()
+            },
+            center.to_pos2(),
+        );
+        if let Some(n) = self.pinboard.graph.node_mut(id) {
+            n.set_label(label);
+        }
+        for (neighbor, conn, label) in outside {
+            self.pinboard
+                .graph
+                .add_edge_with_label(id, neighbor, conn, label);
+        }
+        self.select_only(&[id]);
+        self.unsaved = true;
+    }
+
+    // Remember the graph before an edit, so that the edit can be undone. Relation kinds are kept
+    // along as connections refer to them.
+    fn push_history(&mut self, graph: PinboardGraph) {
+        if self.undo_stack.len() == HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(Snapshot {
+            graph,
+            relation_kinds: self.pinboard.relation_kinds.clone(),
+        });
+        self.redo_stack.clear();
+        self.edit_checkpoint = None;
+    }
+
+    // Remember the current graph, must be called before each undoable edit
+    fn checkpoint(&mut self) {
+        self.push_history(self.pinboard.graph.clone());
+    }
+
+    // Whether the widget has changed its value, taking a checkpoint before its first change so
+    // that typing or dragging is undone at once rather than step by step. The widget must edit a
+    // copy.
+    fn edited(&mut self, resp: &Response) -> bool {
+        if resp.changed() && self.edit_checkpoint != Some(resp.id) {
+            self.checkpoint();
+            self.edit_checkpoint = Some(resp.id);
+        }
+        if resp.lost_focus() {
+            self.edit_checkpoint = None;
+        }
+        resp.changed()
+    }
+
+    // Remember where moved nodes were, so that the move can be undone
+    fn record_move(&mut self, origins: Vec<(NodeIndex, Pos2)>) {
+        if origins.is_empty() {
+            return;
+        }
+        let mut graph = self.pinboard.graph.clone();
+        for (id, pos) in origins {
+            if let Some(n) = graph.node_mut(id) {
+                n.set_location(pos);
+            }
+        }
+        self.push_history(graph);
+    }
+
+    // Swap the graph with the top of `from`, keeping the current one on `to`
+    fn restore(&mut self, undo: bool) {
+        let (from, to) = if undo {
+            (&mut self.undo_stack, &mut self.redo_stack)
+        } else {
+            (&mut self.redo_stack, &mut self.undo_stack)
+        };
+        if let Some(snapshot) = from.pop() {
+            to.push(Snapshot {
+                graph: std::mem::replace(&mut self.pinboard.graph, snapshot.graph),
+                relation_kinds: std::mem::replace(
+                    &mut self.pinboard.relation_kinds,
+                    snapshot.relation_kinds,
+                ),
+            });
+            self.edit_checkpoint = None;
+            self.unsaved = true;
+        }
+    }
+
+    fn undo(&mut self) {
+        self.restore(true);
+    }
+
+    fn redo(&mut self) {
+        self.restore(false);
+    }
+
+    // Arrange the selected nodes, or all nodes if none is selected, in a grid
+    fn arrange_grid(&mut self) {
+        let mut nodes = Vec::from(self.pinboard.graph.selected_nodes());
+        if nodes.is_empty() {
+            nodes = self.pinboard.graph.g.node_indices().collect();
+        }
+        let origins = graph::arrange_grid(
+            &mut self.pinboard.graph,
+            &nodes,
+            self.pinboard.view.node_spacing,
+        );
+        if !origins.is_empty() {
+            self.unsaved = true;
+        }
+        self.record_move(origins);
+    }
+
+    // Line up the selected nodes
+    fn align_selection(&mut self, alignment: graph::Alignment) {
+        let nodes = Vec::from(self.pinboard.graph.selected_nodes());
+        let origins = graph::align(&mut self.pinboard.graph, &nodes, alignment);
+        if !origins.is_empty() {
+            self.unsaved = true;
+        }
+        self.record_move(origins);
+    }
+
+    fn snap_to_grid(&mut self, nodes: impl IntoIterator<Item = NodeIndex>) {
+        let spacing = self.pinboard.view.grid_spacing;
+        for id in nodes {
+            if let Some(n) = self.pinboard.graph.node_mut(id) {
+                n.set_location(graph::snap_to_grid(n.location(), spacing));
+            }
+        }
+    }
+
+    // Start untangling the graph, the layout is animated over the next frames
+    fn start_force_layout(&mut self) {
+        if self.read_only || self.pinboard.lock_layout || self.force_layout.is_some() {
+            return;
+        }
+        self.checkpoint();
+        self.force_layout = Some(0);
+    }
+
+    fn step_force_layout(&mut self, ctx: &Context) {
+        let Some(done) = self.force_layout.as_mut() else {
+            return;
+        };
+        if self.pinboard.lock_layout {
+            self.force_layout = None;
+            return;
+        }
+        let spacing = self.pinboard.view.node_spacing;
+        let mut moved = false;
+        for _ in 0..FORCE_LAYOUT_STEPS_PER_FRAME {
+            // Cool down linearly so that the layout settles
+            let temperature =
+                graph::FORCE_DISTANCE * spacing * (1.0 - *done as f32 / FORCE_LAYOUT_STEPS as f32);
+            moved |= graph::force_step(&mut self.pinboard.graph, spacing, temperature);
+            *done += 1;
+        }
+        self.unsaved |= moved;
+        if *done >= FORCE_LAYOUT_STEPS || !moved {
+            self.force_layout = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    fn show_layout_dialog(&mut self, ui: &Ui) {
+        if !self.show_layout_modal {
+            return;
+        }
+        let mut arrange = false;
+        let mut force = false;
+        Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Node spacing");
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut self.pinboard.view.node_spacing)
+                            .range(0.1..=10.0)
+                            .speed(0.05),
+                    )
+                    .on_hover_text("Scale of distances between arranged and newly connected nodes")
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+            });
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                arrange = ui
+                    .add_enabled(
+                        !self.read_only && !self.pinboard.lock_layout,
+                        Button::new("Arrange in grid"),
+                    )
+                    .on_hover_text("Arrange the selected nodes, or all nodes if none is selected")
+                    .clicked();
+                force = ui
+                    .add_enabled(
+                        !self.read_only && !self.pinboard.lock_layout,
+                        Button::new("Force-directed"),
+                    )
+                    .on_hover_text("Pull connected nodes together and push the others apart")
+                    .clicked();
+                if ui.button("Close").clicked() {
+                    self.show_layout_modal = false;
+                }
+            });
+        });
+        if arrange {
+            self.arrange_grid();
+        }
+        if force {
+            self.start_force_layout();
+        }
+    }
+
+    // Manage the user-defined relation kinds, edits apply to the connections using them right away
+    fn show_relations_dialog(&mut self, ui: &Ui) {
+        if !self.show_relations_modal {
+            return;
+        }
+        let editable = !self.read_only;
+        // Kinds edited or deleted, along with the widget editing them
+        let mut changes = Vec::new();
+        let mut add = false;
+        Modal::new(ui.next_auto_id()).show(ui.ctx(), |ui| {
+            ui.heading("Relations");
+            for relation in Relation::ALL {
+                ui.label(relation.label());
+            }
+            ui.separator();
+            for (i, kind) in self.pinboard.relation_kinds.iter().enumerate() {
+                let mut edited = kind.clone();
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(editable, |ui| {
+                        let color = ui.color_edit_button_srgba(&mut edited.color);
+                        let name = ui.text_edit_singleline(&mut edited.name);
+                        if ui.button("Delete").clicked() {
+                            changes.push((i, None, None));
+                        } else if edited != *kind {
+                            let resp = if name.changed() { name } else { color };
+                            changes.push((i, Some(edited.clone()), Some(resp)));
+                        }
+                    });
+                });
+            }
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                add = ui
+                    .add_enabled(editable, Button::new("Add relation"))
+                    .clicked();
+                if ui.button("Done").clicked() {
+                    self.show_relations_modal = false;
+                }
+            });
+        });
+        for (i, new, resp) in changes {
+            let old = self.pinboard.relation_kinds[i].clone();
+            match resp {
+                Some(resp) => {
+                    self.edited(&resp);
+                }
+                None => self.checkpoint(),
+            }
+            self.pinboard.replace_relation_kind(&old, new.clone());
+            if self.last_relation == Relation::Custom(old) {
+                self.last_relation = new.clone().map_or(Relation::Related, Relation::Custom);
+            }
+            match new {
+                Some(kind) => self.pinboard.relation_kinds[i] = kind,
+                None => {
+                    self.pinboard.relation_kinds.remove(i);
+                }
+            }
+            self.unsaved = true;
+        }
+        if add {
+            self.checkpoint();
+            let name = format!("Relation {}", self.pinboard.relation_kinds.len() + 1);
+            self.pinboard
+                .relation_kinds
+                .push(RelationKind::new(name, Color32::LIGHT_BLUE));
+            self.unsaved = true;
+        }
+    }
+
+    // Open the pinboard of a node dragged out of the window as its own window, returning whether
+    // the node has a pinboard
+    fn pop_out(&mut self, node: NodeIndex) -> bool {
+        let Some(blob) = self
+            .pinboard
+            .graph
+            .node(node)
+            .and_then(|n| n.payload().blob.clone())
+            .filter(|b| *b.ty() == BlobType::PinboardGraph)
+        else {
+            return false;
+        };
+        self.open_blobs(vec![(Either::Node(node), blob)]);
+        true
+    }
+
+    fn handle_events(&mut self, ctx: &Context) {
+        let ctrl = ctx.input(|i| i.modifiers.command);
+        // Collected first, as handling them needs the buffer mutably
+        let events: Vec<_> = self.event_receiver.try_iter().collect();
+        for e in events {
+            match e {
+                Event::EdgeDoubleClick(payload) => {
+                    let edge_id = EdgeIndex::new(payload.id);
+
+                    if let Some(blob) = self
+                        .pinboard
+                        .graph
+                        .edge(edge_id)
+                        .map(|e| e.payload().comment.clone())
+                        .flatten()
+                    {
+                        self.open_blobs(vec![(Either::Edge(edge_id), blob)]);
+                        return;
+                    }
+                }
+                Event::NodeDoubleClick(payload) => {
+                    let node_id = NodeIndex::new(payload.id);
+
+                    if let Some(blob) = self
+                        .pinboard
+                        .graph
+                        .node(node_id)
+                        .and_then(|n| n.payload().blob.clone())
+                    {
+                        self.open_blobs(vec![(Either::Node(node_id), blob)]);
+                        self.open_read_only = self.pinboard.open_children_read_only != ctrl;
+                        return;
+                    }
+                }
+                // Dragging is disabled while the layout is locked, but the graph view might still
+                // report moves from a drag started before locking
+                Event::NodeMove(_) if self.pinboard.lock_layout => {}
+                Event::NodeMove(payload) => {
+                    self.unsaved = true;
+                    if let Some(moves) = self.drag_moves.as_mut() {
+                        *moves
+                            .entry(NodeIndex::new(payload.id))
+                            .or_insert(Vec2::ZERO) += Vec2::from(payload.diff);
+                    }
+                }
+                Event::NodeDragStart(_) => self.drag_moves = Some(HashMap::new()),
+                Event::NodeDragEnd(payload) => {
+                    let origins: Vec<(NodeIndex, Pos2)> = self
+                        .drag_moves
+                        .take()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|(id, diff)| {
+                            self.pinboard
+                                .graph
+                                .node(id)
+                                .map(|n| (id, n.location() - diff))
+                        })
+                        .collect();
+                    let outside = ctx
+                        .input(|i| i.pointer.latest_pos())
+                        .zip(self.window_rect)
+                        .is_some_and(|(pos, rect)| !rect.contains(pos));
+                    if outside && self.pop_out(NodeIndex::new(payload.id)) {
+                        // Moving the nodes back isn't an edit to be undone
+                        for (id, pos) in origins {
+                            if let Some(n) = self.pinboard.graph.node_mut(id) {
+                                n.set_location(pos);
+                            }
+                        }
+                    } else {
+                        if self.pinboard.view.snap_to_grid {
+                            self.snap_to_grid(origins.iter().map(|(id, _)| *id));
+                        }
+                        self.record_move(origins);
+                    }
+                }
+                // Shift-dragging between two nodes connects them, or changes the relation of the
+                // edge already connecting them
+                Event::EdgeCreateRequest(payload) => {
+                    let (a, b) = (
+                        NodeIndex::new(payload.source),
+                        NodeIndex::new(payload.target),
+                    );
+                    let target = match self.pinboard.graph.g.find_edge(a, b) {
+                        Some(edge) => RelationTarget::Edges(vec![edge]),
+                        None => RelationTarget::Connect(a, b),
+                    };
+                    if let Some(pos) = ctx.input(|i| i.pointer.latest_pos()) {
+                        self.relation_picker = Some(RelationPicker {
+                            target,
+                            query: String::new(),
+                            pos,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn add_blob(root: PathBuf, resolve_symlinks: bool) -> Result<Option<Blob>> {
+        let Some(mut path) = FileDialog::new()
+            // https://github.com/PolyMeilex/rfd/issues/235
+            .set_directory(root.canonicalize()?)
+            .pick_file()
+        else {
+            return Ok(None);
+        };
+        if resolve_symlinks {
+            path = tokio::fs::canonicalize(&path).await?;
+        }
+        Self::blob_of_path(path).await.map(Some)
+    }
+
+    async fn blob_of_path(path: PathBuf) -> Result<Blob> {
+        match path.extension().map(|s| s.to_str()).flatten() {
+            Some("pinbrd") => Blob::new(BlobType::PinboardGraph, path).await,
+            _ => Blob::new(BlobType::File, path).await,
+        }
+    }
+
+    // Hash the files dropped at the canvas position, nodes are added once all of them are done
+    fn drop_files(&mut self, paths: Vec<PathBuf>, pos: Pos2) {
+        let resolve_symlinks = self.pinboard.resolve_symlinks;
+        self.drop_promise = Some(Promise::spawn_async(async move {
+            let mut blobs = Vec::new();
+            for mut path in paths {
+                if resolve_symlinks {
+                    match tokio::fs::canonicalize(&path).await {
+                        Ok(p) => path = p,
+                        Err(e) => {
+                            blobs.push(Err(e.into()));
+                            continue;
+                        }
+                    }
+                }
+                blobs.push(Self::blob_of_path(path).await);
+            }
+            (pos, blobs)
+        }));
+    }
+
+    // Add a node for each blob, stacking them diagonally from the position
+    fn add_dropped_nodes(&mut self, pos: Pos2, blobs: Vec<Blob>) {
+        if blobs.is_empty() {
+            return;
+        }
+        self.checkpoint();
+        let offset = CONNECTED_NODE_OFFSET * self.pinboard.view.node_spacing;
+        for (i, blob) in blobs.into_iter().enumerate() {
+            let label = blob.name();
+            let id = self.pinboard.graph.add_node_with_location(
+                NodeData {
+                    blob: Some(blob),
+                    ..NodeData::new()
+                },
+                pos + offset * i as f32,
+            );
+            if let Some(n) = self.pinboard.graph.node_mut(id) {
+                n.set_label(label);
+            }
+        }
+        self.unsaved = true;
+    }
+
+    // Let the user pick a blob for the node or edge
+    fn pick_blob(&mut self, either: Either) {
+        let root = self.get_root();
+        let resolve_symlinks = self.pinboard.resolve_symlinks;
+        self.update_blob_promise = Some(Promise::spawn_async(async move {
+            (either, Self::add_blob(root, resolve_symlinks).await)
+        }));
+    }
+
+    fn add_node(&mut self, pos: Option<Pos2>, metadata: &Metadata) {
+        self.checkpoint();
+        let id = if let Some(pos) = pos {
+            self.pinboard
+                .graph
+                .add_node_with_location(NodeData::new(), metadata.screen_to_canvas_pos(pos))
+        } else {
+            self.pinboard.graph.add_node(NodeData::new())
+        };
+        self.pick_blob(Either::Node(id));
+    }
+
+    // Add a new node next to the given node and connect them with the relation
+    fn connect_new_node(&mut self, from: NodeIndex, relation: Relation) -> Option<NodeIndex> {
+        let loc = self.pinboard.graph.node(from).map(|n| n.location())?;
+        self.checkpoint();
+        let id = self.pinboard.graph.add_node_with_location(
+            NodeData::new(),
+            loc + CONNECTED_NODE_OFFSET * self.pinboard.view.node_spacing,
+        );
+        let label = relation.label();
+        self.pinboard
+            .graph
+            .add_edge_with_label(from, id, Conn::new(relation), label);
+        self.unsaved = true;
+        Some(id)
+    }
+
+    // Add a new node next to the given node, connect them with a `Related` edge and ask for the
+    // blob of the new node
+    fn add_connected_node(&mut self, from: NodeIndex) {
+        let Some(id) = self.connect_new_node(from, Relation::Related) else {
+            return;
+        };
+        self.pick_blob(Either::Node(id));
+    }
+
+    // Buttons for the relations, the highlighted one is shown as selected
+    fn show_relation_submenu(
+        ui: &mut Ui,
+        relations: Vec<Relation>,
+        highlighted: Option<&Relation>,
+    ) -> (bool, Relation) {
+        for relation in relations {
+            let button = Button::new(relation.label()).selected(highlighted == Some(&relation));
+            if ui.add(button).clicked() {
+                ui.close_menu();
+                return (true, relation);
+            }
+        }
+        (false, Relation::Related)
+    }
+
+    // Target of the relation picker given the current selection, if any
+    fn relation_target(&self) -> Option<RelationTarget> {
+        let nodes = self.pinboard.graph.selected_nodes();
+        let edges = self.pinboard.graph.selected_edges();
+        if nodes.len() == 2
+            && self
+                .pinboard
+                .graph
+                .g
+                .find_edge(nodes[0], nodes[1])
+                .is_none()
+        {
+            Some(RelationTarget::Connect(nodes[0], nodes[1]))
+        } else if !edges.is_empty() {
+            Some(RelationTarget::Edges(Vec::from(edges)))
+        } else {
+            None
+        }
+    }
+
+    fn open_relation_picker(&mut self, pos: Pos2) {
+        self.relation_picker = self.relation_target().map(|target| RelationPicker {
+            target,
+            query: String::new(),
+            pos,
+        });
+    }
+
+    fn apply_relation(&mut self, target: RelationTarget, relation: Relation) {
+        self.checkpoint();
+        match target {
+            RelationTarget::Connect(a, b) => {
+                self.last_relation = relation.clone();
+                let label = relation.label();
+                self.pinboard
+                    .graph
+                    .add_edge_with_label(a, b, Conn::new(relation), label);
+            }
+            RelationTarget::Edges(edges) => {
+                for id in edges {
+                    if let Some(edge) = self.pinboard.graph.edge_mut(id) {
+                        edge.set_label(relation.label());
+                        edge.payload_mut().relation = relation.clone();
+                        edge.payload_mut().touch();
+                    }
+                }
+            }
+        }
+        self.unsaved = true;
+    }
+
+    fn show_relation_picker(&mut self, ctx: &Context, id: Id) {
+        let Some(picker) = self.relation_picker.as_mut() else {
+            return;
+        };
+        let relations = self.pinboard.relations();
+        let matching: Vec<Relation> = relations
+            .iter()
+            .filter(|r| {
+                r.label()
+                    .to_lowercase()
+                    .starts_with(&picker.query.to_lowercase())
+            })
+            .cloned()
+            .collect();
+        // Number keys are consumed before the text edit gets to see them
+        let picked = ctx.input_mut(|i| {
+            RELATION_PICKER_KEYS
+                .iter()
+                .position(|k| i.consume_key(Modifiers::NONE, *k))
+                .and_then(|n| relations.get(n).cloned())
+                .or_else(|| {
+                    i.consume_key(Modifiers::NONE, Key::Enter)
+                        .then(|| matching.first().cloned())
+                        .flatten()
+                })
+        });
+        let cancelled = ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape));
+
+        egui::Area::new(id.with("relation_picker"))
+            .fixed_pos(picker.pos)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.text_edit_singleline(&mut picker.query).request_focus();
+                    for (n, relation) in relations.iter().enumerate() {
+                        // Relations beyond the number keys can only be picked by typing
+                        let label = if n < RELATION_PICKER_KEYS.len() {
+                            format!("{} {}", n + 1, relation.label())
+                        } else {
+                            format!("  {}", relation.label())
+                        };
+                        if matching.contains(relation) {
+                            ui.label(label);
+                        } else {
+                            ui.weak(label);
+                        }
+                    }
+                });
+            });
+
+        if let Some(relation) = picked {
+            let picker = self.relation_picker.take().unwrap();
+            self.apply_relation(picker.target, relation);
+        } else if cancelled {
+            self.relation_picker = None;
+        }
+    }
+
+    // Keep the zoom factor within the limits of the board, zooming around the center of the view
+    fn clamp_zoom(&self, ui: &Ui, id: Id, rect: egui::Rect) {
+        let mut metadata = Metadata::load(ui, id);
+        let view = &self.pinboard.view;
+        let zoom = metadata.zoom.clamp(view.min_zoom, view.max_zoom);
+        if zoom != metadata.zoom {
+            let center = rect.center().to_vec2();
+            metadata.pan = center - (center - metadata.pan) / metadata.zoom * zoom;
+            metadata.zoom = zoom;
+            metadata.save(ui, id);
+        }
+    }
+
+    // Pan the view so that the canvas position is at the center of the widget
+    fn center_on(ui: &Ui, id: Id, rect: egui::Rect, pos: Pos2, zoom: Option<f32>) {
+        let mut metadata = Metadata::load(ui, id);
+        if let Some(zoom) = zoom {
+            metadata.zoom = zoom;
+        }
+        metadata.pan = rect.center().to_vec2() - pos.to_vec2() * metadata.zoom;
+        metadata.save(ui, id);
+    }
+
+    // Canvas position to center and zoom factor reaching the target in a view of the size, `None`
+    // if its nodes are gone
+    fn target_view(&self, target: &FocusTarget, view: Vec2, zoom: f32) -> Option<(Pos2, f32)> {
+        let limits = &self.pinboard.view;
+        match target {
+            FocusTarget::Node(node) => {
+                let loc = self.pinboard.graph.node(*node)?.location();
+                let zoom = if limits.zoom_on_focus {
+                    1.0_f32.clamp(limits.min_zoom, limits.max_zoom)
+                } else {
+                    zoom
+                };
+                Some((loc, zoom))
+            }
+            FocusTarget::Frame(nodes) => {
+                // Cards and thumbnails extend around their location
+                let (center, zoom) = fit_frame(
+                    nodes
+                        .iter()
+                        .filter_map(|n| self.pinboard.graph.node(*n))
+                        .map(|n| {
+                            let size = n.payload().box_size().unwrap_or(Vec2::ZERO);
+                            Rect::from_center_size(n.location(), size)
+                        }),
+                    view,
+                )?;
+                Some((center, zoom.clamp(limits.min_zoom, limits.max_zoom)))
+            }
         }
     }
 
-    fn add_node(&mut self, pos: Option<Pos2>, metadata: &Metadata) {
-        let id = if let Some(pos) = pos {
-            self.pinboard
-                .graph
-                .add_node_with_location(None, metadata.screen_to_canvas_pos(pos))
+    // Start moving the view towards the target, or jump to it if animations are disabled
+    fn start_focus(&mut self, ui: &Ui, id: Id, rect: egui::Rect, target: FocusTarget) {
+        let metadata = Metadata::load(ui, id);
+        if !self.pinboard.view.animate_focus {
+            if let Some((pos, zoom)) = self.target_view(&target, rect.size(), metadata.zoom) {
+                Self::center_on(ui, id, rect, pos, Some(zoom));
+            }
+            return;
+        }
+        self.focus_animation = Some(FocusAnimation {
+            target,
+            from: ((rect.center().to_vec2() - metadata.pan) / metadata.zoom).to_pos2(),
+            from_zoom: metadata.zoom,
+            start: ui.input(|i| i.time),
+        });
+    }
+
+    // Advance the focus animation by a frame
+    fn animate_focus(&mut self, ui: &Ui, id: Id, rect: egui::Rect) {
+        let Some(anim) = &self.focus_animation else {
+            return;
+        };
+        // The nodes might have been deleted in the meantime
+        let Some((to, to_zoom)) = self.target_view(&anim.target, rect.size(), anim.from_zoom)
+        else {
+            self.focus_animation = None;
+            return;
+        };
+        let t = ((ui.input(|i| i.time) - anim.start) / FOCUS_ANIMATION_DURATION) as f32;
+        let (pos, zoom) = anim.at(t, to, to_zoom);
+        Self::center_on(ui, id, rect, pos, Some(zoom));
+        if t < 1.0 {
+            ui.ctx().request_repaint();
         } else {
-            self.pinboard.graph.add_node(None)
+            self.focus_animation = None;
+        }
+    }
+
+    // Select the node and center the view on it
+    pub fn focus_node(&mut self, node: NodeIndex) {
+        self.select_only(&[node]);
+        self.focus = Some(FocusTarget::Node(node));
+    }
+
+    // Select the edge and center the view on its start node
+    pub fn focus_edge(&mut self, edge: EdgeIndex) {
+        let Some((start, _)) = self.pinboard.graph.edge_endpoints(edge) else {
+            return;
         };
-        let root = self.get_root();
-        self.update_blob_promise = Some(Promise::spawn_async(async move {
-            (Either::Node(id), Self::add_blob(root).await)
-        }));
+        self.select_edge_only(edge);
+        self.focus = Some(FocusTarget::Node(start));
+    }
+
+    // Select the first node with the label and center the view on it
+    pub fn focus_label(&mut self, label: &str) {
+        let Some(node) = self
+            .pinboard
+            .graph
+            .nodes_iter()
+            .find(|(_, n)| n.label() == label || n.payload().aliases.iter().any(|a| a == label))
+            .map(|(id, _)| id)
+        else {
+            warn!(
+                "no node labeled {} in pinboard {}",
+                label, self.pinboard.title
+            );
+            return;
+        };
+        self.focus_node(node);
+    }
+
+    // Fit the whole graph into the view
+    pub fn fit(&mut self) {
+        // Nodes hidden by the tag filter aren't shown, so they don't need to be in view
+        let nodes: Vec<_> = self
+            .pinboard
+            .graph
+            .nodes_iter()
+            .filter(|(_, n)| n.payload().visibility != Visibility::Hidden)
+            .map(|(id, _)| id)
+            .collect();
+        if !nodes.is_empty() {
+            self.focus = Some(FocusTarget::Frame(nodes));
+        }
+    }
+
+    // Fit the selected nodes into the view
+    fn fit_selection(&mut self) {
+        let nodes = Vec::from(self.pinboard.graph.selected_nodes());
+        if !nodes.is_empty() {
+            self.focus = Some(FocusTarget::Frame(nodes));
+        }
+    }
+
+    // Select a node uniformly at random and center the view on it
+    fn jump_to_random_node(&mut self) {
+        let nodes: Vec<NodeIndex> = self.pinboard.graph.g.node_indices().collect();
+        let Some(&node) = nodes.choose(&mut rand::thread_rng()) else {
+            return;
+        };
+        self.focus_node(node);
+    }
+
+    // Deselect everything except the given nodes
+    fn select_only(&mut self, nodes: &[NodeIndex]) {
+        for e in self.pinboard.graph.g.edge_weights_mut() {
+            e.set_selected(false);
+        }
+        for i in self.pinboard.graph.g.node_indices().collect::<Vec<_>>() {
+            if let Some(n) = self.pinboard.graph.node_mut(i) {
+                n.set_selected(nodes.contains(&i));
+            }
+        }
+        // The lists are otherwise only updated by the next frame of the graph view
+        let graph = &mut self.pinboard.graph;
+        let selected = nodes.iter().copied().filter(|n| graph.node(*n).is_some());
+        graph.set_selected_nodes(selected.collect());
+        graph.set_selected_edges(Vec::new());
+    }
+
+    // Select every node on the board, doing nothing on an empty board
+    fn select_all(&mut self) {
+        let nodes: Vec<_> = self.pinboard.graph.g.node_indices().collect();
+        if !nodes.is_empty() {
+            self.select_only(&nodes);
+        }
+    }
+
+    // Tags of all nodes, once per node carrying them
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.pinboard
+            .graph
+            .nodes_iter()
+            .flat_map(|(_, n)| n.payload().tags())
+    }
+
+    // Show the nodes carrying any of the filtered tags, dimming or hiding the others
+    fn apply_tag_filter(&mut self) {
+        let filtered = if self.hide_filtered {
+            Visibility::Hidden
+        } else {
+            Visibility::Dimmed
+        };
+        for node in self.pinboard.graph.g.node_weights_mut() {
+            let data = node.payload_mut();
+            data.visibility =
+                if self.tag_filter.is_empty() || data.tags().any(|t| self.tag_filter.contains(t)) {
+                    Visibility::Shown
+                } else {
+                    filtered
+                };
+        }
+    }
+
+    // Toggle the tags of the board to filter its nodes by
+    fn show_tag_filter_bar(&mut self, ui: &mut Ui) {
+        let tags: BTreeSet<String> = self.tags().map(str::to_string).collect();
+        // Forget tags removed from every node
+        self.tag_filter.retain(|t| tags.contains(t));
+        if tags.is_empty() {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Tags:");
+            for tag in tags {
+                let mut on = self.tag_filter.contains(&tag);
+                if ui.toggle_value(&mut on, &tag).changed() {
+                    if on {
+                        self.tag_filter.insert(tag);
+                    } else {
+                        self.tag_filter.remove(&tag);
+                    }
+                }
+            }
+            ui.separator();
+            ui.checkbox(&mut self.hide_filtered, "Hide others");
+            if ui
+                .add_enabled(!self.tag_filter.is_empty(), Button::new("Clear"))
+                .clicked()
+            {
+                self.tag_filter.clear();
+            }
+        });
+        ui.separator();
+    }
+
+    // Select the nodes carrying the tag, or nothing if no tag is given
+    pub fn select_tagged(&mut self, tag: Option<&str>) {
+        let nodes: Vec<_> = self
+            .pinboard
+            .graph
+            .nodes_iter()
+            .filter(|(_, n)| tag.is_some_and(|t| n.payload().tags().any(|x| x == t)))
+            .map(|(id, _)| id)
+            .collect();
+        self.select_only(&nodes);
+    }
+
+    // Select only the edge
+    fn select_edge_only(&mut self, edge: EdgeIndex) {
+        self.select_only(&[]);
+        if let Some(e) = self.pinboard.graph.edge_mut(edge) {
+            e.set_selected(true);
+            self.pinboard.graph.set_selected_edges(vec![edge]);
+        }
+    }
+
+    // Path of the markdown file of the only selected node
+    fn previewed_path(&self) -> Option<PathBuf> {
+        let [node] = self.pinboard.graph.selected_nodes() else {
+            return None;
+        };
+        let blob = self.pinboard.graph.node(*node)?.payload().blob.as_ref()?;
+        (*blob.ty() == BlobType::File && preview::is_markdown(blob.path()))
+            .then(|| blob.path().clone())
+    }
+
+    // List every edge carrying a comment
+    fn show_comments_panel(&mut self, ui: &mut Ui) {
+        ui.heading("Edge comments");
+        ui.separator();
+        let mut to_open = None;
+        let mut to_select = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let graph = &self.pinboard.graph;
+            for (id, e) in graph.edges_iter() {
+                let Some(comment) = &e.payload().comment else {
+                    continue;
+                };
+                let (a, b) = graph.edge_endpoints(id).unwrap();
+                let label = |n| graph.node(n).map(|n| n.label()).unwrap_or_default();
+                ui.label(format!(
+                    "{} — {} ({})",
+                    label(a),
+                    label(b),
+                    e.payload().relation.label()
+                ));
+                ui.horizontal(|ui| {
+                    if ui
+                        .link(
+                            comment
+                                .path()
+                                .file_name()
+                                .map(|s| s.to_string_lossy().to_string())
+                                .unwrap_or_default(),
+                        )
+                        .on_hover_text(comment.path().display().to_string())
+                        .clicked()
+                    {
+                        to_open = Some((id, comment.clone()));
+                    }
+                    if ui.small_button("Select").clicked() {
+                        to_select = Some(id);
+                    }
+                });
+                ui.separator();
+            }
+        });
+        if let Some((id, blob)) = to_open {
+            self.open_blobs(vec![(Either::Edge(id), blob)]);
+        }
+        if let Some(id) = to_select {
+            self.select_edge_only(id);
+        }
+    }
+
+    fn progress_bar(ui: &mut Ui, counts: &RelationCounts) {
+        let text = format!(
+            "{} progress, {} conflicts",
+            counts.progress, counts.conflict
+        );
+        match counts.resolved() {
+            Some(resolved) => {
+                ui.add(egui::ProgressBar::new(resolved).text(text));
+            }
+            None => {
+                ui.weak(text);
+            }
+        }
+    }
+
+    // Read-only summary of how resolved the board is, in terms of progress against conflicts
+    fn show_progress_panel(&self, ui: &mut Ui) {
+        let counts = progress::overall(&self.pinboard.graph);
+        ui.horizontal(|ui| {
+            ui.strong("Understanding");
+            Self::progress_bar(ui, &counts);
+            ui.label(format!(
+                "{} related, {} insights",
+                counts.related, counts.insight
+            ));
+        });
+        egui::CollapsingHeader::new("Clusters").show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("clusters").num_columns(2).show(ui, |ui| {
+                        for c in progress::clusters(&self.pinboard.graph) {
+                            ui.label(format!("{} ({} nodes)", c.name, c.size));
+                            Self::progress_bar(ui, &c.counts);
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+    }
+
+    // Display the graph view along with its shortcuts and context menu
+    fn show_graph(&mut self, ui: &mut Ui, id: Id, clipboard: &mut Option<Clipboard>) {
+        // Holding space turns primary dragging into panning like in design tools, the graph view
+        // pans on drags which neither start from a node nor drag out a selection rectangle, so we
+        // just stop both. Middle mouse dragging pans by itself.
+        let space_pan = !ui.ctx().wants_keyboard_input() && ui.input(|i| i.key_down(Key::Space));
+        let resp = ui.add(
+            // We cannot save graphview because it borrows the underlying graph. And we
+            // cannot do self-referential struct...
+            &mut PinboardGraphView::new(&mut self.pinboard.graph, id)
+                .with_interactions(
+                    &SettingsInteraction::new()
+                        .with_dragging_enabled(
+                            !space_pan && !self.read_only && !self.pinboard.lock_layout,
+                        )
+                        .with_node_clicking_enabled(true)
+                        .with_node_selection_enabled(true)
+                        .with_node_selection_multi_enabled(true)
+                        .with_edge_selection_enabled(true)
+                        .with_edge_selection_multi_enabled(true)
+                        .with_edge_creation_enabled(!self.read_only)
+                        .with_rect_selection_enabled(!space_pan),
+                )
+                .with_navigations(
+                    // Fitting is animated by us instead
+                    &SettingsNavigation::new()
+                        .with_zoom_and_pan_enabled(true)
+                        .with_fit_to_screen_enabled(false),
+                )
+                .with_styles(&self.pinboard.view.style())
+                .with_events(&self.event_publisher),
+        );
+
+        self.graph_rect = Some(resp.rect);
+
+        if resp.hovered() && space_pan {
+            ui.ctx().set_cursor_icon(if resp.dragged() {
+                CursorIcon::Grabbing
+            } else {
+                CursorIcon::Grab
+            });
+        }
+
+        self.clamp_zoom(ui, id, resp.rect);
+        // Dragging or zooming by the user takes over from the animation
+        if resp.dragged() || (resp.hovered() && ui.input(|i| i.smooth_scroll_delta != Vec2::ZERO)) {
+            self.focus_animation = None;
+        }
+        if let Some(target) = self.focus.take() {
+            self.start_focus(ui, id, resp.rect, target);
+        }
+        self.animate_focus(ui, id, resp.rect);
+
+        self.show_relation_picker(ui.ctx(), id);
+
+        // Technically you could also directly use context.data_mut, but we wouldn't bother
+        // to write it like that.
+        // NOTE: It's important to make sure metadata is updated before we process cursor
+        // information
+        let metadata = Metadata::load(ui, id);
+
+        // Show the description and the preview of the hovered node
+        let hovered = ui
+            .input(|i| i.pointer.hover_pos())
+            .filter(|_| resp.hovered())
+            .and_then(|pos| self.pinboard.graph.node_by_screen_pos(&metadata, pos));
+        let payload = hovered
+            .and_then(|n| self.pinboard.graph.node(n))
+            .map(|n| n.payload());
+        let description = payload
+            .map(|p| p.description.clone())
+            .filter(|d| !d.is_empty());
+        // Renamed nodes still tell what they refer to
+        let content = payload
+            .filter(|p| p.label.is_some())
+            .and_then(|p| p.blob.as_ref())
+            .map(Blob::name);
+        let preview =
+            self.hover_previews
+                .poll(ui.ctx(), hovered, payload.and_then(|p| p.blob.as_ref()));
+        let resp = if description.is_some() || content.is_some() || preview.is_some() {
+            resp.on_hover_ui_at_pointer(|ui| {
+                if let Some(c) = content {
+                    ui.weak(c);
+                }
+                if let Some(d) = description {
+                    ui.label(d);
+                }
+                if let Some(uri) = preview {
+                    ui.add(egui::Image::new(uri).max_width(300.0));
+                }
+            })
+        } else {
+            resp
+        };
+
+        if resp.hovered() {
+            // Ctrl+C and friends arrive as events rather than key presses, text fields handle
+            // them on their own
+            if ui.memory(|m| m.focused().is_none()) {
+                let pos = ui
+                    .input(|i| i.pointer.hover_pos())
+                    .unwrap_or(resp.rect.center());
+                self.handle_clipboard_events(
+                    ui.ctx(),
+                    clipboard,
+                    metadata.screen_to_canvas_pos(pos),
+                );
+            }
+            // Process keyboard shortcuts, more specific ones first as consuming a shortcut
+            // ignores extra Shift and Alt
+            for command in keymap().by_specificity() {
+                if !command.is_global()
+                    && self.can_run(command)
+                    && ui.input_mut(|i| i.consume_shortcut(&shortcut(command)))
+                {
+                    self.run_command(command, ui, &metadata);
+                }
+            }
+        }
+
+        // Files dragged in from the OS become nodes where they are dropped
+        if !self.read_only && self.drop_promise.is_none() {
+            let (paths, pos) = ui.input(|i| {
+                let paths: Vec<_> = i
+                    .raw
+                    .dropped_files
+                    .iter()
+                    .filter_map(|f| f.path.clone())
+                    .collect();
+                (paths, i.pointer.hover_pos())
+            });
+            if let Some(pos) = pos.filter(|p| !paths.is_empty() && resp.rect.contains(*p)) {
+                self.drop_files(paths, metadata.screen_to_canvas_pos(pos));
+            }
+        }
+
+        resp.context_menu(|ui| {
+            if self.read_only {
+                ui.label("This pinboard is read-only");
+                return;
+            }
+            // Position when user interacted in the context menu, this value should be
+            // saved for the use of node addition later, either passing through closure.
+            let pos = ui.input(|i| i.pointer.interact_pos());
+            // TODO: These should spun up a property sidepanel and ask user to put their
+            // stuff there
+            if ui.button("Add node").clicked() {
+                self.add_node(pos, &metadata);
+                ui.close_menu();
+            }
+            if ui.button("Add note node").clicked() {
+                self.add_note_node(pos.map(|p| metadata.screen_to_canvas_pos(p)));
+                ui.close_menu();
+            }
+            if ui.button("Add link node").clicked() {
+                self.link_draft = Some(LinkDraft {
+                    pos: pos.map(|p| metadata.screen_to_canvas_pos(p)),
+                    ..Default::default()
+                });
+                ui.close_menu();
+            }
+
+            if self.pinboard.graph.selected_nodes().len() == 1 {
+                ui.separator();
+                if ui
+                    .add(
+                        Button::new("Add connected node").shortcut_text(
+                            ui.ctx()
+                                .format_shortcut(&shortcut(Command::AddConnectedNode)),
+                        ),
+                    )
+                    .clicked()
+                {
+                    let from = self.pinboard.graph.selected_nodes()[0];
+                    self.add_connected_node(from);
+                    ui.close_menu();
+                }
+                let id = self.pinboard.graph.selected_nodes()[0];
+                if ui
+                    .add_enabled(
+                        !self.read_only,
+                        Button::new("Rename node")
+                            .shortcut_text(ui.ctx().format_shortcut(&shortcut(Command::Rename))),
+                    )
+                    .clicked()
+                {
+                    self.start_renaming_node(id);
+                    ui.close_menu();
+                }
+                let card = self.pinboard.graph.node(id).unwrap().payload().card;
+                if ui
+                    .button(if card {
+                        "Show as circle"
+                    } else {
+                        "Show as card"
+                    })
+                    .clicked()
+                {
+                    let data = self.pinboard.graph.node_mut(id).unwrap().payload_mut();
+                    data.card = !card;
+                    // Refresh the snippet in case the file has changed
+                    if let Some(mut blob) = data.blob.clone().filter(|_| !card) {
+                        let scope = self.search_scope();
+                        let refresh_hash = !self.pinboard.freeze_hashes;
+                        self.update_blob_promise = Some(Promise::spawn_blocking(move || {
+                            let res = blob.update(&scope, refresh_hash).map(|()| Some(blob));
+                            (Either::Node(id), res)
+                        }));
+                    }
+                    self.unsaved = true;
+                    ui.close_menu();
+                }
+                ui.menu_button("Connect to new note", |ui| {
+                    let (clicked, relation) =
+                        Self::show_relation_submenu(ui, self.pinboard.relations(), None);
+                    if clicked {
+                        let loc = self.pinboard.graph.node(id).unwrap().location();
+                        let note = self.add_note_node(Some(
+                            loc + CONNECTED_NODE_OFFSET * self.pinboard.view.node_spacing,
+                        ));
+                        let label = relation.label();
+                        self.pinboard.graph.add_edge_with_label(
+                            id,
+                            note,
+                            Conn::new(relation),
+                            label,
+                        );
+                    }
+                });
+                ui.menu_button("Description", |ui| {
+                    let data = self.pinboard.graph.node(id).unwrap().payload();
+                    let mut text = data.description.clone();
+                    let resp = ui.text_edit_multiline(&mut text);
+                    if self.edited(&resp) {
+                        let data = self.pinboard.graph.node_mut(id).unwrap().payload_mut();
+                        data.description = text;
+                        data.touch();
+                        self.unsaved = true;
+                    }
+                });
+                ui.menu_button("Aliases", |ui| {
+                    ui.label("One alias per line:");
+                    let mut text = self
+                        .pinboard
+                        .graph
+                        .node(id)
+                        .unwrap()
+                        .payload()
+                        .aliases
+                        .join("\n");
+                    let resp = ui.text_edit_multiline(&mut text);
+                    if self.edited(&resp) {
+                        let data = self.pinboard.graph.node_mut(id).unwrap().payload_mut();
+                        data.aliases = if text.is_empty() {
+                            Vec::new()
+                        } else {
+                            text.split('\n').map(str::to_string).collect()
+                        };
+                        self.unsaved = true;
+                    }
+                });
+                ui.menu_button("Tags", |ui| {
+                    ui.label("One tag per line:");
+                    let mut text = self
+                        .pinboard
+                        .graph
+                        .node(id)
+                        .unwrap()
+                        .payload()
+                        .tags
+                        .join("\n");
+                    let resp = ui.text_edit_multiline(&mut text);
+                    if self.edited(&resp) {
+                        let data = self.pinboard.graph.node_mut(id).unwrap().payload_mut();
+                        data.tags = if text.is_empty() {
+                            Vec::new()
+                        } else {
+                            text.split('\n').map(str::to_string).collect()
+                        };
+                        data.touch();
+                        self.unsaved = true;
+                    }
+                });
+                if ui.button("Update node").clicked() {
+                    let id = self.pinboard.graph.selected_nodes()[0].clone();
+                    self.pick_blob(Either::Node(id));
+                    ui.close_menu();
+                }
+                if let Some(blob) = self
+                    .pinboard
+                    .graph
+                    .node(id)
+                    .and_then(|n| n.payload().blob.clone())
+                    .filter(|b| *b.ty() == BlobType::File)
+                {
+                    ui.menu_button("Open with", |ui| {
+                        for opener in Opener::CHOICES {
+                            if ui.button(opener.label()).clicked() {
+                                self.open_blobs(vec![(Either::Node(id), blob.clone())]);
+                                self.open_with = opener;
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+                if self
+                    .pinboard
+                    .graph
+                    .node(id)
+                    .unwrap()
+                    .payload()
+                    .blob
+                    .is_some()
+                    && ui
+                        .button("Detach blob")
+                        .on_hover_text("Keep the node and its edges but drop its content")
+                        .clicked()
+                {
+                    self.checkpoint();
+                    let node = self.pinboard.graph.node_mut(id).unwrap();
+                    node.payload_mut().blob = None;
+                    // Same label as a freshly added node unless the user named it
+                    let label = node.payload().label_or(id.index().to_string());
+                    node.set_label(label);
+                    self.unsaved = true;
+                    ui.close_menu();
+                }
+            }
+
+            // Display context menu based on what we have selected
+            if self.pinboard.graph.selected_nodes().len() > 1 {
+                if ui.button("Open all selected").clicked() {
+                    let nodes = Vec::from(self.pinboard.graph.selected_nodes());
+                    if nodes.len() > OPEN_ALL_CONFIRM_THRESHOLD {
+                        self.nodes_to_open = Some(nodes);
+                    } else {
+                        self.open_nodes(&nodes);
+                    }
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(!self.read_only, Button::new("Extract to sub-board..."))
+                    .on_hover_text("Move the selected nodes into a new pinboard linked from here")
+                    .clicked()
+                {
+                    self.extract_selection();
+                    ui.close_menu();
+                }
+                ui.add_enabled_ui(!self.read_only && !self.pinboard.lock_layout, |ui| {
+                    ui.menu_button("Align", |ui| {
+                        for alignment in graph::Alignment::ALL {
+                            if ui.button(alignment.label()).clicked() {
+                                self.align_selection(alignment);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+            }
+
+            if self.pinboard.graph.selected_nodes().len() > 0 {
+                if ui.button("Delete selected node(s)").clicked() {
+                    self.delete_nodes(&Vec::from(self.pinboard.graph.selected_nodes()));
+                    ui.close_menu();
+                }
+            }
+
+            // If we have two nodes selected, offer an option to connect them by edge
+            if self.pinboard.graph.selected_nodes().len() == 2 {
+                let a = self.pinboard.graph.selected_nodes()[0];
+                let b = self.pinboard.graph.selected_nodes()[1];
+                if self.pinboard.graph.g().find_edge(a, b).is_none() {
+                    if ui
+                        .button(format!("Connect as {}", self.last_relation.label()))
+                        .on_hover_text("Connect with the relation used last")
+                        .clicked()
+                    {
+                        let relation = self.last_relation.clone();
+                        self.apply_relation(RelationTarget::Connect(a, b), relation);
+                        ui.close_menu();
+                    }
+                    ui.menu_button("Connect with", |ui| {
+                        let (clicked, relation) = Self::show_relation_submenu(
+                            ui,
+                            self.pinboard.relations(),
+                            Some(&self.last_relation),
+                        );
+                        if clicked {
+                            self.apply_relation(RelationTarget::Connect(a, b), relation);
+                        }
+                    });
+                }
+            }
+
+            if self.pinboard.graph.selected_edges().len() == 1 {
+                let id = self.pinboard.graph.selected_edges()[0];
+                if ui.button("Add to the Edge").clicked() {
+                    self.pick_blob(Either::Edge(id));
+                    ui.close_menu();
+                }
+
+                if ui.button("Reverse direction").clicked() {
+                    self.checkpoint();
+                    let conn = self.pinboard.graph.edge_mut(id).unwrap().payload_mut();
+                    conn.arrow = conn.arrow.reversed();
+                    self.unsaved = true;
+                    ui.close_menu();
+                }
+                if self.pinboard.graph.edge(id).unwrap().payload().arrow != Arrow::None
+                    && ui.button("Remove direction").clicked()
+                {
+                    self.checkpoint();
+                    self.pinboard
+                        .graph
+                        .edge_mut(id)
+                        .unwrap()
+                        .payload_mut()
+                        .arrow = Arrow::None;
+                    self.unsaved = true;
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Change Relation", |ui| {
+                    let (clicked, relation) =
+                        Self::show_relation_submenu(ui, self.pinboard.relations(), None);
+                    if clicked {
+                        self.apply_relation(RelationTarget::Edges(vec![id]), relation);
+                    }
+                });
+            }
+
+            if self.pinboard.graph.selected_edges().len() > 1 {
+                ui.menu_button("Set relation for all selected", |ui| {
+                    let (clicked, relation) =
+                        Self::show_relation_submenu(ui, self.pinboard.relations(), None);
+                    if clicked {
+                        let edges = Vec::from(self.pinboard.graph.selected_edges());
+                        self.apply_relation(RelationTarget::Edges(edges), relation);
+                    }
+                });
+            }
+
+            if self.pinboard.graph.selected_edges().len() > 0 {
+                if ui.button("Delete selected edge(s)").clicked() {
+                    self.checkpoint();
+                    for e in Vec::from(self.pinboard.graph.selected_edges()) {
+                        self.pinboard.graph.remove_edge(e);
+                    }
+                    self.unsaved = true;
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    // File, Edit and View menus of the pinboard window
+    fn show_menus(&mut self, ui: &mut Ui, ctx: &Context, id: Id) {
+        ui.menu_button("File", |ui| {
+            for action in [Action::Save, Action::Rename] {
+                if ui
+                    .add_enabled(
+                        !self.read_only || !action.edits(),
+                        Button::new(action.label()).shortcut_text(action.shortcut_text(ctx)),
+                    )
+                    .clicked()
+                {
+                    self.perform(action, ui, id);
+                    ui.close_menu();
+                }
+            }
+            if ui
+                .add_enabled(!self.read_only, Button::new("Properties..."))
+                .clicked()
+            {
+                self.show_properties_modal = true;
+                ui.close_menu();
+            }
+            if ui
+                .button("Validate board")
+                .on_hover_text("Look for missing and modified files in the background")
+                .clicked()
+            {
+                self.validate();
+                ui.close_menu();
+            }
+            if ui.button("Export manifest...").clicked() {
+                self.export_manifest();
+                ui.close_menu();
+            }
+            if ui
+                .button("Export adjacency matrix...")
+                .on_hover_text(
+                    "Relations as numbers: 0 none, 1 related, 2 insight, \
+                     3 progress, 4 conflict",
+                )
+                .clicked()
+            {
+                self.export_adjacency();
+                ui.close_menu();
+            }
+            if ui.button("Export JSON-LD...").clicked() {
+                self.export_jsonld();
+                ui.close_menu();
+            }
+            if ui
+                .button("Export DOT/SVG...")
+                .on_hover_text("Rendering SVG requires GraphViz to be installed")
+                .clicked()
+            {
+                self.export_dot();
+                ui.close_menu();
+            }
+            if ui
+                .button("Export bundle...")
+                .on_hover_text("Write the pinboard together with copies of its files to share it")
+                .clicked()
+            {
+                self.export_bundle();
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(!self.read_only, Button::new("Import edges from CSV..."))
+                .on_hover_text("Connect nodes by label from source,target,relation rows")
+                .clicked()
+            {
+                self.import_edges();
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(!self.read_only, Button::new("Make paths relative..."))
+                .on_hover_text("Save blob paths relative to a chosen directory for sharing")
+                .clicked()
+            {
+                self.make_paths_relative();
+                ui.close_menu();
+            }
+            if let Some(base) = &self.pinboard.path_base {
+                if ui
+                    .add_enabled(!self.read_only, Button::new("Make paths relative to file"))
+                    .on_hover_text(format!(
+                        "Save blob paths relative to the pinboard file instead of {}",
+                        base.display()
+                    ))
+                    .clicked()
+                {
+                    self.pinboard.path_base = None;
+                    self.unsaved = true;
+                    ui.close_menu();
+                }
+            }
+            if ui.button("Capture view as PNG...").clicked() {
+                self.request_screenshot(ctx);
+                ui.close_menu();
+            }
+            ui.checkbox(&mut self.read_only, "Read-only");
+            ui.add_enabled_ui(!self.read_only, |ui| {
+                ui.menu_button("Neovim", |ui| self.show_nvim_menu(ui));
+                if ui
+                    .checkbox(&mut self.pinboard.lock_layout, "Lock layout")
+                    .on_hover_text("Prevent nodes from being moved")
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+                if ui
+                    .checkbox(
+                        &mut self.pinboard.open_children_read_only,
+                        "Open nested pinboards read-only",
+                    )
+                    .on_hover_text("Ctrl+double-click opens them the other way")
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+                if ui
+                    .checkbox(&mut self.pinboard.compress, "Compress file")
+                    .on_hover_text("Save the pinboard file gzip compressed")
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+                if ui
+                    .checkbox(&mut self.pinboard.compact_on_save, "Compact on save")
+                    .on_hover_text("Renumber nodes and edges densely when saving")
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+                if ui
+                    .checkbox(&mut self.pinboard.resolve_symlinks, "Resolve symlinks")
+                    .on_hover_text("Store the real path of symlinked files when adding them")
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+                if ui
+                    .checkbox(&mut self.pinboard.freeze_hashes, "Freeze blob hashes")
+                    .on_hover_text(
+                        "Keep the hashes of existing files instead of refreshing them \
+                     on open, warning when their content has changed",
+                    )
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+                if ui
+                    .checkbox(&mut self.pinboard.check_git, "Warn about uncommitted blobs")
+                    .on_hover_text(
+                        "Check on open whether files have uncommitted changes in their \
+                     git repository, which may make their hashes drift",
+                    )
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+            });
+        });
+        ui.menu_button("Edit", |ui| {
+            if ui
+                .add_enabled(
+                    !self.read_only && !self.undo_stack.is_empty(),
+                    Button::new("Undo")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::Undo))),
+                )
+                .clicked()
+            {
+                self.undo();
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(
+                    !self.read_only && !self.redo_stack.is_empty(),
+                    Button::new("Redo")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::Redo))),
+                )
+                .clicked()
+            {
+                self.redo();
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui
+                .add_enabled(
+                    self.pinboard.graph.node_count() > 0,
+                    Button::new("Select all nodes")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::SelectAll))),
+                )
+                .clicked()
+            {
+                self.select_all();
+                ui.close_menu();
+            }
+            if ui
+                .add(
+                    Button::new("Deselect all")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::DeselectAll))),
+                )
+                .clicked()
+            {
+                self.select_only(&[]);
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("Relations...").clicked() {
+                self.show_relations_modal = true;
+                ui.close_menu();
+            }
+            ui.menu_button("Layout", |ui| {
+                if ui.button("Settings...").clicked() {
+                    self.show_layout_modal = true;
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(
+                        !self.read_only && !self.pinboard.lock_layout,
+                        Button::new("Force-directed"),
+                    )
+                    .clicked()
+                {
+                    self.start_force_layout();
+                    ui.close_menu();
+                }
+            });
+            if ui
+                .add_enabled(
+                    !self.read_only && self.relation_target().is_some(),
+                    Button::new("Pick relation")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::RelationPicker))),
+                )
+                .on_hover_text(
+                    "Connect the two selected nodes or change the relation of selected edges",
+                )
+                .clicked()
+            {
+                if let Some(pos) = ui.input(|i| i.pointer.interact_pos()) {
+                    self.open_relation_picker(pos);
+                }
+                ui.close_menu();
+            }
+        });
+        ui.menu_button("View", |ui| {
+            if ui
+                .add_enabled(
+                    self.pinboard.graph.node_count() > 0,
+                    Button::new("Fit all")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::Fit))),
+                )
+                .clicked()
+            {
+                self.fit();
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(
+                    !self.pinboard.graph.selected_nodes().is_empty(),
+                    Button::new("Fit selection")
+                        .shortcut_text(ctx.format_shortcut(&shortcut(Command::FitSelection))),
+                )
+                .clicked()
+            {
+                self.fit_selection();
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.checkbox(&mut self.show_list, "List view");
+            ui.checkbox(&mut self.show_comments, "Edge comments");
+            ui.checkbox(&mut self.show_progress, "Progress overview");
+            ui.checkbox(&mut self.show_preview, "Markdown preview");
+            if ui
+                .checkbox(&mut self.pinboard.view.compact_toolbar, "Compact toolbar")
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            if ui
+                .checkbox(
+                    &mut self.pinboard.view.animate_focus,
+                    "Animate jumps to nodes",
+                )
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            if ui
+                .add_enabled(
+                    self.pinboard.view.animate_focus,
+                    egui::Checkbox::new(
+                        &mut self.pinboard.view.zoom_on_focus,
+                        "Reset zoom on jumps",
+                    ),
+                )
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            ui.separator();
+            if ui
+                .checkbox(&mut self.pinboard.view.show_edge_labels, "Show edge labels")
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            if ui
+                .checkbox(&mut self.pinboard.view.show_grid, "Show grid")
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            if ui
+                .checkbox(&mut self.pinboard.view.snap_to_grid, "Snap to grid")
+                .on_hover_text("Move dropped nodes to the nearest grid intersection")
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Grid spacing");
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut self.pinboard.view.grid_spacing)
+                            .range(5.0..=500.0),
+                    )
+                    .changed()
+                {
+                    self.unsaved = true;
+                }
+            });
+            if ui
+                .checkbox(
+                    &mut self.pinboard.view.edges_to_center,
+                    "Edges to node centers",
+                )
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            if ui
+                .checkbox(
+                    &mut self.pinboard.view.edge_label_background,
+                    "Edge label background",
+                )
+                .changed()
+            {
+                self.unsaved = true;
+            }
+            ui.menu_button("Fade old elements", |ui| {
+                let view = &mut self.pinboard.view;
+                let mut changed = ui
+                    .checkbox(&mut view.fade_old, "Enabled")
+                    .on_hover_text("Fade nodes and edges by the time they were last edited")
+                    .changed();
+                ui.add_enabled_ui(view.fade_old, |ui| {
+                    egui::Grid::new("fade_settings").show(ui, |ui| {
+                        ui.label("Fade after (days)");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut view.fade_after_days).range(0.0..=3650.0),
+                            )
+                            .changed();
+                        ui.end_row();
+                        ui.label("Fade over (days)");
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut view.fade_span_days).range(0.0..=3650.0))
+                            .changed();
+                        ui.end_row();
+                        ui.label("Minimum opacity");
+                        changed |= ui
+                            .add(egui::Slider::new(&mut view.fade_min_opacity, 0.0..=1.0))
+                            .changed();
+                        ui.end_row();
+                    });
+                });
+                if changed {
+                    self.unsaved = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                let view = &mut self.pinboard.view;
+                let mut custom = view.background.is_some();
+                let mut color = view.background.unwrap_or(Color32::from_gray(30));
+                let toggled = ui.checkbox(&mut custom, "Background").changed();
+                let picked = custom && ui.color_edit_button_srgba(&mut color).changed();
+                if toggled || picked {
+                    view.background = custom.then_some(color);
+                    self.unsaved = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                let view = &mut self.pinboard.view;
+                ui.label("Zoom limits");
+                let min = ui.add(
+                    egui::DragValue::new(&mut view.min_zoom)
+                        .range(0.01..=view.max_zoom)
+                        .speed(0.01),
+                );
+                let max = ui.add(
+                    egui::DragValue::new(&mut view.max_zoom)
+                        .range(view.min_zoom..=100.0)
+                        .speed(0.1),
+                );
+                if min.changed() || max.changed() {
+                    self.unsaved = true;
+                }
+            });
+            ui.menu_button("Node labels", |ui| {
+                for (position, name) in [
+                    (LabelPosition::Above, "Above"),
+                    (LabelPosition::Inside, "Inside"),
+                    (LabelPosition::Below, "Below"),
+                    (LabelPosition::Right, "Right"),
+                ] {
+                    if ui
+                        .radio_value(&mut self.pinboard.view.node_label_position, position, name)
+                        .changed()
+                    {
+                        self.unsaved = true;
+                    }
+                }
+            });
+        });
     }
 
-    fn show_relation_submenu(ui: &mut Ui) -> (bool, Relation) {
-        let mut relation = Relation::Related;
-        let mut clicked = false;
-        if ui.button("Related").clicked() {
-            relation = Relation::Related;
-            clicked = true;
-            ui.close_menu();
-        }
-        if ui.button("Insight").clicked() {
-            relation = Relation::Insight;
-            clicked = true;
-            ui.close_menu();
-        }
-        if ui.button("Progress").clicked() {
-            relation = Relation::Progress;
-            clicked = true;
-            ui.close_menu();
+    fn perform(&mut self, action: Action, ui: &mut Ui, id: Id) {
+        if self.read_only && action.edits() {
+            return;
         }
-        if ui.button("Conflict").clicked() {
-            relation = Relation::Conflict;
-            clicked = true;
-            ui.close_menu();
+        match action {
+            Action::Save => self.save(),
+            Action::Rename => self.show_rename_modal = true,
+            Action::AddNode => {
+                let metadata = Metadata::load(ui, id);
+                let pos = self.graph_rect.map(|r| r.center());
+                self.add_node(pos, &metadata);
+            }
+            Action::Fit => self.fit(),
+            Action::RandomNode => self.jump_to_random_node(),
+            Action::ResetView => PinboardGraphView::reset_metadata(id, ui),
         }
-        return (clicked, relation);
     }
 
-    // Display the UI and optionally return the Blob to preview
-    pub fn show(&mut self, ctx: &Context, open: &mut bool) -> Option<Blob> {
-        let mut metadata = Metadata::default();
-        let id = Id::new(self.pinboard.uuid);
-        // keyboard shortcuts
-        let save_shortcut = KeyboardShortcut::new(Modifiers::CTRL, Key::S);
-        let rename_shortcut = KeyboardShortcut::new(Modifiers::NONE, Key::F2);
-        let add_node_shortcut = KeyboardShortcut::new(Modifiers::CTRL, Key::N);
-        let title = format!(
-            "{}{}",
+    // Title of the window or tab, marking unsaved and read-only pinboards
+    pub fn title(&self) -> String {
+        format!(
+            "{}{}{}",
             self.pinboard.title.as_str(),
-            if self.unsaved { "*" } else { "" }
-        );
+            if self.unsaved { "*" } else { "" },
+            if self.read_only { " (read-only)" } else { "" }
+        )
+    }
 
-        Window::new(title)
+    // Display the UI in its own window and return the Blobs to preview
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        open: &mut bool,
+        clipboard: &mut Option<Clipboard>,
+    ) -> Vec<Blob> {
+        let id = Id::new(self.pinboard.uuid);
+        let window = Window::new(self.title())
             // Set UUID as Id to avoid collision
             .id(id)
             .open(open)
-            .show(ctx, |ui| {
-                egui::menu::bar(ui, |ui| {
-                    ui.menu_button("File", |ui| {
-                        if ui
-                            .add(
-                                Button::new("Save")
-                                    .shortcut_text(ctx.format_shortcut(&save_shortcut)),
-                            )
-                            .clicked()
-                        {
-                            self.save();
-                            ui.close_menu();
-                        }
-                        if ui
-                            .add(
-                                Button::new("Rename")
-                                    .shortcut_text(ctx.format_shortcut(&rename_shortcut)),
-                            )
-                            .clicked()
-                        {
-                            self.show_rename_modal = true;
-                            ui.close_menu();
-                        }
-                    });
-                    if ui.button("Reset View").clicked() {
-                        PinboardGraphView::reset_metadata(id, ui);
-                    }
-                });
-                ui.separator();
-                let resp = ui.add(
-                    // We cannot save graphview because it borrows the underlying graph. And we
-                    // cannot do self-referential struct...
-                    &mut PinboardGraphView::new(&mut self.pinboard.graph, id)
-                        .with_interactions(
-                            &SettingsInteraction::new()
-                                .with_dragging_enabled(true)
-                                .with_node_clicking_enabled(true)
-                                .with_node_selection_enabled(true)
-                                .with_node_selection_multi_enabled(true)
-                                .with_edge_selection_enabled(true)
-                                .with_edge_selection_multi_enabled(true),
-                        )
-                        .with_navigations(
-                            &SettingsNavigation::new()
-                                .with_zoom_and_pan_enabled(true)
-                                .with_fit_to_screen_enabled(false),
-                        )
-                        .with_events(&self.event_publisher),
-                );
+            .show(ctx, |ui| self.show_contents(ui, id, clipboard));
+        self.window_rect = window.map(|w| w.response.rect);
+        self.update(ctx, id)
+    }
 
-                // Technically you could also directly use context.data_mut, but we wouldn't bother
-                // to write it like that.
-                // NOTE: It's important to make sure metadata is updated before we process cursor
-                // information
-                metadata = Metadata::load(ui, id);
+    // Show the pinboard filling the ui of its tab, pinboards in other tabs are given no ui but
+    // still have to be updated
+    pub fn show_tab(
+        &mut self,
+        ctx: &Context,
+        ui: Option<&mut Ui>,
+        clipboard: &mut Option<Clipboard>,
+    ) -> Vec<Blob> {
+        let id = Id::new(self.pinboard.uuid);
+        self.window_rect = ui.map(|ui| {
+            self.show_contents(ui, id, clipboard);
+            ui.max_rect()
+        });
+        self.update(ctx, id)
+    }
 
-                if resp.hovered() {
-                    // Process keyboard shortcuts
-                    if ui.input_mut(|i| i.consume_shortcut(&save_shortcut)) {
-                        self.save();
-                    }
-                    if ui.input_mut(|i| i.consume_shortcut(&rename_shortcut)) {
-                        self.show_rename_modal = true;
+    fn show_contents(&mut self, ui: &mut Ui, id: Id, clipboard: &mut Option<Clipboard>) {
+        let ctx = &ui.ctx().clone();
+        egui::menu::bar(ui, |ui| {
+            if self.pinboard.view.compact_toolbar {
+                ui.menu_button("☰", |ui| self.show_menus(ui, ctx, id));
+                ui.separator();
+                for action in Action::TOOLBAR {
+                    if ui
+                        .add_enabled(
+                            !self.read_only || !action.edits(),
+                            Button::new(action.icon()),
+                        )
+                        .on_hover_text(action.hover_text(ctx))
+                        .clicked()
+                    {
+                        self.perform(action, ui, id);
                     }
-                    if ui.input_mut(|i| i.consume_shortcut(&add_node_shortcut)) {
-                        let pos = ui.input(|i| i.pointer.hover_pos());
-                        self.add_node(pos, &metadata);
+                }
+            } else {
+                self.show_menus(ui, ctx, id);
+                for action in [Action::Fit, Action::RandomNode, Action::ResetView] {
+                    if ui.button(action.label()).clicked() {
+                        self.perform(action, ui, id);
                     }
                 }
+            }
+        });
+        self.show_breadcrumbs(ui);
+        self.show_health_bar(ui);
+        self.show_tag_filter_bar(ui);
+        self.apply_tag_filter();
+        if self.show_progress {
+            egui::TopBottomPanel::bottom(id.with("progress"))
+                .show_inside(ui, |ui| self.show_progress_panel(ui));
+        }
+        if self.show_preview {
+            let path = self.previewed_path();
+            egui::SidePanel::right(id.with("preview"))
+                .default_width(300.0)
+                .show_inside(ui, |ui| self.preview.show(ui, path.as_deref()));
+        }
+        if self.show_comments {
+            egui::SidePanel::right(id.with("comments"))
+                .show_inside(ui, |ui| self.show_comments_panel(ui));
+        }
+        if self.show_list {
+            let to_open = self.node_list.show(ui, &mut self.pinboard.graph);
+            if let Some(node) = to_open {
+                self.open_nodes(&[node]);
+            }
+        } else {
+            self.show_graph(ui, id, clipboard);
+        }
 
-                resp.context_menu(|ui| {
-                    // Position when user interacted in the context menu, this value should be
-                    // saved for the use of node addition later, either passing through closure.
-                    let pos = ui.input(|i| i.pointer.interact_pos());
-                    // TODO: These should spun up a property sidepanel and ask user to put their
-                    // stuff there
-                    if ui.button("Add node").clicked() {
-                        self.add_node(pos, &metadata);
-                        ui.close_menu();
-                    }
+        self.show_rename_dialog(ui);
+        self.show_rename_node_dialog(ui);
+        self.show_properties_dialog(ui);
+        self.show_layout_dialog(ui);
+        self.show_relations_dialog(ui);
+        self.show_open_all_dialog(ui);
+        self.show_ambiguous_match_dialog(ui);
+        self.show_save_failed_dialog(ui);
+        self.show_unmatched_rows_dialog(ui);
+        self.show_outside_base_dialog(ui);
+        self.show_link_dialog(ui);
+    }
 
-                    if self.pinboard.graph.selected_nodes().len() == 1 {
-                        ui.separator();
-                        if ui.button("Update node").clicked() {
-                            let id = self.pinboard.graph.selected_nodes()[0].clone();
-                            let root = self.get_root();
-                            self.update_blob_promise = Some(Promise::spawn_async(async move {
-                                (Either::Node(id), Self::add_blob(root).await)
-                            }));
-                            ui.close_menu();
-                        }
-                    }
+    // Everything done every frame no matter whether the pinboard is visible, returning the blobs
+    // to be opened by the app
+    fn update(&mut self, ctx: &Context, id: Id) -> Vec<Blob> {
+        self.show_note_editor(ctx, id);
 
-                    // Display context menu based on what we have selected
-                    if self.pinboard.graph.selected_nodes().len() > 0 {
-                        if ui.button("Delete selected node(s)").clicked() {
-                            for n in Vec::from(self.pinboard.graph.selected_nodes()) {
-                                self.pinboard.graph.remove_node(n);
-                            }
-                            self.unsaved = true;
-                            ui.close_menu();
-                        }
-                    }
+        self.handle_events(ctx);
+        self.handle_screenshot(ctx);
+        self.step_force_layout(ctx);
 
-                    // If we have two nodes selected, offer an option to connect them by edge
-                    if self.pinboard.graph.selected_nodes().len() == 2 {
-                        let a = self.pinboard.graph.selected_nodes()[0];
-                        let b = self.pinboard.graph.selected_nodes()[1];
-                        if self.pinboard.graph.g().find_edge(a, b).is_none() {
-                            ui.menu_button("Connect with", |ui| {
-                                let selected = Vec::from(self.pinboard.graph.selected_nodes());
-                                let (clicked, relation) = Self::show_relation_submenu(ui);
-                                if clicked {
-                                    let label = relation.label();
-                                    self.pinboard.graph.add_edge_with_label(
-                                        selected[0],
-                                        selected[1],
-                                        Conn {
-                                            comment: None,
-                                            relation,
-                                        },
-                                        label,
-                                    );
-                                    self.unsaved = true;
-                                }
-                            });
-                        }
-                    }
+        // Handle Promises
+        handle_promise(&mut self.save_file_promise, |r| match r {
+            Ok(Some(p)) => {
+                self.path = Some(p.to_path_buf());
+                self.unsaved = false;
+                self.saved_for_close = std::mem::take(&mut self.close_after_save);
+                if let Err(e) = recovery::remove(self.pinboard.get_uuid()) {
+                    error!("cannot remove recovery file: {}", e);
+                }
+            }
+            Ok(None) => {
+                info!(
+                    "user didn't select path to save pinboard {}",
+                    self.pinboard.title
+                );
+                self.close_after_save = false;
+            }
+            Err(e) => {
+                error!("cannot save pinboard: {}", e);
+                self.save_error = Some(e.to_string());
+                self.close_after_save = false;
+            }
+        });
 
-                    if self.pinboard.graph.selected_edges().len() == 1 {
-                        let id = self.pinboard.graph.selected_edges()[0];
-                        if ui.button("Add to the Edge").clicked() {
-                            let root = self.get_root();
-                            self.update_blob_promise = Some(Promise::spawn_async(async move {
-                                (Either::Edge(id), Self::add_blob(root).await)
-                            }));
-                            ui.close_menu();
-                        }
+        handle_promise(&mut self.export_promise, |r| {
+            if let Err(e) = r {
+                error!("cannot export pinboard: {}", e);
+            }
+        });
 
-                        ui.menu_button("Change Relation", |ui| {
-                            let (clicked, relation) = Self::show_relation_submenu(ui);
-                            if clicked {
-                                let edge = self.pinboard.graph.edge_mut(id).unwrap();
-                                edge.set_label(relation.label());
-                                edge.payload_mut().relation = relation;
-                                self.unsaved = true;
-                            }
-                        });
-                    }
+        let root = handle_promise(&mut self.search_root_promise, |r| match r {
+            Ok(dir) => dir.clone(),
+            Err(e) => {
+                error!("cannot pick search root: {}", e);
+                None
+            }
+        });
+        if let Some(root) = root.flatten() {
+            if !self.pinboard.search_roots.contains(&root) {
+                self.pinboard.search_roots.push(root);
+                self.unsaved = true;
+            }
+        }
 
-                    if self.pinboard.graph.selected_edges().len() > 0 {
-                        if ui.button("Delete selected edge(s)").clicked() {
-                            for e in Vec::from(self.pinboard.graph.selected_edges()) {
-                                self.pinboard.graph.remove_edge(e);
-                            }
-                            self.unsaved = true;
-                            ui.close_menu();
-                        }
-                    }
-                });
+        let base = handle_promise(&mut self.base_dir_promise, |r| match r {
+            Ok(Some(dir)) => Some(dir.clone()),
+            Ok(None) => {
+                info!("user didn't select base directory");
+                None
+            }
+            Err(e) => {
+                error!("cannot pick base directory: {}", e);
+                None
+            }
+        });
+        if let Some(base) = base.flatten() {
+            let outside = self.rebase_paths(&base);
+            if !outside.is_empty() {
+                warn!(
+                    "{} blobs are outside of {:?} and kept their paths",
+                    outside.len(),
+                    base
+                );
+                self.outside_base = Some((base, outside));
+            }
+        }
 
-                self.show_rename_dialog(ui);
-            });
+        handle_promise(&mut self.health_promise, |report| {
+            if !report.is_healthy() {
+                warn!("pinboard {}: {}", self.pinboard.title, report.summary());
+                self.health = Some(report.clone());
+                self.show_health_details |= self.validating;
+            } else if self.validating {
+                self.health = Some(report.clone());
+            }
+            self.validating = false;
+        });
 
-        self.handle_events();
+        let extracted = handle_promise(&mut self.extract_promise, |(nodes, copied, r)| match r {
+            Ok(Some(blob)) => Some((nodes.clone(), copied.clone(), blob.clone())),
+            Ok(None) => {
+                info!("user didn't pick a file for the sub-board");
+                None
+            }
+            Err(e) => {
+                error!("cannot extract sub-board: {}", e);
+                None
+            }
+        });
+        if let Some((nodes, copied, blob)) = extracted.flatten() {
+            self.replace_with_sub_board(&nodes, &copied, blob);
+        }
 
-        // Handle Promises
-        handle_promise(&mut self.save_file_promise, |r| match r {
-            Ok(p) => {
-                self.path = Some(p.to_path_buf());
-                self.unsaved = false;
+        let relocation = handle_promise(&mut self.relocate_promise, |r| match r {
+            Ok((old, Some(new))) => Some((old.clone(), new.clone())),
+            Ok((old, None)) => {
+                info!("user didn't pick a file for {:?}", old);
+                None
             }
             Err(e) => {
-                error!("cannot save pinboard: {}", e);
+                error!("cannot pick file: {}", e);
+                None
+            }
+        });
+        if let Some((old, new)) = relocation.flatten() {
+            self.apply_relocation(&old, new);
+        }
+
+        let content = handle_promise(&mut self.import_promise, |r| match r {
+            Ok(content) => content.clone(),
+            Err(e) => {
+                error!("cannot import edges: {}", e);
+                None
             }
         });
+        if let Some(content) = content.flatten() {
+            let before = self.pinboard.graph.clone();
+            let unmatched = import::import_edges_csv(&mut self.pinboard.graph, &content);
+            if self.pinboard.graph.edge_count() != before.edge_count() {
+                self.push_history(before);
+                self.unsaved = true;
+            }
+            if !unmatched.is_empty() {
+                warn!("{} rows couldn't be imported", unmatched.len());
+                self.unmatched_rows = Some(unmatched);
+            }
+        }
 
         handle_promise(&mut self.update_blob_promise, |(either, b)| match b {
-            Ok(blob) => {
+            Ok(None) => info!("user didn't select file"),
+            Ok(Some(blob)) => {
                 Self::handle_update_blob_to_node(
                     &mut self.unsaved,
                     &mut self.pinboard.graph,
@@ -468,28 +4483,53 @@ impl PinboardBuffer {
             }
             Err(e) => {
                 error!("cannot open blob: {}", e);
+                if let Some(m) = e.downcast_ref::<MultipleMatches>() {
+                    self.ambiguous_match = Some((*either, m.0.clone()));
+                }
             }
         });
 
-        handle_promise(
-            &mut self.update_blob_and_open_promise,
-            |(either, b)| match b {
-                Ok(blob) => {
-                    Self::handle_update_blob_to_node(
-                        &mut self.unsaved,
-                        &mut self.pinboard.graph,
-                        either,
-                        blob,
-                    );
-                    Some(blob.clone())
-                }
-                Err(e) => {
-                    error!("cannot update blob: {}", e);
-                    None
-                }
-            },
-        )
-        .flatten()
+        let dropped = handle_promise(&mut self.drop_promise, |(pos, results)| {
+            let blobs: Vec<_> = results
+                .iter()
+                .filter_map(|b| match b {
+                    Ok(blob) => Some(blob.clone()),
+                    Err(e) => {
+                        error!("cannot add dropped file: {}", e);
+                        None
+                    }
+                })
+                .collect();
+            (*pos, blobs)
+        });
+        if let Some((pos, blobs)) = dropped {
+            self.add_dropped_nodes(pos, blobs);
+        }
+
+        handle_promise(&mut self.update_blob_and_open_promise, |results| {
+            results
+                .iter()
+                .filter_map(|(either, b)| match b {
+                    Ok(blob) => {
+                        Self::handle_update_blob_to_node(
+                            &mut self.unsaved,
+                            &mut self.pinboard.graph,
+                            either,
+                            blob,
+                        );
+                        Some(blob.clone())
+                    }
+                    Err(e) => {
+                        error!("cannot update blob: {}", e);
+                        if let Some(m) = e.downcast_ref::<MultipleMatches>() {
+                            self.ambiguous_match = Some((*either, m.0.clone()));
+                        }
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
     }
 
     // Borrow checker is too dumb to infer across function call that we are mutably borrowing
@@ -500,32 +4540,717 @@ impl PinboardBuffer {
         either: &Either,
         blob: &Blob,
     ) {
-        let filename = blob
-            .path()
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let filename = blob.name();
         match either {
             Either::Edge(id) => {
                 graph.edge_mut(*id).map(|e| {
                     if let Some(b) = &e.payload().comment {
                         *unsaved = *unsaved || (b != blob);
                     }
+                    if e.payload().comment.as_ref() != Some(blob) {
+                        e.payload_mut().touch();
+                    }
                     e.payload_mut().comment = Some(blob.clone());
                     e.set_label(filename);
                 });
             }
             Either::Node(id) => {
                 graph.node_mut(*id).map(|n| {
-                    if let Some(b) = &n.payload() {
+                    if let Some(b) = &n.payload().blob {
                         *unsaved = *unsaved || (b != blob);
                     }
-                    *n.payload_mut() = Some(blob.clone());
-                    n.set_label(filename);
+                    if n.payload().blob.as_ref() != Some(blob) {
+                        n.payload_mut().touch();
+                    }
+                    n.payload_mut().blob = Some(blob.clone());
+                    let label = n.payload().label_or(filename);
+                    n.set_label(label);
                 });
             }
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_settings_defaults_omitted() {
+        assert_eq!(
+            serde_json::to_string(&ViewSettings::default()).unwrap(),
+            "{}"
+        );
+        let view = ViewSettings {
+            show_grid: true,
+            grid_spacing: 40.0,
+            ..Default::default()
+        };
+        let s = serde_json::to_string(&view).unwrap();
+        assert_eq!(s, r#"{"show_grid":true,"grid_spacing":40.0}"#);
+        let view: ViewSettings = serde_json::from_str(&s).unwrap();
+        assert_eq!(view.grid_spacing, 40.0);
+        assert_eq!(view.max_zoom, ViewSettings::default().max_zoom);
+    }
+
+    #[tokio::test]
+    async fn node_positions_persist() {
+        let mut pinboard = Pinboard::new(
+            "Positions".to_string(),
+            PinboardGraph::from(&StableGraph::default()),
+        );
+        let a = pinboard
+            .graph
+            .add_node_with_location(NodeData::default(), Pos2::new(12.5, -40.0));
+        let b = pinboard
+            .graph
+            .add_node_with_location(NodeData::default(), Pos2::new(-300.0, 7.25));
+        // Moving a node after its creation should be saved as well
+        pinboard
+            .graph
+            .node_mut(b)
+            .unwrap()
+            .set_location(Pos2::new(100.0, 200.0));
+
+        let path = std::env::temp_dir().join(format!("{}.pinbrd", pinboard.get_uuid()));
+        PinboardBuffer::save_to_path(pinboard, path.clone())
+            .await
+            .unwrap();
+        let loaded = Pinboard::from_bytes(&tokio::fs::read(&path).await.unwrap()).unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(
+            loaded.graph.node(a).unwrap().location(),
+            Pos2::new(12.5, -40.0)
+        );
+        assert_eq!(
+            loaded.graph.node(b).unwrap().location(),
+            Pos2::new(100.0, 200.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_save_leaves_no_file() {
+        let pinboard = Pinboard::default();
+        let dir = std::env::temp_dir().join(format!("missing-{}", pinboard.get_uuid()));
+        let path = dir.join("board.pinbrd");
+        assert!(PinboardBuffer::save_to_path(pinboard, path.clone())
+            .await
+            .is_err());
+        assert!(!path.exists());
+        assert!(!dir.join(".board.pinbrd.tmp").exists());
+    }
+
+    #[test]
+    fn compressed_roundtrip() {
+        let mut pinboard = Pinboard::new(
+            "Compressed".to_string(),
+            PinboardGraph::from(&StableGraph::default()),
+        );
+        pinboard.compress = true;
+        let bytes = pinboard.to_bytes().unwrap();
+        assert!(bytes.starts_with(&GZIP_MAGIC));
+        let loaded = Pinboard::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.title, "Compressed");
+        assert!(loaded.compress);
+
+        pinboard.compress = false;
+        let loaded = Pinboard::from_bytes(&pinboard.to_bytes().unwrap()).unwrap();
+        assert!(!loaded.compress);
+    }
+
+    #[test]
+    fn minimal_serialization() {
+        let mut pinboard = Pinboard::new(
+            "Minimal".to_string(),
+            PinboardGraph::from(&StableGraph::default()),
+        );
+        let a = pinboard.graph.add_node(NodeData::default());
+        let b = pinboard.graph.add_node(NodeData::default());
+        pinboard.graph.add_edge(
+            a,
+            b,
+            Conn {
+                comment: None,
+                relation: Relation::Related,
+                arrow: Arrow::None,
+                modified: None,
+            },
+        );
+        let json = String::from_utf8(pinboard.to_bytes().unwrap()).unwrap();
+        for key in [
+            "compress",
+            "check_git",
+            "nvim",
+            "author",
+            "aliases",
+            "tags",
+            "card",
+            "comment",
+            "arrow",
+            "modified",
+        ] {
+            assert!(!json.contains(&format!("\"{key}\"")), "{key} in {json}");
+        }
+
+        let loaded = Pinboard::from_bytes(json.as_bytes()).unwrap();
+        assert_eq!(loaded.title, "Minimal");
+        assert!(!loaded.compress);
+        assert_eq!(loaded.graph.node_count(), 2);
+        let (_, conn) = loaded.graph.edges_iter().next().unwrap();
+        let conn = conn.payload();
+        assert!(conn.comment.is_none() && conn.arrow.is_none());
+        let node = loaded.graph.node(a).unwrap().payload();
+        assert!(node.blob.is_none() && node.aliases.is_empty() && node.tags.is_empty());
+    }
+
+    #[test]
+    fn select_all_nodes() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        // Nothing to select on an empty board
+        buf.select_all();
+        assert_eq!(buf.pinboard.graph.node_count(), 0);
+
+        let a = buf.pinboard.graph.add_node(NodeData::default());
+        let b = buf.pinboard.graph.add_node(NodeData::default());
+        buf.select_all();
+        for n in [a, b] {
+            assert!(buf.pinboard.graph.node(n).unwrap().selected());
+        }
+        buf.select_only(&[]);
+        for n in [a, b] {
+            assert!(!buf.pinboard.graph.node(n).unwrap().selected());
+        }
+    }
+
+    #[tokio::test]
+    async fn dropped_files() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let blob = PinboardBuffer::blob_of_path(PathBuf::from("./tests/misc/foo.txt"))
+            .await
+            .unwrap();
+        buf.add_dropped_nodes(Pos2::new(10.0, 20.0), vec![blob.clone(), blob]);
+        assert_eq!(buf.pinboard.graph.node_count(), 2);
+        let locations: Vec<_> = buf
+            .pinboard
+            .graph
+            .nodes_iter()
+            .map(|(_, n)| n.location())
+            .collect();
+        assert!(locations.contains(&Pos2::new(10.0, 20.0)));
+        assert!(buf.unsaved);
+        // Adding the nodes is undone at once
+        buf.undo();
+        assert_eq!(buf.pinboard.graph.node_count(), 0);
+    }
+
+    #[test]
+    fn clipboard() {
+        let mut source = PinboardBuffer::new(Pinboard::default(), None, false);
+        let graph = &mut source.pinboard.graph;
+        let a = graph.add_node_with_location(NodeData::new(), Pos2::new(0.0, 0.0));
+        let b = graph.add_node_with_location(NodeData::new(), Pos2::new(20.0, 0.0));
+        let c = graph.add_node_with_location(NodeData::new(), Pos2::new(0.0, 50.0));
+        graph.add_edge(a, b, Conn::new(Relation::Insight));
+        graph.add_edge(b, c, Conn::new(Relation::Related));
+        source.select_only(&[a, b]);
+        let copied = source.cut_selection().unwrap();
+        // Only the edge among the copied nodes comes along
+        assert_eq!(copied.nodes.len(), 2);
+        assert_eq!(copied.edges.len(), 1);
+        assert_eq!(source.pinboard.graph.node_count(), 1);
+
+        let mut target = PinboardBuffer::new(Pinboard::default(), None, false);
+        target.paste(&copied, Pos2::new(100.0, 100.0));
+        let graph = &target.pinboard.graph;
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.edge_count(), 1);
+        let mut locations: Vec<_> = graph.nodes_iter().map(|(_, n)| n.location()).collect();
+        locations.sort_by(|p, q| p.x.total_cmp(&q.x));
+        assert_eq!(locations, [Pos2::new(90.0, 100.0), Pos2::new(110.0, 100.0)]);
+        assert_eq!(graph.selected_nodes().len(), 2);
+        // Pasting is undone at once
+        target.undo();
+        assert_eq!(target.pinboard.graph.node_count(), 0);
+    }
+
+    #[test]
+    fn clipboard_events() {
+        let ctx = Context::default();
+        let input = |events| egui::RawInput {
+            events,
+            ..Default::default()
+        };
+        let mut clipboard = None;
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let a = buf.pinboard.graph.add_node(NodeData::new());
+        buf.pinboard
+            .graph
+            .node_mut(a)
+            .unwrap()
+            .set_label("paper".to_string());
+        buf.select_only(&[a]);
+
+        let output = ctx.run(input(vec![egui::Event::Copy]), |ctx| {
+            buf.handle_clipboard_events(ctx, &mut clipboard, Pos2::ZERO)
+        });
+        // Copying fills the OS clipboard, so that the paste shortcut arrives as an event
+        let text = output.platform_output.copied_text;
+        assert_eq!(text, "paper");
+        let _ = ctx.run(input(vec![egui::Event::Paste(text)]), |ctx| {
+            buf.handle_clipboard_events(ctx, &mut clipboard, Pos2::ZERO)
+        });
+        assert_eq!(buf.pinboard.graph.node_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn sub_board_extraction() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let graph = &mut buf.pinboard.graph;
+        let a = graph.add_node_with_location(NodeData::new(), Pos2::new(0.0, 0.0));
+        let b = graph.add_node_with_location(NodeData::new(), Pos2::new(20.0, 20.0));
+        let c = graph.add_node_with_location(NodeData::new(), Pos2::new(100.0, 0.0));
+        graph.add_edge(a, b, Conn::new(Relation::Related));
+        graph.add_edge(a, c, Conn::new(Relation::Insight));
+        graph.add_edge(b, c, Conn::new(Relation::Insight));
+        graph.add_edge(b, c, Conn::new(Relation::Conflict));
+        let blob = Blob::new(
+            BlobType::PinboardGraph,
+            PathBuf::from("./tests/misc/foo.txt"),
+        )
+        .await
+        .unwrap();
+        let copied = buf.copy_nodes(&[a, b]).unwrap();
+        // Nodes edited while picking the file are kept
+        buf.rename_node(a, "Edited");
+        buf.replace_with_sub_board(&[a, b], &copied, blob.clone());
+        assert_eq!(buf.pinboard.graph.node_count(), 3);
+        buf.undo();
+        buf.replace_with_sub_board(&[a, b], &copied, blob);
+
+        let graph = &buf.pinboard.graph;
+        assert_eq!(graph.node_count(), 2);
+        // Edges to the remaining node collapse into one per relation
+        assert_eq!(graph.edge_count(), 2);
+        let sub = graph.selected_nodes()[0];
+        let node = graph.node(sub).unwrap();
+        assert_eq!(node.location(), Pos2::new(10.0, 10.0));
+        assert!(*node.payload().blob.as_ref().unwrap().ty() == BlobType::PinboardGraph);
+        assert!(graph.g.find_edge(sub, c).is_some());
+        buf.undo();
+        assert_eq!(buf.pinboard.graph.node_count(), 3);
+    }
+
+    #[test]
+    fn custom_node_labels() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let node = buf.pinboard.graph.add_node(NodeData {
+            blob: Some(Blob::note("Idea\nmore".to_string())),
+            ..NodeData::new()
+        });
+        let label = |buf: &PinboardBuffer| buf.pinboard.graph.node(node).unwrap().label();
+        buf.rename_node(node, " Key insight ");
+        assert_eq!(label(&buf), "Key insight");
+        // Changing the content keeps the custom label
+        let blob = Blob::note("Other".to_string());
+        PinboardBuffer::handle_update_blob_to_node(
+            &mut buf.unsaved,
+            &mut buf.pinboard.graph,
+            &Either::Node(node),
+            &blob,
+        );
+        assert_eq!(label(&buf), "Key insight");
+        // An empty label goes back to the name of the content
+        buf.rename_node(node, "");
+        assert_eq!(buf.pinboard.graph.node(node).unwrap().payload().label, None);
+        assert_eq!(label(&buf), blob.name());
+    }
+
+    #[tokio::test]
+    async fn repair_single_problems() {
+        let file = Blob::new(BlobType::File, PathBuf::from("./tests/misc/foo.txt"))
+            .await
+            .unwrap();
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let mut nodes = Vec::new();
+        for path in ["gone/a.txt", "gone/b.txt", "gone/c.txt"] {
+            let mut blob = file.clone();
+            blob.set_path(PathBuf::from(path));
+            nodes.push(buf.pinboard.graph.add_node(NodeData {
+                blob: Some(blob),
+                ..NodeData::new()
+            }));
+        }
+        let path = |buf: &PinboardBuffer, n| {
+            let blob = buf.pinboard.graph.node(n).unwrap().payload().blob.clone();
+            blob.unwrap().path().clone()
+        };
+        buf.health = Some(HealthReport {
+            repairable: vec![
+                ("gone/a.txt".into(), "found/a.txt".into()),
+                ("gone/b.txt".into(), "found/b.txt".into()),
+            ],
+            lost: vec!["gone/c.txt".into()],
+            ..Default::default()
+        });
+
+        // Only the chosen blob is repaired
+        buf.apply_repairs(Some(Path::new("gone/a.txt")));
+        assert_eq!(path(&buf, nodes[0]), PathBuf::from("found/a.txt"));
+        assert_eq!(path(&buf, nodes[1]), PathBuf::from("gone/b.txt"));
+        assert_eq!(buf.health.as_ref().unwrap().repairable.len(), 1);
+
+        buf.apply_relocation(
+            Path::new("gone/c.txt"),
+            PathBuf::from("./tests/misc/foo.txt"),
+        );
+        assert_eq!(path(&buf, nodes[2]), PathBuf::from("./tests/misc/foo.txt"));
+        assert!(buf.health.as_ref().unwrap().lost.is_empty());
+
+        buf.focus_path(Path::new("gone/b.txt"));
+        assert!(buf.pinboard.graph.node(nodes[1]).unwrap().selected());
+        assert!(!buf.pinboard.graph.node(nodes[0]).unwrap().selected());
+        // Both are undone one at a time
+        assert_eq!(buf.undo_stack.len(), 2);
+        buf.undo();
+        assert_eq!(path(&buf, nodes[2]), PathBuf::from("gone/c.txt"));
+        buf.undo();
+        assert_eq!(path(&buf, nodes[0]), PathBuf::from("gone/a.txt"));
+    }
+
+    #[tokio::test]
+    async fn paths_relative_to_file() {
+        let file = Blob::new(BlobType::File, PathBuf::from("./tests/misc/foo.txt"))
+            .await
+            .unwrap();
+        let blob = |path: &str| {
+            let mut blob = file.clone();
+            blob.set_path(PathBuf::from(path));
+            blob
+        };
+        let mut pinboard = Pinboard::default();
+        let a = pinboard.graph.add_node(NodeData {
+            blob: Some(blob("/project/notes/a.md")),
+            ..NodeData::new()
+        });
+        let b = pinboard.graph.add_node(NodeData {
+            blob: Some(blob("/elsewhere/b.md")),
+            ..NodeData::new()
+        });
+        let path = |pinboard: &Pinboard, n| {
+            let blob = pinboard.graph.node(n).unwrap().payload().blob.clone();
+            blob.unwrap().path().clone()
+        };
+        pinboard.relativize_paths(Path::new("/project/boards/board.pinbrd"));
+        assert_eq!(path(&pinboard, a), PathBuf::from("../notes/a.md"));
+        assert_eq!(path(&pinboard, b), PathBuf::from("/elsewhere/b.md"));
+
+        // The project moved along with the pinboard
+        let mut loaded = Pinboard::from_bytes(&pinboard.to_bytes().unwrap()).unwrap();
+        loaded
+            .resolve_paths(Path::new("/moved/boards/board.pinbrd"))
+            .unwrap();
+        assert!(!loaded.relative_paths);
+        assert_eq!(path(&loaded, a), PathBuf::from("/moved/notes/a.md"));
+
+        // Older files are relative to the working directory
+        let mut old = Pinboard::default();
+        let c = old.graph.add_node(NodeData {
+            blob: Some(blob("notes/c.md")),
+            ..NodeData::new()
+        });
+        old.resolve_paths(Path::new("/project/boards/board.pinbrd"))
+            .unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(path(&old, c), cwd.join("notes/c.md"));
+
+        // Paths under a chosen base are relative to it, which moves along with the pinboard
+        let mut buf = PinboardBuffer::new(loaded, None, false);
+        let outside = buf.rebase_paths(Path::new("/moved/notes"));
+        assert_eq!(outside, vec![PathBuf::from("/elsewhere/b.md")]);
+        assert!(buf.unsaved);
+        let mut saved = buf.pinboard.clone();
+        saved.relativize_paths(Path::new("/moved/boards/board.pinbrd"));
+        assert_eq!(path(&saved, a), PathBuf::from("a.md"));
+        assert_eq!(path(&saved, b), PathBuf::from("/elsewhere/b.md"));
+        assert_eq!(saved.path_base, Some(PathBuf::from("../notes")));
+        let mut loaded = Pinboard::from_bytes(&saved.to_bytes().unwrap()).unwrap();
+        loaded
+            .resolve_paths(Path::new("/again/boards/board.pinbrd"))
+            .unwrap();
+        assert_eq!(path(&loaded, a), PathBuf::from("/again/notes/a.md"));
+        assert_eq!(loaded.path_base, Some(PathBuf::from("/again/notes")));
+    }
+
+    #[test]
+    fn custom_relations() {
+        let mut pinboard = Pinboard::default();
+        let kind = RelationKind::new("Cites".to_string(), Color32::LIGHT_BLUE);
+        pinboard.relation_kinds.push(kind.clone());
+        assert_eq!(pinboard.relations().len(), Relation::ALL.len() + 1);
+        let a = pinboard.graph.add_node(NodeData::default());
+        let b = pinboard.graph.add_node(NodeData::default());
+        let relation = Relation::Custom(kind.clone());
+        let e =
+            pinboard
+                .graph
+                .add_edge_with_label(a, b, Conn::new(relation.clone()), relation.label());
+
+        let loaded = Pinboard::from_bytes(&pinboard.to_bytes().unwrap()).unwrap();
+        assert!(loaded.relation_kinds == pinboard.relation_kinds);
+        assert!(loaded.graph.edge(e).unwrap().payload().relation == relation);
+
+        let renamed = RelationKind {
+            name: "Quotes".to_string(),
+            ..kind.clone()
+        };
+        pinboard.replace_relation_kind(&kind, Some(renamed.clone()));
+        let edge = pinboard.graph.edge(e).unwrap();
+        assert!(edge.payload().relation == Relation::Custom(renamed.clone()));
+        assert_eq!(edge.label(), "Quotes");
+        pinboard.replace_relation_kind(&renamed, None);
+        assert!(pinboard.graph.edge(e).unwrap().payload().relation == Relation::Related);
+
+        // Identical kinds stay apart
+        let twin = RelationKind::new("Cites".to_string(), Color32::LIGHT_BLUE);
+        let f = pinboard.graph.add_edge_with_label(
+            b,
+            a,
+            Conn::new(Relation::Custom(twin.clone())),
+            twin.name.clone(),
+        );
+        pinboard.replace_relation_kind(&kind, None);
+        assert!(pinboard.graph.edge(f).unwrap().payload().relation == Relation::Custom(twin));
+    }
+
+    #[test]
+    fn relation_kinds_without_ids() {
+        let mut pinboard = Pinboard::default();
+        let mut kind = RelationKind::new("Cites".to_string(), Color32::LIGHT_BLUE);
+        kind.id = Uuid::nil();
+        pinboard.relation_kinds.push(kind.clone());
+        let a = pinboard.graph.add_node(NodeData::default());
+        let b = pinboard.graph.add_node(NodeData::default());
+        let e = pinboard.graph.add_edge_with_label(
+            a,
+            b,
+            Conn::new(Relation::Custom(kind)),
+            "Cites".to_string(),
+        );
+        let loaded = Pinboard::from_bytes(&pinboard.to_bytes().unwrap()).unwrap();
+        let kind = &loaded.relation_kinds[0];
+        assert!(!kind.id.is_nil());
+        assert!(loaded.graph.edge(e).unwrap().payload().relation == Relation::Custom(kind.clone()));
+    }
+
+    #[test]
+    fn undo_relation_kinds() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        buf.checkpoint();
+        buf.pinboard
+            .relation_kinds
+            .push(RelationKind::new("Cites".to_string(), Color32::LIGHT_BLUE));
+        buf.undo();
+        assert!(buf.pinboard.relation_kinds.is_empty());
+        buf.redo();
+        assert_eq!(buf.pinboard.relation_kinds.len(), 1);
+    }
+
+    #[test]
+    fn tag_filter() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let a = buf.pinboard.graph.add_node(NodeData {
+            tags: vec!["proof".to_string()],
+            ..NodeData::default()
+        });
+        let b = buf.pinboard.graph.add_node(NodeData::default());
+        let visibility =
+            |buf: &PinboardBuffer, n| buf.pinboard.graph.node(n).unwrap().payload().visibility;
+        buf.apply_tag_filter();
+        assert_eq!(visibility(&buf, b), Visibility::Shown);
+
+        buf.tag_filter.insert("proof".to_string());
+        buf.apply_tag_filter();
+        assert_eq!(visibility(&buf, a), Visibility::Shown);
+        assert_eq!(visibility(&buf, b), Visibility::Dimmed);
+        buf.hide_filtered = true;
+        buf.apply_tag_filter();
+        assert_eq!(visibility(&buf, b), Visibility::Hidden);
+    }
+
+    #[test]
+    fn last_relation_is_remembered() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let a = buf.pinboard.graph.add_node(NodeData::default());
+        let b = buf.pinboard.graph.add_node(NodeData::default());
+        let c = buf.pinboard.graph.add_node(NodeData::default());
+        assert!(buf.last_relation == Relation::Related);
+        buf.apply_relation(RelationTarget::Connect(a, b), Relation::Insight);
+        assert!(buf.last_relation == Relation::Insight);
+        // Changing the relation of existing edges isn't creating one
+        let edges: Vec<_> = buf.pinboard.graph.edges_iter().map(|(e, _)| e).collect();
+        buf.apply_relation(RelationTarget::Edges(edges), Relation::Conflict);
+        assert!(buf.last_relation == Relation::Insight);
+        buf.apply_relation(RelationTarget::Connect(b, c), buf.last_relation.clone());
+        assert_eq!(buf.pinboard.graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn focus_animation_endpoints() {
+        let anim = FocusAnimation {
+            target: FocusTarget::Node(NodeIndex::new(0)),
+            from: Pos2::new(-100.0, 50.0),
+            from_zoom: 0.5,
+            start: 0.0,
+        };
+        let to = Pos2::new(300.0, 10.0);
+        assert_eq!(anim.at(0.0, to, 1.0), (anim.from, 0.5));
+        assert_eq!(anim.at(1.0, to, 1.0), (to, 1.0));
+        // Overshooting frames stay at the target
+        assert_eq!(anim.at(1.5, to, 1.0), (to, 1.0));
+        let (mid, zoom) = anim.at(0.5, to, 1.0);
+        assert_eq!(mid, Pos2::new(100.0, 30.0));
+        assert!((zoom - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fit_frames_nodes() {
+        assert_eq!(fit_frame([], Vec2::new(800.0, 600.0)), None);
+        let points = [Pos2::new(-100.0, 0.0), Pos2::new(300.0, 100.0)];
+        let rects = points.map(|p| Rect::from_min_max(p, p));
+        let (center, zoom) = fit_frame(rects, Vec2::new(800.0, 600.0)).unwrap();
+        assert_eq!(center, Pos2::new(100.0, 50.0));
+        // The wider side limits the zoom, margins included
+        assert!((zoom - 800.0 / (400.0 + 2.0 * FIT_MARGIN)).abs() < 1e-6);
+
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let a = buf.pinboard.graph.add_node(NodeData::default());
+        buf.fit_selection();
+        assert_eq!(buf.focus, None);
+        buf.select_only(&[a]);
+        buf.fit_selection();
+        assert_eq!(buf.focus, Some(FocusTarget::Frame(vec![a])));
+
+        // Cards are framed as a whole, hidden nodes are left out
+        let card = buf.pinboard.graph.add_node(NodeData {
+            blob: Some(Blob::note("Idea".to_string())),
+            card: true,
+            ..NodeData::default()
+        });
+        buf.pinboard
+            .graph
+            .node_mut(a)
+            .unwrap()
+            .payload_mut()
+            .visibility = Visibility::Hidden;
+        buf.fit();
+        let target = buf.focus.take().unwrap();
+        assert_eq!(target, FocusTarget::Frame(vec![card]));
+        let view = Vec2::new(800.0, 600.0);
+        let (_, zoom) = buf.target_view(&target, view, 1.0).unwrap();
+        assert!(zoom <= 800.0 / 160.0);
+    }
+
+    #[test]
+    fn undo_redo_edits() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        let a = buf.pinboard.graph.add_node(NodeData::default());
+        let b = buf.pinboard.graph.add_node(NodeData::default());
+        buf.apply_relation(RelationTarget::Connect(a, b), Relation::Insight);
+        buf.delete_nodes(&[b]);
+        assert_eq!(buf.pinboard.graph.node_count(), 1);
+
+        buf.undo();
+        assert_eq!(buf.pinboard.graph.node_count(), 2);
+        assert_eq!(buf.pinboard.graph.edge_count(), 1);
+        buf.undo();
+        assert_eq!(buf.pinboard.graph.edge_count(), 0);
+        // Nothing left to undo
+        buf.undo();
+        assert_eq!(buf.pinboard.graph.node_count(), 2);
+
+        buf.redo();
+        assert_eq!(buf.pinboard.graph.edge_count(), 1);
+        // A new edit drops the undone ones
+        buf.record_move(vec![(a, Pos2::new(1.0, 2.0))]);
+        buf.redo();
+        assert_eq!(buf.pinboard.graph.node_count(), 2);
+        buf.undo();
+        assert_eq!(
+            buf.pinboard.graph.node(a).unwrap().location(),
+            Pos2::new(1.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn link_nodes() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        buf.add_link_node(LinkDraft {
+            pos: Some(Pos2::new(5.0, 5.0)),
+            link: " https://example.com ".to_string(),
+            title: String::new(),
+        });
+        let (_, node) = buf.pinboard.graph.nodes_iter().next().unwrap();
+        assert_eq!(node.label(), "https://example.com");
+        assert_eq!(node.location(), Pos2::new(5.0, 5.0));
+        let blob = node.payload().blob.as_ref().unwrap();
+        assert!(*blob.ty() == BlobType::Url);
+        assert!(buf.unsaved);
+        // Links are neither bundled nor rewritten
+        assert!(buf.rebase_paths(Path::new("/")).is_empty());
+    }
+
+    #[test]
+    fn note_nodes() {
+        let mut buf = PinboardBuffer::new(Pinboard::default(), None, false);
+        buf.add_note_node(None);
+        let (id, node) = buf.pinboard.graph.nodes_iter().next().unwrap();
+        assert_eq!(node.label(), "Note");
+        assert_eq!(buf.editing_note, Some(id));
+        // Notes live in the pinboard file
+        let loaded = Pinboard::from_bytes(&buf.pinboard.to_bytes().unwrap()).unwrap();
+        let blob = loaded
+            .graph
+            .node(id)
+            .unwrap()
+            .payload()
+            .blob
+            .clone()
+            .unwrap();
+        assert_eq!(blob.note_text(), Some(""));
+    }
+
+    #[test]
+    fn deleting_node_removes_edges() {
+        let mut buf = PinboardBuffer::new(
+            Pinboard::new(
+                "Delete".to_string(),
+                PinboardGraph::from(&StableGraph::default()),
+            ),
+            None,
+            false,
+        );
+        let graph = &mut buf.pinboard.graph;
+        let a = graph.add_node(NodeData::default());
+        let b = graph.add_node(NodeData::default());
+        let c = graph.add_node(NodeData::default());
+        let ab = graph.add_edge(a, b, Conn::new(Relation::Related));
+        graph.add_edge(b, c, Conn::new(Relation::Insight));
+        let ac = graph.add_edge(a, c, Conn::new(Relation::Conflict));
+        graph.set_selected_nodes(vec![b]);
+        graph.set_selected_edges(vec![ab, ac]);
+
+        buf.delete_nodes(&[b]);
+
+        let graph = &buf.pinboard.graph;
+        assert!(buf.unsaved);
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.edge_count(), 1);
+        for (id, _) in graph.edges_iter() {
+            let (start, end) = graph.edge_endpoints(id).unwrap();
+            assert!(graph.node(start).is_some() && graph.node(end).is_some());
+        }
+        assert!(graph.selected_nodes().is_empty());
+        assert_eq!(graph.selected_edges(), &[ac]);
+    }
+}
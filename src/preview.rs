@@ -0,0 +1,105 @@
+// This module contains the preview pane rendering the markdown file of the selected node.
+
+use egui::Ui;
+use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use log::warn;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+// Interval between checking the previewed file for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+const EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| EXTENSIONS.iter().any(|m| e.eq_ignore_ascii_case(m)))
+}
+
+// Content of the previewed file, reloaded when its modification time changes
+#[derive(Default)]
+pub struct MarkdownPreview {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    content: String,
+    // Whether reading the file failed, it is read again only once it has been modified
+    failed: bool,
+    last_check: Option<Instant>,
+    cache: CommonMarkCache,
+}
+
+impl MarkdownPreview {
+    // Markdown files are small enough to be read on the UI thread
+    fn reload(&mut self, path: &Path) {
+        self.last_check = Some(Instant::now());
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if self.path.as_deref() == Some(path)
+            && modified == self.modified
+            && (modified.is_some() || self.failed)
+        {
+            return;
+        }
+        (self.content, self.failed) = match std::fs::read_to_string(path) {
+            Ok(content) => (content, false),
+            Err(e) => {
+                warn!("cannot read {:?} for preview: {}", path, e);
+                (String::new(), true)
+            }
+        };
+        self.path = Some(path.to_path_buf());
+        self.modified = modified;
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, path: Option<&Path>) {
+        let Some(path) = path else {
+            ui.weak("Select a node of a markdown file to preview it");
+            return;
+        };
+        let due = self
+            .last_check
+            .is_none_or(|t| t.elapsed() >= RELOAD_INTERVAL);
+        if self.path.as_deref() != Some(path) || due {
+            self.reload(path);
+        }
+        ui.ctx().request_repaint_after(RELOAD_INTERVAL);
+        ui.strong(
+            path.file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )
+        .on_hover_text(path.display().to_string());
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            CommonMarkViewer::new().show(ui, &mut self.cache, &self.content);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_extensions() {
+        assert!(is_markdown(Path::new("notes/idea.md")));
+        assert!(is_markdown(Path::new("README.MARKDOWN")));
+        assert!(!is_markdown(Path::new("paper.pdf")));
+        assert!(!is_markdown(Path::new("md")));
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = std::env::temp_dir().join(format!("pinlab_preview_{}.md", uuid::Uuid::new_v4()));
+        let mut preview = MarkdownPreview::default();
+        preview.reload(&path);
+        assert!(preview.failed);
+        // Once the file shows up, it is read
+        std::fs::write(&path, "# Title").unwrap();
+        preview.reload(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(!preview.failed);
+        assert_eq!(preview.content, "# Title");
+    }
+}
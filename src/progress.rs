@@ -0,0 +1,120 @@
+// This module contains the understanding progress computed from relations of edges.
+
+use crate::graph::{PinboardGraph, Relation};
+use petgraph::{unionfind::UnionFind, visit::NodeIndexable};
+use std::collections::HashMap;
+
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct RelationCounts {
+    pub related: usize,
+    pub insight: usize,
+    pub progress: usize,
+    pub conflict: usize,
+}
+
+impl RelationCounts {
+    fn add(&mut self, relation: &Relation) {
+        match relation {
+            Relation::Related => self.related += 1,
+            Relation::Insight => self.insight += 1,
+            Relation::Progress => self.progress += 1,
+            Relation::Conflict => self.conflict += 1,
+            // User-defined relations say nothing about progress
+            Relation::Custom(_) => self.related += 1,
+        }
+    }
+
+    // Share of progress among progress and conflicts, none if there are neither
+    pub fn resolved(&self) -> Option<f32> {
+        let total = self.progress + self.conflict;
+        (total > 0).then(|| self.progress as f32 / total as f32)
+    }
+}
+
+// Connected part of the graph
+pub struct Cluster {
+    // Label of the alphabetically first node
+    pub name: String,
+    pub size: usize,
+    pub counts: RelationCounts,
+}
+
+pub fn overall(graph: &PinboardGraph) -> RelationCounts {
+    let mut counts = RelationCounts::default();
+    for (_, e) in graph.edges_iter() {
+        counts.add(&e.payload().relation);
+    }
+    counts
+}
+
+// Clusters having at least one edge, largest first
+pub fn clusters(graph: &PinboardGraph) -> Vec<Cluster> {
+    let mut sets = UnionFind::new(graph.g.node_bound());
+    for (id, _) in graph.edges_iter() {
+        let (a, b) = graph.edge_endpoints(id).unwrap();
+        sets.union(a.index(), b.index());
+    }
+    let mut clusters: HashMap<usize, Cluster> = HashMap::new();
+    for (id, e) in graph.edges_iter() {
+        let (a, _) = graph.edge_endpoints(id).unwrap();
+        clusters
+            .entry(sets.find(a.index()))
+            .or_insert_with(|| Cluster {
+                name: String::new(),
+                size: 0,
+                counts: RelationCounts::default(),
+            })
+            .counts
+            .add(&e.payload().relation);
+    }
+    for (id, n) in graph.nodes_iter() {
+        if let Some(c) = clusters.get_mut(&sets.find(id.index())) {
+            c.size += 1;
+            if c.name.is_empty() || n.label() < c.name {
+                c.name = n.label();
+            }
+        }
+    }
+    let mut res: Vec<Cluster> = clusters.into_values().collect();
+    res.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Conn, NodeData};
+    use petgraph::stable_graph::StableGraph;
+
+    #[test]
+    fn cluster_progress() {
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        let a = graph.add_node_with_label(NodeData::default(), "a".to_string());
+        let b = graph.add_node_with_label(NodeData::default(), "b".to_string());
+        let c = graph.add_node_with_label(NodeData::default(), "c".to_string());
+        let d = graph.add_node_with_label(NodeData::default(), "d".to_string());
+        let e = graph.add_node_with_label(NodeData::default(), "e".to_string());
+        graph.add_node_with_label(NodeData::default(), "isolated".to_string());
+        graph.add_edge(b, a, Conn::new(Relation::Progress));
+        graph.add_edge(b, c, Conn::new(Relation::Progress));
+        graph.add_edge(a, c, Conn::new(Relation::Conflict));
+        graph.add_edge(d, e, Conn::new(Relation::Related));
+
+        assert_eq!(
+            overall(&graph),
+            RelationCounts {
+                related: 1,
+                insight: 0,
+                progress: 2,
+                conflict: 1,
+            }
+        );
+        let clusters = clusters(&graph);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].name, "a");
+        assert_eq!(clusters[0].size, 3);
+        assert_eq!(clusters[0].counts.resolved(), Some(2.0 / 3.0));
+        assert_eq!(clusters[1].name, "d");
+        assert_eq!(clusters[1].counts.resolved(), None);
+    }
+}
@@ -0,0 +1,62 @@
+// This module contains the crash recovery of pinboards with unsaved changes.
+
+use crate::pinboard::Pinboard;
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+// A pinboard written to the recovery directory
+#[derive(Serialize, Deserialize)]
+pub struct Recovered {
+    // path of the pinboard file, none if it has never been saved
+    pub path: Option<PathBuf>,
+    pub pinboard: Pinboard,
+}
+
+// Directory storing the recovery files, one per pinboard named after its UUID
+fn dir() -> Result<PathBuf> {
+    eframe::storage_dir("Pinlab")
+        .map(|d| d.join("recovery"))
+        .ok_or(anyhow!("cannot determine the recovery directory"))
+}
+
+fn file(uuid: &Uuid) -> Result<PathBuf> {
+    Ok(dir()?.join(format!("{}.pinbrd", uuid)))
+}
+
+pub async fn write(recovered: Recovered) -> Result<()> {
+    let path = file(recovered.pinboard.get_uuid())?;
+    tokio::fs::create_dir_all(dir()?).await?;
+    tokio::fs::write(&path, serde_json::to_string(&recovered)?).await?;
+    Ok(())
+}
+
+pub fn remove(uuid: &Uuid) -> Result<()> {
+    let path = file(uuid)?;
+    if path.try_exists()? {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+// Read all recovery files left behind, this should only be called on startup
+pub fn list() -> Result<Vec<Recovered>> {
+    let dir = dir()?;
+    if !dir.try_exists()? {
+        return Ok(Vec::new());
+    }
+    let mut res = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Ok(serde_json::from_str::<Recovered>(s.as_str())?))
+        {
+            Ok(recovered) => res.push(recovered),
+            Err(e) => warn!("cannot read recovery file {:?}: {}", path, e),
+        }
+    }
+    Ok(res)
+}
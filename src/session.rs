@@ -0,0 +1,464 @@
+// This module contains exporting and importing all open pinboards together with their blobs, so
+// that a whole working session can be moved to another machine. A single pinboard can be bundled
+// the same way to be shared.
+
+use crate::{config, pinboard::Pinboard};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::{info, warn};
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+};
+use uuid::Uuid;
+
+// Content of a blob file, base64 encoded
+#[derive(Serialize, Deserialize)]
+struct BundledBlob {
+    path: PathBuf,
+    content: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SessionBoard {
+    path: Option<PathBuf>,
+    pinboard: Pinboard,
+}
+
+// The archive is a gzip compressed JSON document
+#[derive(Serialize, Deserialize)]
+struct Session {
+    boards: Vec<SessionBoard>,
+    blobs: Vec<BundledBlob>,
+}
+
+// A single pinboard whose blob paths are relative to the directory it's extracted into
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    pinboard: Pinboard,
+    blobs: Vec<BundledBlob>,
+}
+
+// Every blob of nodes and edges backed by a file
+fn blob_paths(pinboard: &Pinboard) -> Vec<PathBuf> {
+    let nodes = pinboard
+        .graph
+        .nodes_iter()
+        .filter_map(|(_, n)| n.payload().blob.as_ref());
+    let edges = pinboard
+        .graph
+        .edges_iter()
+        .filter_map(|(_, e)| e.payload().comment.as_ref());
+    nodes
+        .chain(edges)
+        .filter(|b| b.is_local())
+        .map(|b| b.path().clone())
+        .collect()
+}
+
+// Relative paths blobs are stored under in an archive, their original paths without the root and
+// parent components below the blobs directory. Paths which would collide that way, or with a file
+// where another needs a directory, are put into numbered directories instead.
+fn bundled_paths(paths: impl IntoIterator<Item = PathBuf>) -> HashMap<PathBuf, PathBuf> {
+    let mut paths: Vec<PathBuf> = paths.into_iter().collect();
+    paths.sort();
+    paths.dedup();
+    let mut taken: Vec<PathBuf> = Vec::new();
+    let mut res = HashMap::new();
+    for path in paths {
+        let stripped: PathBuf = path
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+        let mut bundled = Path::new("blobs").join(&stripped);
+        let mut i = 2;
+        while taken
+            .iter()
+            .any(|t| t.starts_with(&bundled) || bundled.starts_with(t))
+        {
+            bundled = Path::new("blobs").join(i.to_string()).join(&stripped);
+            i += 1;
+        }
+        taken.push(bundled.clone());
+        res.insert(path, bundled);
+    }
+    res
+}
+
+// Point the blobs of the pinboard backed by files somewhere else
+fn rewrite_paths(pinboard: &mut Pinboard, f: impl Fn(&Path) -> PathBuf) {
+    pinboard.for_each_blob(|blob| {
+        if blob.is_local() {
+            blob.set_path(f(blob.path()));
+        }
+    });
+}
+
+// Read the blobs of the pinboards once each, skipping those which cannot be read
+async fn read_blobs<'a>(boards: impl Iterator<Item = &'a Pinboard>) -> Vec<BundledBlob> {
+    let mut blobs = HashMap::new();
+    for pinboard in boards {
+        for blob in blob_paths(pinboard) {
+            if blobs.contains_key(&blob) {
+                continue;
+            }
+            match tokio::fs::read(&blob).await {
+                Ok(content) => {
+                    blobs.insert(blob, STANDARD.encode(content));
+                }
+                Err(e) => warn!("cannot bundle blob {:?}: {}", blob, e),
+            }
+        }
+    }
+    blobs
+        .into_iter()
+        .map(|(path, content)| BundledBlob { path, content })
+        .collect()
+}
+
+async fn write_blobs(target: &Path, blobs: Vec<BundledBlob>) -> Result<()> {
+    // Paths come from the archive, which must not place files outside the target
+    if let Some(blob) = blobs.iter().find(|b| {
+        b.path.as_os_str().is_empty()
+            || !b
+                .path
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+    }) {
+        return Err(anyhow!("refusing to extract blob to {:?}", blob.path));
+    }
+    for blob in blobs {
+        let restored = target.join(&blob.path);
+        if let Some(parent) = restored.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&restored, STANDARD.decode(blob.content)?).await?;
+    }
+    Ok(())
+}
+
+fn compress(content: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    Ok(encoder.finish()?)
+}
+
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut content)?;
+    Ok(content)
+}
+
+pub async fn export_dialog(boards: Vec<(Option<PathBuf>, Pinboard)>) -> Result<()> {
+    let Some(path) = FileDialog::new()
+        // https://github.com/PolyMeilex/rfd/issues/235
+        .set_directory(config::dialog_dir()?)
+        .add_filter("Pinboard session", &["pinsession"])
+        .save_file()
+    else {
+        info!("user canceled exporting session");
+        return Ok(());
+    };
+    export(boards, path).await
+}
+
+pub async fn import_dialog(taken: HashSet<Uuid>) -> Result<Vec<PathBuf>> {
+    let Some(path) = FileDialog::new()
+        .set_directory(config::dialog_dir()?)
+        .add_filter("Pinboard session", &["pinsession"])
+        .pick_file()
+    else {
+        info!("user canceled importing session");
+        return Ok(Vec::new());
+    };
+    let Some(target) = FileDialog::new()
+        .set_title("Restore session into")
+        .pick_folder()
+    else {
+        info!("user canceled importing session");
+        return Ok(Vec::new());
+    };
+    import(path, target, taken).await
+}
+
+pub async fn export(mut boards: Vec<(Option<PathBuf>, Pinboard)>, path: PathBuf) -> Result<()> {
+    // Nested pinboards which are part of the session refer to them by their canonical paths, so
+    // that they can be pointed to the restored pinboard files
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    for (board_path, _) in &mut boards {
+        *board_path = board_path.as_deref().map(canonical);
+    }
+    let board_paths: HashSet<PathBuf> = boards.iter().filter_map(|(p, _)| p.clone()).collect();
+    for (_, pinboard) in &mut boards {
+        rewrite_paths(pinboard, |p| {
+            Some(canonical(p))
+                .filter(|c| board_paths.contains(c))
+                .unwrap_or_else(|| p.to_path_buf())
+        });
+    }
+    let blobs = read_blobs(boards.iter().map(|(_, p)| p))
+        .await
+        .into_iter()
+        .filter(|b| !board_paths.contains(&b.path))
+        .collect();
+    let session = Session {
+        boards: boards
+            .into_iter()
+            .map(|(path, pinboard)| SessionBoard { path, pinboard })
+            .collect(),
+        blobs,
+    };
+    tokio::fs::write(path, compress(&serde_json::to_vec(&session)?)?).await?;
+    Ok(())
+}
+
+// Restore the session into the target directory, rewriting blob paths to the restored files.
+// Pinboards whose UUID is taken already get a fresh one. Nothing is written if any file would be
+// overwritten. Returns the paths of the restored pinboard files.
+pub async fn import(path: PathBuf, target: PathBuf, taken: HashSet<Uuid>) -> Result<Vec<PathBuf>> {
+    let session: Session = serde_json::from_slice(&decompress(&tokio::fs::read(&path).await?)?)?;
+
+    let mut uuids = taken;
+    let mut names = HashSet::new();
+    // Original paths of the pinboards mapped to their restored files
+    let mut restored_boards = HashMap::new();
+    let mut boards = Vec::new();
+    for SessionBoard { path, mut pinboard } in session.boards {
+        // Blobs are restored below the target rather than the base
+        pinboard.path_base = None;
+        if !uuids.insert(*pinboard.get_uuid()) {
+            pinboard.renew_uuid();
+            uuids.insert(*pinboard.get_uuid());
+        }
+        let name = path
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map(PathBuf::from)
+            .filter(|name| !names.contains(name))
+            .unwrap_or(PathBuf::from(format!("{}.pinbrd", pinboard.get_uuid())));
+        names.insert(name.clone());
+        let file = target.join(name);
+        if let Some(path) = path {
+            restored_boards.insert(path, file.clone());
+        }
+        boards.push((file, pinboard));
+    }
+
+    // Sessions store the original paths of blobs
+    let bundled = bundled_paths(session.blobs.iter().map(|b| b.path.clone()));
+    let restored = |p: &Path| {
+        restored_boards
+            .get(p)
+            .cloned()
+            .or_else(|| bundled.get(p).map(|b| target.join(b)))
+            // Blobs which couldn't be read when exporting keep pointing to where they were
+            .unwrap_or_else(|| p.to_path_buf())
+    };
+    for (_, pinboard) in &mut boards {
+        rewrite_paths(pinboard, restored);
+    }
+
+    for file in boards
+        .iter()
+        .map(|(file, _)| file.clone())
+        .chain(bundled.values().map(|b| target.join(b)))
+    {
+        if file.try_exists()? {
+            return Err(anyhow!("{:?} already exists", file));
+        }
+    }
+    let blobs = session
+        .blobs
+        .into_iter()
+        .map(|b| BundledBlob {
+            path: bundled[&b.path].clone(),
+            content: b.content,
+        })
+        .collect();
+    write_blobs(&target, blobs).await?;
+
+    let mut res = Vec::new();
+    for (file, pinboard) in boards {
+        tokio::fs::write(&file, pinboard.to_bytes()?).await?;
+        res.push(file);
+    }
+    Ok(res)
+}
+
+pub async fn export_bundle_dialog(pinboard: Pinboard) -> Result<()> {
+    let Some(path) = FileDialog::new()
+        // https://github.com/PolyMeilex/rfd/issues/235
+        .set_directory(config::dialog_dir()?)
+        .add_filter("Pinboard bundle", &["pinbrdz"])
+        .save_file()
+    else {
+        info!("user didn't select path to export bundle");
+        return Ok(());
+    };
+    export_bundle(pinboard, path).await
+}
+
+// Write the pinboard together with copies of its blobs, which are referred to by paths relative
+// to the directory the bundle is extracted into
+pub async fn export_bundle(mut pinboard: Pinboard, path: PathBuf) -> Result<()> {
+    let blobs = read_blobs(std::iter::once(&pinboard)).await;
+    let bundled = bundled_paths(blobs.iter().map(|b| b.path.clone()));
+    let blobs = blobs
+        .into_iter()
+        .map(|b| BundledBlob {
+            path: bundled[&b.path].clone(),
+            content: b.content,
+        })
+        .collect();
+    // Blob paths of bundles are relative to where they're extracted rather than the base
+    pinboard.path_base = None;
+    // Blobs which couldn't be read keep pointing to where they were
+    rewrite_paths(&mut pinboard, |p| {
+        bundled.get(p).cloned().unwrap_or_else(|| p.to_path_buf())
+    });
+    let bundle = Bundle { pinboard, blobs };
+    tokio::fs::write(path, compress(&serde_json::to_vec(&bundle)?)?).await?;
+    Ok(())
+}
+
+// Extract the bundle into a directory named after it next to it, returning the path of the
+// extracted pinboard file. Bundles extracted before are reused.
+pub async fn extract_bundle(path: &Path) -> Result<PathBuf> {
+    let target = path.with_extension("");
+    let file = target.join(path.with_extension("pinbrd").file_name().unwrap());
+    // Reuse the extraction unless the bundle has been replaced since
+    if file.try_exists()?
+        && tokio::fs::metadata(path).await?.modified()?
+            <= tokio::fs::metadata(&file).await?.modified()?
+    {
+        warn!("{:?} has been extracted before, opening {:?}", path, file);
+        return Ok(file);
+    }
+    let bundle: Bundle = serde_json::from_slice(&decompress(&tokio::fs::read(path).await?)?)?;
+    write_blobs(&target, bundle.blobs).await?;
+    let mut pinboard = bundle.pinboard;
+    // Blob paths of the bundle are already relative to the extracted pinboard file
+    pinboard.relative_paths = true;
+    tokio::fs::write(&file, pinboard.to_bytes()?).await?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Blob, BlobType, NodeData, PinboardGraph};
+    use petgraph::stable_graph::StableGraph;
+
+    #[tokio::test]
+    async fn roundtrip() {
+        let dir = std::env::temp_dir().join(format!("pinlab-session-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        graph.add_node(NodeData {
+            blob: Some(
+                Blob::new(BlobType::File, PathBuf::from("./tests/misc/foo.txt"))
+                    .await
+                    .unwrap(),
+            ),
+            ..Default::default()
+        });
+        let pinboard = Pinboard::new("session".into(), graph);
+        let archive = dir.join("test.pinsession");
+        export(vec![(None, pinboard.clone())], archive.clone())
+            .await
+            .unwrap();
+
+        let target = dir.join("restored");
+        std::fs::create_dir_all(&target).unwrap();
+        let files = import(archive.clone(), target.clone(), HashSet::new())
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        let restored = Pinboard::from_bytes(&std::fs::read(&files[0]).unwrap()).unwrap();
+        let blob_path = restored
+            .graph
+            .nodes_iter()
+            .find_map(|(_, n)| n.payload().blob.as_ref().map(|b| b.path().clone()))
+            .unwrap();
+        assert!(blob_path.starts_with(target.join("blobs")));
+        assert_eq!(
+            std::fs::read(blob_path).unwrap(),
+            std::fs::read("./tests/misc/foo.txt").unwrap()
+        );
+        // Importing again would overwrite the restored files
+        let taken = HashSet::from([*pinboard.get_uuid()]);
+        assert!(import(archive, target.clone(), taken).await.is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn colliding_blobs() {
+        let bundled = bundled_paths(
+            ["/a/b", "a/b", "../a/b", "/a", "/c/d"]
+                .into_iter()
+                .map(PathBuf::from),
+        );
+        let mut paths: Vec<_> = bundled.values().collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), 5);
+        // No path is placed inside another one
+        for a in &paths {
+            assert!(paths.iter().all(|b| a == b || !b.starts_with(a)));
+        }
+        assert_eq!(bundled[Path::new("/c/d")], Path::new("blobs/c/d"));
+    }
+
+    #[tokio::test]
+    async fn bundle() {
+        let dir = std::env::temp_dir().join(format!("pinlab-bundle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut graph = PinboardGraph::from(&StableGraph::default());
+        graph.add_node(NodeData {
+            blob: Some(
+                Blob::new(BlobType::File, PathBuf::from("./tests/misc/foo.txt"))
+                    .await
+                    .unwrap(),
+            ),
+            ..Default::default()
+        });
+        let archive = dir.join("shared.pinbrdz");
+        export_bundle(Pinboard::new("bundle".into(), graph), archive.clone())
+            .await
+            .unwrap();
+
+        let file = extract_bundle(&archive).await.unwrap();
+        assert_eq!(file, dir.join("shared").join("shared.pinbrd"));
+        let mut extracted = Pinboard::from_bytes(&std::fs::read(&file).unwrap()).unwrap();
+        extracted.resolve_paths(&file).unwrap();
+        let blob_path = extracted
+            .graph
+            .nodes_iter()
+            .find_map(|(_, n)| n.payload().blob.as_ref().map(|b| b.path().clone()))
+            .unwrap();
+        assert_eq!(
+            std::fs::read(blob_path).unwrap(),
+            std::fs::read("./tests/misc/foo.txt").unwrap()
+        );
+        // Extracting again reuses the extracted pinboard
+        assert_eq!(extract_bundle(&archive).await.unwrap(), file);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn escaping_blobs() {
+        let dir = std::env::temp_dir().join(format!("pinlab-escape-{}", uuid::Uuid::new_v4()));
+        for path in ["../escaped", "/tmp/escaped", "a/../../escaped"] {
+            let blobs = vec![BundledBlob {
+                path: PathBuf::from(path),
+                content: STANDARD.encode("foo"),
+            }];
+            assert!(write_blobs(&dir, blobs).await.is_err());
+        }
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,203 @@
+// This module contains the settings window, editing a copy of the config which replaces the
+// config of the app once saved.
+
+use crate::{
+    config::{self, Config, Theme},
+    handle_promise,
+};
+use egui::{Context, Ui};
+use poll_promise::Promise;
+use rfd::FileDialog;
+use std::path::PathBuf;
+
+// Interval the autosave checkbox starts with
+const DEFAULT_AUTOSAVE_SECS: u64 = 60;
+
+// Setting the picked directory goes to
+#[derive(Clone, Copy)]
+enum DirTarget {
+    Dialog,
+    RepairRoot,
+}
+
+pub enum Action {
+    None,
+    Close,
+    Save(Config),
+}
+
+pub struct Settings {
+    draft: Config,
+    // Extensions of files opened in neovim as typed, separated by commas
+    nvim_ext: String,
+    dir_promise: Option<Promise<Option<(DirTarget, PathBuf)>>>,
+    // Why the config file couldn't be loaded, saving replaces it only once the user agreed to
+    load_error: Option<String>,
+    replace: bool,
+}
+
+impl Settings {
+    pub fn new(config: &Config, load_error: Option<String>) -> Self {
+        Self {
+            draft: config.clone(),
+            nvim_ext: config.nvim_ext.join(", "),
+            dir_promise: None,
+            load_error,
+            replace: false,
+        }
+    }
+
+    fn pick_dir(&mut self, target: DirTarget) {
+        self.dir_promise = Some(Promise::spawn_async(async move {
+            FileDialog::new()
+                // https://github.com/PolyMeilex/rfd/issues/235
+                .set_directory(config::dialog_dir().ok()?)
+                .pick_folder()
+                .map(|dir| (target, dir))
+        }));
+    }
+
+    fn show_neovim(&mut self, ui: &mut Ui) {
+        ui.label("Neovim server");
+        let mut srv = self.draft.nvim_srv.clone().unwrap_or_default();
+        if ui
+            .text_edit_singleline(&mut srv)
+            .on_hover_text("Files are opened by default apps if empty")
+            .changed()
+        {
+            self.draft.nvim_srv = Some(srv).filter(|s| !s.trim().is_empty());
+        }
+        ui.end_row();
+        ui.label("Neovim extensions");
+        ui.text_edit_singleline(&mut self.nvim_ext)
+            .on_hover_text("Types of files to open in neovim, separated by commas");
+        ui.end_row();
+    }
+
+    fn show_dirs(&mut self, ui: &mut Ui) {
+        ui.label("Dialog directory");
+        ui.horizontal(|ui| {
+            match &self.draft.dialog_dir {
+                Some(dir) => ui.label(dir.display().to_string()),
+                None => ui.weak("Working directory"),
+            };
+            if ui.button("Choose...").clicked() {
+                self.pick_dir(DirTarget::Dialog);
+            }
+            if self.draft.dialog_dir.is_some() && ui.button("Reset").clicked() {
+                self.draft.dialog_dir = None;
+            }
+        });
+        ui.end_row();
+        ui.label("Repair roots")
+            .on_hover_text("Directories searched for missing files of every pinboard");
+        ui.vertical(|ui| {
+            let mut removed = None;
+            for (i, root) in self.draft.repair_roots.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(root.display().to_string());
+                    if ui.small_button("×").on_hover_text("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                self.draft.repair_roots.remove(i);
+            }
+            if ui.button("Add...").clicked() {
+                self.pick_dir(DirTarget::RepairRoot);
+            }
+        });
+        ui.end_row();
+    }
+
+    pub fn show(&mut self, ctx: &Context) -> Action {
+        if let Some(Some((target, dir))) = handle_promise(&mut self.dir_promise, |r| r.clone()) {
+            match target {
+                DirTarget::Dialog => self.draft.dialog_dir = Some(dir),
+                DirTarget::RepairRoot if !self.draft.repair_roots.contains(&dir) => {
+                    self.draft.repair_roots.push(dir)
+                }
+                DirTarget::RepairRoot => {}
+            }
+        }
+
+        let mut open = true;
+        let mut action = Action::None;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings").num_columns(2).show(ui, |ui| {
+                    ui.label("Theme");
+                    ui.horizontal(|ui| {
+                        for theme in Theme::ALL {
+                            ui.radio_value(&mut self.draft.theme, theme, theme.label());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Autosave");
+                    ui.horizontal(|ui| {
+                        let mut enabled = self.draft.autosave_secs > 0;
+                        if ui
+                            .checkbox(&mut enabled, "every")
+                            .on_hover_text("Save pinboards with unsaved changes to their files")
+                            .changed()
+                        {
+                            self.draft.autosave_secs =
+                                if enabled { DEFAULT_AUTOSAVE_SECS } else { 0 };
+                        }
+                        ui.add_enabled(
+                            enabled,
+                            egui::DragValue::new(&mut self.draft.autosave_secs)
+                                .range(1..=3600)
+                                .suffix(" s"),
+                        );
+                    });
+                    ui.end_row();
+
+                    self.show_neovim(ui);
+                    self.show_dirs(ui);
+                });
+                ui.separator();
+                if let Some(path) = config::path() {
+                    ui.weak(format!(
+                        "Saved to {}, where keyboard shortcuts are set as well. Command line \
+                         arguments take precedence until saving here.",
+                        path.display()
+                    ));
+                }
+                if let Some(e) = &self.load_error {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("The config file couldn't be loaded: {}", e),
+                    );
+                    ui.checkbox(&mut self.replace, "Replace it with these settings");
+                }
+                ui.horizontal(|ui| {
+                    let can_save = self.load_error.is_none() || self.replace;
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save"))
+                        .clicked()
+                    {
+                        let mut config = self.draft.clone();
+                        config.nvim_ext = self
+                            .nvim_ext
+                            .split(',')
+                            .map(|e| e.trim().trim_start_matches('.').to_string())
+                            .filter(|e| !e.is_empty())
+                            .collect();
+                        action = Action::Save(config);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Action::Close;
+                    }
+                });
+            });
+        if !open {
+            action = Action::Close;
+        }
+        action
+    }
+}
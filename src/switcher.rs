@@ -0,0 +1,203 @@
+// This module contains the quick switcher, a palette fuzzy matching open and recent pinboards
+// as well as nodes and edges of the open ones.
+
+use egui::{Context, Id, Key, Modal, Modifiers};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+// Number of matches shown at most
+const RESULT_LIMIT: usize = 50;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Target {
+    // Pinboard already loaded, which is brought to front
+    Open(Uuid),
+    // Recent pinboard to be opened from disk
+    Recent(PathBuf),
+    // Node or edge of a loaded pinboard, which is brought to front and centered on it
+    Node(Uuid, NodeIndex),
+    Edge(Uuid, EdgeIndex),
+}
+
+pub struct Entry {
+    pub title: String,
+    pub path: Option<PathBuf>,
+    // Title of the pinboard containing a node or edge
+    pub board: Option<String>,
+    pub target: Target,
+}
+
+impl Entry {
+    fn haystack(&self) -> String {
+        let mut res = self.title.clone();
+        if let Some(path) = &self.path {
+            res.push_str(&format!(" {}", path.display()));
+        }
+        if let Some(board) = &self.board {
+            res.push_str(&format!(" {}", board));
+        }
+        res
+    }
+
+    fn text(&self) -> String {
+        let mut res = self.title.clone();
+        if let Some(board) = &self.board {
+            res.push_str(&format!(" in {}", board));
+        }
+        if let Some(path) = &self.path {
+            res.push_str(&format!(" ({})", path.display()));
+        }
+        res
+    }
+
+    fn is_element(&self) -> bool {
+        matches!(self.target, Target::Node(..) | Target::Edge(..))
+    }
+}
+
+pub enum Action {
+    None,
+    Close,
+    Pick(Target),
+}
+
+/// Score of matching the query as a subsequence of the text case insensitively, `None` if it
+/// doesn't match. Consecutive characters and characters at the start of words score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = pos + text[pos..].iter().position(|c| *c == q)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == i) {
+            score += 5;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(i);
+        pos = i + 1;
+    }
+    Some(score)
+}
+
+#[derive(Default)]
+pub struct Switcher {
+    query: String,
+    selected: usize,
+}
+
+impl Switcher {
+    // Entries matching the query, best match first. Entries keep their order on ties so that open
+    // boards come before recent ones, and both before nodes and edges. Nodes and edges are only
+    // listed once something is typed.
+    fn matching<'a>(&self, entries: &'a [Entry]) -> Vec<&'a Entry> {
+        let mut res: Vec<_> = entries
+            .iter()
+            .filter(|e| !self.query.trim().is_empty() || !e.is_element())
+            .filter_map(|e| fuzzy_score(&self.query, &e.haystack()).map(|s| (s, e)))
+            .collect();
+        res.sort_by_key(|(s, _)| -s);
+        res.into_iter().take(RESULT_LIMIT).map(|(_, e)| e).collect()
+    }
+
+    pub fn show(&mut self, ctx: &Context, entries: &[Entry]) -> Action {
+        let matching = self.matching(entries);
+        let (up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(matching.len().saturating_sub(1));
+
+        let mut action = if enter {
+            matching
+                .get(self.selected)
+                .map_or(Action::None, |e| Action::Pick(e.target.clone()))
+        } else {
+            Action::None
+        };
+        let modal = Modal::new(Id::new("quick_switcher")).show(ctx, |ui| {
+            ui.set_width(400.0);
+            let query = ui.text_edit_singleline(&mut self.query);
+            query.request_focus();
+            if query.changed() {
+                self.selected = 0;
+            }
+            ui.separator();
+            if matching.is_empty() {
+                ui.weak("No matching pinboards or nodes");
+            }
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    for (i, e) in matching.iter().enumerate() {
+                        let label = ui.selectable_label(i == self.selected, e.text());
+                        if i == self.selected && (up || down) {
+                            label.scroll_to_me(None);
+                        }
+                        if label.clicked() {
+                            action = Action::Pick(e.target.clone());
+                        }
+                    }
+                });
+        });
+        if modal.should_close() && matches!(action, Action::None) {
+            action = Action::Close;
+        }
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching() {
+        assert!(fuzzy_score("pb", "Pinboard").is_some());
+        assert!(fuzzy_score("bp", "Pinboard").is_none());
+        assert_eq!(fuzzy_score("", "Pinboard"), Some(0));
+        // Consecutive characters win over scattered ones
+        assert!(fuzzy_score("pin", "Pinboard") > fuzzy_score("pin", "Paper index notes"));
+        // Word starts win over characters in the middle of words
+        assert!(fuzzy_score("r", "Reading list") > fuzzy_score("r", "Pinboard"));
+    }
+
+    #[test]
+    fn nodes_need_a_query() {
+        let uuid = Uuid::new_v4();
+        let entries = [
+            Entry {
+                title: "Reading".to_string(),
+                path: None,
+                board: None,
+                target: Target::Open(uuid),
+            },
+            Entry {
+                title: "Lemma".to_string(),
+                path: Some(PathBuf::from("notes/lemma.md")),
+                board: Some("Reading".to_string()),
+                target: Target::Node(uuid, NodeIndex::new(0)),
+            },
+        ];
+        let mut switcher = Switcher::default();
+        assert_eq!(switcher.matching(&entries).len(), 1);
+        // Nodes match their file names and pinboards as well
+        switcher.query = "lemma.md".to_string();
+        assert_eq!(switcher.matching(&entries)[0].target, entries[1].target);
+        switcher.query = "read".to_string();
+        assert_eq!(switcher.matching(&entries).len(), 2);
+    }
+}